@@ -0,0 +1,354 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fs::OpenOptions,
+    io::{self, Write},
+    process::Stdio,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use regex::Regex;
+use tokio::{
+    process::Command,
+    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+};
+
+use crate::alert::webhook::{Retry, Webhooks};
+use crate::common::{
+    constants::ALERT_HISTORY,
+    fields,
+    structs::{AlertRule, Log},
+};
+
+// Оповещения по строкам хвоста: правила из Memory проверяются на каждой строке,
+// прошедшей фильтры, до того как она уйдёт в TUI или в stdout
+pub mod webhook;
+
+// Срабатывание правила
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fired {
+    pub time: DateTime<Utc>,
+    pub rule: String,
+    pub source: String,
+    // Совпадений в окне на момент срабатывания
+    pub count: usize,
+    pub line: String,
+}
+
+impl Fired {
+    // Строка alerts.log: время и поля срабатывания, затем сама строка лога
+    pub fn to_line(&self) -> String {
+        format!(
+            "{} {} {} count={} {}",
+            self.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            fields::pair("rule", &self.rule),
+            fields::pair("source", &self.source),
+            self.count,
+            self.line
+        )
+    }
+}
+
+// Состояние оповещений для TUI: последние срабатывания, подсвеченные строки, звонки
+#[derive(Default)]
+pub struct AlertStatus {
+    fired: Mutex<VecDeque<Fired>>,
+    highlighted: Mutex<VecDeque<String>>,
+    // Сколько раз правила просили звонок; TUI звонит, когда число растёт
+    pub bells: AtomicU64,
+    error: Mutex<Option<String>>,
+}
+
+impl AlertStatus {
+    fn push(&self, fired: &Fired, rule: &AlertRule) {
+        let mut history = self.fired.lock().unwrap();
+        if history.len() == ALERT_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(fired.clone());
+        if rule.highlight {
+            let mut highlighted = self.highlighted.lock().unwrap();
+            if highlighted.len() == ALERT_HISTORY {
+                highlighted.pop_front();
+            }
+            highlighted.push_back(fired.line.clone());
+        }
+        if rule.bell {
+            self.bells.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Последнее срабатывание правила с этим именем
+    pub fn last(&self, rule: &str) -> Option<Fired> {
+        let history = self.fired.lock().unwrap();
+        history.iter().rev().find(|f| f.rule == rule).cloned()
+    }
+
+    pub fn latest(&self) -> Option<Fired> {
+        self.fired.lock().unwrap().back().cloned()
+    }
+
+    pub fn highlighted(&self) -> HashSet<String> {
+        self.highlighted.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    fn set_error(&self, error: String) {
+        *self.error.lock().unwrap() = Some(error);
+    }
+}
+
+struct Watch {
+    rule: AlertRule,
+    regex: Regex,
+    hits: VecDeque<DateTime<Utc>>,
+    last_fired: Option<DateTime<Utc>>,
+}
+
+pub struct Evaluator {
+    watches: Vec<Watch>,
+}
+
+impl Evaluator {
+    // Правила с пустым или неверным шаблоном пропускаются, ошибка видна в статусе
+    pub fn new(rules: &[AlertRule], status: &AlertStatus) -> Evaluator {
+        let mut watches = Vec::new();
+        for rule in rules.iter().filter(|r| !r.pattern.is_empty()) {
+            match Regex::new(&rule.pattern) {
+                Ok(regex) => watches.push(Watch {
+                    rule: rule.clone(),
+                    regex,
+                    hits: VecDeque::new(),
+                    last_fired: None,
+                }),
+                Err(e) => status.set_error(format!("rule {}: {}", rule.name, e)),
+            }
+        }
+        Evaluator { watches }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+
+    // Правила, сработавшие на этой строке. После срабатывания окно начинается заново,
+    // а до конца cooldown правило молчит
+    pub fn check(&mut self, log: &Log, now: DateTime<Utc>) -> Vec<(&AlertRule, Fired)> {
+        let mut fired = Vec::new();
+        for watch in &mut self.watches {
+            if watch
+                .rule
+                .source
+                .as_ref()
+                .is_some_and(|s| *s != log.source_name)
+                || !watch.regex.is_match(&log.data)
+            {
+                continue;
+            }
+            watch.hits.push_back(now);
+            // Окно, которое тянется дальше начала времён, ничего не отбрасывает
+            let window_start =
+                delta(watch.rule.window_secs).and_then(|window| now.checked_sub_signed(window));
+            while watch
+                .hits
+                .front()
+                .is_some_and(|&t| window_start.is_some_and(|start| t <= start))
+            {
+                watch.hits.pop_front();
+            }
+            // Слишком длинный cooldown не кончается никогда
+            let cooled = watch.last_fired.is_none_or(|last| {
+                delta(watch.rule.cooldown_secs).is_some_and(|cooldown| now - last >= cooldown)
+            });
+            if watch.hits.len() < watch.rule.count || !cooled {
+                continue;
+            }
+            fired.push((
+                &watch.rule,
+                Fired {
+                    time: now,
+                    rule: watch.rule.name.clone(),
+                    source: log.source_name.clone(),
+                    count: watch.hits.len(),
+                    line: log.data.clone(),
+                },
+            ));
+            watch.hits.clear();
+            watch.last_fired = Some(now);
+        }
+        fired
+    }
+}
+
+// Секунды правила из памяти: там они не урезаны разбором и могут не влезть в TimeDelta
+fn delta(secs: u64) -> Option<TimeDelta> {
+    i64::try_from(secs).ok().and_then(TimeDelta::try_seconds)
+}
+
+// Действия правила; звонок и подсветку делает TUI по статусу
+fn act(rule: &AlertRule, fired: &Fired, status: &AlertStatus, webhooks: &Webhooks) {
+    status.push(fired, rule);
+    if let Some(path) = &rule.file
+        && let Err(e) = append(path, &fired.to_line())
+    {
+        status.set_error(format!("rule {}: {}: {}", rule.name, path, e));
+    }
+    if let Some(command) = &rule.run {
+        // Команда узнаёт о срабатывании из переменных окружения; ждём её в стороне, чтобы не оставить зомби
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("LOGSCRAPER_ALERT_RULE", &fired.rule)
+            .env("LOGSCRAPER_ALERT_SOURCE", &fired.source)
+            .env("LOGSCRAPER_ALERT_COUNT", fired.count.to_string())
+            .env("LOGSCRAPER_ALERT_LINE", &fired.line)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match child {
+            Ok(mut child) => {
+                tokio::spawn(async move {
+                    let _ = child.wait().await;
+                });
+            }
+            Err(e) => status.set_error(format!("rule {}: {}", rule.name, e)),
+        }
+    }
+    if let Some(url) = &rule.webhook {
+        webhooks.send(url, fired, status);
+    }
+}
+
+fn append(path: &str, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+// Звено хвоста между источниками и получателем: проверяет правила и передаёт строку дальше
+pub async fn watch(
+    rules: Vec<AlertRule>,
+    status: Arc<AlertStatus>,
+    mut rx: UnboundedReceiver<Log>,
+    tx: UnboundedSender<Log>,
+) {
+    let mut evaluator = Evaluator::new(&rules, &status);
+    let webhooks = Webhooks::start(status.clone(), Retry::default());
+    while let Some(log) = rx.recv().await {
+        if !evaluator.is_empty() {
+            for (rule, fired) in evaluator.check(&log, Utc::now()) {
+                act(rule, &fired, &status, &webhooks);
+            }
+        }
+        if tx.send(log).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use tempdir::TempDir;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+
+    fn log(source: &str, data: &str) -> Log {
+        Log {
+            date_time: Utc::now(),
+            data: data.to_string(),
+            source_name: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_window_and_cooldown() {
+        let status = AlertStatus::default();
+        let rules = vec![
+            AlertRule::parse(
+                "name=oom match=OutOfMemory source=api count=3 window=60s cooldown=5m",
+            ),
+            AlertRule::parse("match=(unclosed"),
+        ];
+        let mut evaluator = Evaluator::new(&rules, &status);
+        assert!(status.error().unwrap().contains("(unclosed"));
+
+        let start = DateTime::from_timestamp(1_792_238_400, 0).unwrap();
+        let at = |seconds: i64| start + TimeDelta::seconds(seconds);
+        let oom = log("api", "java.lang.OutOfMemoryError");
+        // Чужой источник и старые совпадения за окном не считаются
+        assert!(evaluator.check(&log("web", &oom.data), at(0)).is_empty());
+        assert!(evaluator.check(&oom, at(0)).is_empty());
+        assert!(evaluator.check(&oom, at(61)).is_empty());
+        assert!(evaluator.check(&oom, at(62)).is_empty());
+        let fired = evaluator.check(&oom, at(63));
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].1.rule.as_str(), fired[0].1.count), ("oom", 3));
+
+        // В cooldown молчит, после — снова нужно три совпадения
+        for seconds in 64..67 {
+            assert!(evaluator.check(&oom, at(seconds)).is_empty());
+        }
+        for seconds in 363..365 {
+            assert!(evaluator.check(&oom, at(seconds)).is_empty());
+        }
+        assert_eq!(evaluator.check(&oom, at(365)).len(), 1);
+
+        // Окно и cooldown, не влезающие в TimeDelta, не роняют проверку
+        let mut huge = AlertRule::parse("match=OutOfMemory count=2");
+        huge.window_secs = u64::MAX;
+        huge.cooldown_secs = i64::MAX as u64;
+        let mut evaluator = Evaluator::new(&[huge], &status);
+        assert!(evaluator.check(&oom, at(0)).is_empty());
+        assert_eq!(evaluator.check(&oom, at(1_000_000)).len(), 1);
+        assert!(evaluator.check(&oom, at(2_000_000)).is_empty());
+        assert!(evaluator.check(&oom, at(3_000_000)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watch_actions() {
+        let tmp_dir = TempDir::new("alert").expect("Не получилось создать временную директорию");
+        let alerts = tmp_dir.path().join("alerts.log");
+        let marker = tmp_dir.path().join("ran");
+        let rule = AlertRule::parse(&format!(
+            "name=disk match=\"disk full\" bell highlight file={} run=\"echo $LOGSCRAPER_ALERT_SOURCE > {}\"",
+            alerts.display(),
+            marker.display()
+        ));
+        let status = Arc::new(AlertStatus::default());
+        let (line_tx, line_rx) = unbounded_channel();
+        let (tx, mut rx) = unbounded_channel();
+        tokio::spawn(watch(vec![rule], status.clone(), line_rx, tx));
+
+        line_tx.send(log("db", "all good")).unwrap();
+        line_tx.send(log("db", "disk full on /var")).unwrap();
+        // Строки идут дальше все, с оповещением или без
+        assert_eq!(rx.recv().await.unwrap().data, "all good");
+        assert_eq!(rx.recv().await.unwrap().data, "disk full on /var");
+
+        assert_eq!(status.bells.load(Ordering::Relaxed), 1);
+        assert!(status.highlighted().contains("disk full on /var"));
+        let fired = status.last("disk").unwrap();
+        assert_eq!(fired.source, "db");
+        assert!(
+            fs::read_to_string(&alerts)
+                .unwrap()
+                .ends_with(" rule=disk source=db count=1 disk full on /var\n")
+        );
+        for _ in 0..50 {
+            if fs::read_to_string(&marker).is_ok_and(|s| s == "db\n") {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Команда правила не запустилась");
+    }
+}
@@ -0,0 +1,250 @@
+use std::{io, sync::Arc, time::Duration};
+
+use http_body_util::Full;
+use hyper::{Request, Uri, body::Bytes, header};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{Client, connect::HttpConnector},
+    rt::TokioExecutor,
+};
+use tokio::sync::mpsc::{self, Receiver, Sender, error::TrySendError};
+
+use crate::alert::{AlertStatus, Fired};
+use crate::common::constants::{
+    WEBHOOK_ATTEMPTS, WEBHOOK_BACKOFF_MAX, WEBHOOK_BACKOFF_MIN, WEBHOOK_QUEUE, WEBHOOK_TIMEOUT,
+};
+
+// Срабатывания уходят POST-запросом с JSON. Очередь ограничена и заполняется без ожидания:
+// медленный получатель теряет оповещения, но не задерживает хвост
+
+pub struct Retry {
+    pub attempts: u32,
+    pub backoff_min: Duration,
+    pub backoff_max: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            attempts: WEBHOOK_ATTEMPTS,
+            backoff_min: WEBHOOK_BACKOFF_MIN,
+            backoff_max: WEBHOOK_BACKOFF_MAX,
+            timeout: WEBHOOK_TIMEOUT,
+        }
+    }
+}
+
+struct Delivery {
+    url: String,
+    body: String,
+}
+
+pub struct Webhooks {
+    tx: Sender<Delivery>,
+}
+
+impl Webhooks {
+    // Отправитель живёт, пока жив Webhooks; очередь дорабатывает и после
+    pub fn start(status: Arc<AlertStatus>, retry: Retry) -> Webhooks {
+        let (tx, rx) = mpsc::channel(WEBHOOK_QUEUE);
+        tokio::spawn(deliver(rx, status, retry));
+        Webhooks { tx }
+    }
+
+    pub fn send(&self, url: &str, fired: &Fired, status: &AlertStatus) {
+        let body = serde_json::json!({
+            "rule": fired.rule,
+            "source": fired.source,
+            "count": fired.count,
+            "time": fired.time.to_rfc3339(),
+            "line": fired.line,
+        })
+        .to_string();
+        let delivery = Delivery {
+            url: url.to_string(),
+            body,
+        };
+        if let Err(TrySendError::Full(_)) = self.tx.try_send(delivery) {
+            status.set_error(format!("webhook queue full, alert {} dropped", fired.rule));
+        }
+    }
+}
+
+async fn deliver(mut rx: Receiver<Delivery>, status: Arc<AlertStatus>, retry: Retry) {
+    let client = client();
+    while let Some(delivery) = rx.recv().await {
+        if let Err(e) = post_with_retry(&client, &delivery, &retry).await {
+            status.set_error(format!("webhook {}: {}", delivery.url, e));
+        }
+    }
+}
+
+// Повторяем при сетевой ошибке, 429 и 5xx с нарастающей паузой; на прочие коды не повторяем
+async fn post_with_retry(
+    client: &HttpClient,
+    delivery: &Delivery,
+    retry: &Retry,
+) -> io::Result<()> {
+    let mut backoff = retry.backoff_min;
+    let mut attempt = 1;
+    loop {
+        let result = tokio::time::timeout(retry.timeout, post(client, &delivery.url, &delivery.body))
+            .await
+            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()));
+        let error = match result {
+            Ok(code) if (200..300).contains(&code) => return Ok(()),
+            Ok(code) if code == 429 || code >= 500 => io::Error::other(format!("HTTP {}", code)),
+            Ok(code) => return Err(io::Error::other(format!("HTTP {}", code))),
+            Err(e) if e.kind() == io::ErrorKind::InvalidInput => return Err(e),
+            Err(e) => e,
+        };
+        if attempt >= retry.attempts {
+            return Err(io::Error::other(format!(
+                "gave up after {} attempts: {}",
+                attempt, error
+            )));
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(retry.backoff_max);
+        attempt += 1;
+    }
+}
+
+type HttpClient = Client<HttpsConnector<HttpConnector>, Full<Bytes>>;
+
+// Клиент с пулом соединений; https проверяется по корневым сертификатам webpki-roots
+fn client() -> HttpClient {
+    let https = HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Client::builder(TokioExecutor::new()).build(https)
+}
+
+// Тело ответа не нужно, только код
+async fn post(client: &HttpClient, url: &str, body: &str) -> io::Result<u16> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only http:// and https:// URLs are supported",
+        ));
+    }
+    let request = Request::post(uri)
+        .header(header::USER_AGENT, "logscraper")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let response = client.request(request).await.map_err(io::Error::other)?;
+    Ok(response.status().as_u16())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use axum::{Router, extract::State, http::StatusCode, routing::post as route_post};
+    use chrono::Utc;
+    use tokio::{net::TcpListener, sync::mpsc::unbounded_channel};
+
+    use super::*;
+    use crate::alert::watch;
+    use crate::common::structs::{AlertRule, Log};
+
+    // Получатель-заглушка: первый запрос отвечает 503, следующие принимаются
+    #[derive(Default)]
+    struct Receiver {
+        calls: Mutex<Vec<String>>,
+    }
+
+    async fn hook(State(receiver): State<Arc<Receiver>>, body: String) -> StatusCode {
+        let mut calls = receiver.calls.lock().unwrap();
+        calls.push(body);
+        match calls.len() {
+            1 => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::NO_CONTENT,
+        }
+    }
+
+    async fn stand_in() -> (String, Arc<Receiver>) {
+        let receiver = Arc::new(Receiver::default());
+        let app = Router::new()
+            .route("/hook", route_post(hook))
+            .with_state(receiver.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (format!("http://{}/hook", addr), receiver)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retry() {
+        let (url, receiver) = stand_in().await;
+        let rule = AlertRule::parse(&format!("name=oom match=OutOfMemory webhook={}", url));
+        let status = Arc::new(AlertStatus::default());
+        let (line_tx, line_rx) = unbounded_channel();
+        let (tx, mut rx) = unbounded_channel();
+        tokio::spawn(watch(vec![rule], status.clone(), line_rx, tx));
+
+        line_tx
+            .send(Log {
+                date_time: Utc::now(),
+                data: "java.lang.OutOfMemoryError".to_string(),
+                source_name: "api".to_string(),
+            })
+            .unwrap();
+        // Строка уходит дальше сразу, не дожидаясь доставки
+        assert_eq!(rx.recv().await.unwrap().source_name, "api");
+
+        for _ in 0..100 {
+            if receiver.calls.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let calls = receiver.calls.lock().unwrap().clone();
+        assert_eq!(calls.len(), 2, "Ожидался повтор после 503");
+        assert_eq!(calls[0], calls[1]);
+        let body: serde_json::Value = serde_json::from_str(&calls[1]).unwrap();
+        assert_eq!(body["rule"], "oom");
+        assert_eq!(body["source"], "api");
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["line"], "java.lang.OutOfMemoryError");
+        assert_eq!(status.error(), None);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_gives_up() {
+        let retry = Retry {
+            attempts: 2,
+            backoff_min: Duration::from_millis(10),
+            backoff_max: Duration::from_millis(10),
+            timeout: Duration::from_secs(1),
+        };
+        // Порт закрыт: две попытки, затем отказ — и по http, и по https
+        let client = client();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        for scheme in ["http", "https"] {
+            let delivery = Delivery {
+                url: format!("{}://{}/hook", scheme, addr),
+                body: "{}".to_string(),
+            };
+            let error = post_with_retry(&client, &delivery, &retry).await.unwrap_err();
+            assert!(error.to_string().starts_with("gave up after 2 attempts"));
+        }
+
+        // Прочие схемы не пробуем вовсе
+        let ftp = Delivery {
+            url: "ftp://example.com/hook".to_string(),
+            body: "{}".to_string(),
+        };
+        let error = post_with_retry(&client, &ftp, &retry).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+
+use crate::common::enums::{Filter, Order};
+use crate::common::structs::{Bookmark, Path};
+use crate::reader::file::visit_placed;
+use crate::redact::Redactor;
+
+// Закладки ссылаются на место строки в источнике, а не на номер на странице:
+// страница и порядок меняются, а путь, смещение и время строки — нет
+
+// Итог фонового поиска: закладка и номер её строки или причина, почему строки нет
+pub type Jump = (Bookmark, Result<usize, String>);
+
+// Номер строки закладки в выдаче Page Mode с этими фильтрами и порядком.
+// Выдача — устойчивая сортировка строк в порядке сканирования, поэтому номер — это число
+// строк, которые идут раньше, плюс одинаковые с ней, отсканированные до неё (после — для
+// обратного порядка). Один проход по источникам без сбора строк; вызывать из отдельного потока
+pub fn position(
+    paths: &[Path],
+    filters: &Option<Vec<Filter>>,
+    order: &Order,
+    bookmark: &Bookmark,
+) -> Result<usize, String> {
+    let (Some(path), Some(offset)) = (&bookmark.path, bookmark.offset) else {
+        return Err("the line is not in a file".to_string());
+    };
+    let Some(target) = paths.iter().position(|p| &p.path == path) else {
+        return Err(format!("{} is not a source of this profile", path));
+    };
+    let reverse = *order == Order::OrderByDateReverse;
+    let mut before = 0;
+    let mut found = false;
+    visit_placed(paths, filters, |source, place, line| {
+        let earlier = match line.cmp(&bookmark.line) {
+            Ordering::Less => !reverse,
+            Ordering::Greater => reverse,
+            Ordering::Equal if source == target && place == Some(offset) => {
+                found = true;
+                false
+            }
+            Ordering::Equal => ((source, place.unwrap_or(0)) < (target, offset)) != reverse,
+        };
+        if earlier {
+            before += 1;
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    if !found {
+        return Err("the line is hidden by the filters or the file changed".to_string());
+    }
+    Ok(before)
+}
+
+// Хронология инцидента: закладки по времени строки, без времени — в конце
+pub fn timeline(bookmarks: &[Bookmark], redactor: Option<&Redactor>) -> String {
+    let mut sorted: Vec<&Bookmark> = bookmarks.iter().collect();
+    sorted.sort_by_key(|b| (b.time.is_none(), b.time));
+    let mut out = String::new();
+    for bookmark in sorted {
+        let time = bookmark.time.map_or("-".to_string(), |t| {
+            t.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+        });
+        let place = match (&bookmark.path, bookmark.offset) {
+            (Some(path), Some(offset)) => format!("{}@{}", path, offset),
+            _ => "-".to_string(),
+        };
+        let line = match redactor {
+            Some(redactor) => redactor.redact(&bookmark.line),
+            None => bookmark.line.clone(),
+        };
+        out.push_str(&format!(
+            "{}  [{}] {}",
+            time, bookmark.mark, bookmark.source
+        ));
+        if !bookmark.note.is_empty() {
+            out.push_str(&format!("  {}", bookmark.note));
+        }
+        out.push_str(&format!("\n    {}  {}\n", place, line));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use chrono::DateTime;
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::common::structs::{PageLine, SearchFilter};
+    use crate::reader::file::read_page;
+
+    fn bookmark(line: &PageLine) -> Bookmark {
+        Bookmark {
+            mark: 'a',
+            path: line.place.as_ref().map(|place| place.path.clone()),
+            source: String::new(),
+            offset: line.place.as_ref().map(|place| place.offset),
+            time: None,
+            note: String::new(),
+            line: line.line.clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_position_matches_page() {
+        let tmp_dir = TempDir::new("bookmark").expect("Не получилось создать временную директорию");
+        let mut paths = Vec::new();
+        for (name, content) in [
+            (
+                "api",
+                "2026-10-17T12:00:01Z dup\r\n2026-10-17T12:00:03Z x\n2026-10-17T12:00:01Z dup\n",
+            ),
+            ("db", "2026-10-17T12:00:01Z dup\n2026-10-17T12:00:02Z y dup\n"),
+        ] {
+            let file_path = tmp_dir.path().join(format!("{}.log", name));
+            fs::write(&file_path, content).unwrap();
+            paths.push(Path::new(
+                file_path.to_str().unwrap().to_string(),
+                name.to_string(),
+            ));
+        }
+        let search = Some(vec![Filter::Search(SearchFilter {
+            substr: "dup".to_string(),
+        })]);
+
+        // Номер каждой строки совпадает с её местом в полной выдаче, в том числе у одинаковых строк
+        for filters in [None, search] {
+            for order in [Order::OrderByDate, Order::OrderByDateReverse] {
+                let page = read_page(paths.clone(), usize::MAX, 0, filters.clone(), order.clone())
+                    .await
+                    .unwrap();
+                for (n, line) in page.iter().enumerate() {
+                    assert_eq!(position(&paths, &filters, &order, &bookmark(line)), Ok(n));
+                }
+            }
+        }
+
+        // Смещение не на начале этой строки — файл изменился
+        let page = read_page(paths.clone(), 1, 0, None, Order::OrderByDate).await.unwrap();
+        let mut moved = bookmark(&page[0]);
+        moved.offset = Some(1);
+        assert!(position(&paths, &None, &Order::OrderByDate, &moved).is_err());
+        moved.path = None;
+        assert!(position(&paths, &None, &Order::OrderByDate, &moved).is_err());
+    }
+
+    #[test]
+    fn test_timeline() {
+        let path = "/var/log/db.log";
+        let bookmarks = vec![
+            Bookmark {
+                mark: 'b',
+                path: Some(path.to_string()),
+                source: "db".to_string(),
+                offset: Some(24),
+                time: DateTime::from_timestamp(1_792_238_405, 0),
+                note: "failover".to_string(),
+                line: "2026-10-17T12:00:05Z failover to 10.0.0.2".to_string(),
+            },
+            Bookmark {
+                mark: 'a',
+                path: None,
+                source: "-".to_string(),
+                offset: None,
+                time: None,
+                note: String::new(),
+                line: "tail line".to_string(),
+            },
+        ];
+        let timeline = timeline(&bookmarks, Some(&Redactor::new(&[])));
+        assert_eq!(
+            timeline,
+            format!(
+                "2026-10-17T12:00:05Z  [b] db  failover\n    {}@24  2026-10-17T12:00:05Z failover to <IP>\n\
+                 -  [a] -\n    -  tail line\n",
+                path
+            )
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::{error::Error, future::Future};
+
+use tokio::net::TcpListener;
+
+use crate::cli::{
+    EXIT_ERROR, EXIT_OK, AgentArgs,
+    follow::shutdown_signal,
+    sources,
+};
+use crate::common::structs::Memory;
+use crate::remote::agent::Agent;
+
+pub async fn run(args: AgentArgs) -> i32 {
+    let result = match (Memory::load(), TcpListener::bind(&args.listen).await) {
+        (Ok(memory), Ok(listener)) => {
+            if let Ok(addr) = listener.local_addr() {
+                eprintln!("logscraper: agent listening on {}", addr);
+            }
+            agent(args, memory, listener, shutdown_signal()).await
+        }
+        (Err(e), _) => Err(e.into()),
+        (_, Err(e)) => Err(e.into()),
+    };
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("logscraper: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+// Пути из аргументов заменяют сохранённые; команды и приёмники активного профиля агент не запускает
+pub async fn agent(
+    args: AgentArgs,
+    mut memory: Memory,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn Error>> {
+    if !args.paths.is_empty() || !args.pods.is_empty() {
+        memory.paths = sources(&args.paths, &args.pods)?;
+    }
+    if memory.paths.is_empty() {
+        return Err("nothing to share: pass --path or --pods, or add paths in the TUI".into());
+    }
+    if args.token.is_empty() {
+        return Err("the agent token must not be empty".into());
+    }
+
+    let agent = Agent::new(memory.paths, args.token);
+    tokio::select! {
+        _ = shutdown => Ok(()),
+        res = agent.serve(listener) => res.map_err(|e| e.into()),
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    error::Error,
+    future::Future,
+    io::{self, IsTerminal},
+    sync::{Arc, atomic::Ordering},
+};
+
+use crate::cli::{
+    EXIT_ERROR, EXIT_OK, ExportArgs,
+    follow::shutdown_signal,
+    query::build_filters,
+    sources,
+};
+use crate::common::{
+    constants::{EXPORT_PROGRESS_INTERVAL, EXPORT_STDOUT},
+    structs::Memory,
+};
+use crate::export::{self, ExportProgress, ExportRequest, Query, Selection};
+use crate::redact::Redactor;
+
+pub async fn run(args: ExportArgs) -> i32 {
+    match export_lines(args, shutdown_signal()).await {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("logscraper: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+// Ctrl-C отменяет выгрузку: недописанный файл удаляется
+pub async fn export_lines(
+    args: ExportArgs,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn Error>> {
+    let filters = build_filters(&args.filters)?;
+    let paths = sources(&args.paths, &args.pods)?;
+    // Правило с неверным шаблоном не должно молча пропустить секрет в файл
+    let redactor = match args.redact {
+        true => {
+            let memory = Memory::load()?;
+            let redactor = Redactor::new(&memory.redaction.rules);
+            if let Some(e) = redactor.error {
+                return Err(format!("redaction {}", e).into());
+            }
+            Some(Arc::new(redactor))
+        }
+        false => None,
+    };
+    let limit = if args.limit == 0 {
+        usize::MAX
+    } else {
+        args.limit
+    };
+    let request = ExportRequest {
+        selection: Selection::Query(Query {
+            paths,
+            agents: Vec::new(),
+            filters,
+            order: args.order.into(),
+            offset: args.offset,
+            limit,
+        }),
+        format: args.format.into(),
+        output: args.output.clone(),
+        redactor,
+    };
+    let progress = Arc::new(ExportProgress::default());
+    progress.start();
+    // Прогресс рисуем, только если stderr — терминал и не смешивается с выгрузкой
+    let show_progress = args.output != EXPORT_STDOUT && io::stderr().is_terminal();
+
+    let export = export::export(request, progress.clone());
+    tokio::pin!(export);
+    tokio::pin!(shutdown);
+    let mut ticker = tokio::time::interval(EXPORT_PROGRESS_INTERVAL);
+    let mut cancelled = false;
+    let result = loop {
+        // Отмену проверяем первой: иначе быстрая выгрузка успевает закончиться раньше
+        tokio::select! {
+            biased;
+            _ = &mut shutdown, if !cancelled => {
+                cancelled = true;
+                progress.cancelled.store(true, Ordering::Relaxed);
+            }
+            res = &mut export => break res,
+            _ = ticker.tick(), if show_progress => {
+                let current = progress.current.lock().unwrap().clone();
+                eprint!("\r\x1b[2K{} {}", progress.text(), current);
+            }
+        }
+    };
+    if show_progress {
+        eprint!("\r\x1b[2K");
+    }
+    match result? {
+        Some(count) if args.output != EXPORT_STDOUT => {
+            eprintln!("logscraper: exported {} lines to {}", count, args.output);
+            Ok(())
+        }
+        Some(_) => Ok(()),
+        None => Err("export cancelled".into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use clap::Parser;
+    use tempdir::TempDir;
+
+    use crate::cli::{Cli, Command};
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> ExportArgs {
+        let cli = Cli::try_parse_from(args).expect("Не удалось разобрать аргументы");
+        match cli.command {
+            Some(Command::Export(args)) => args,
+            _ => panic!("Ожидалась подкоманда export"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_command() {
+        let tmp_dir = TempDir::new("export").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("app.log");
+        fs::write(
+            &log,
+            "2026-10-17T12:00:03Z level=error c\n2026-10-17T12:00:01Z level=error a\n2026-10-17T12:00:02Z level=info b\n",
+        )
+        .unwrap();
+        let output = tmp_dir.path().join("errors.log");
+        let args = parse(&[
+            "logscraper",
+            "export",
+            "--path",
+            log.to_str().unwrap(),
+            "--field",
+            "level=error",
+            "--order",
+            "desc",
+            "--output",
+            output.to_str().unwrap(),
+        ]);
+        export_lines(args, std::future::pending())
+            .await
+            .expect("Выгрузка не выполнилась");
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "2026-10-17T12:00:03Z level=error c\n2026-10-17T12:00:01Z level=error a\n"
+        );
+
+        // Отмена до конца выгрузки: файла нет, команда завершается ошибкой
+        let args = parse(&[
+            "logscraper",
+            "export",
+            "--path",
+            log.to_str().unwrap(),
+            "--format",
+            "csv",
+            "-o",
+            tmp_dir.path().join("all.csv").to_str().unwrap(),
+        ]);
+        assert!(export_lines(args, std::future::ready(())).await.is_err());
+        assert!(!tmp_dir.path().join("all.csv").exists());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    error::Error,
+    future::Future,
+    io::{self, BufWriter, Write},
+    sync::Arc,
+};
+
+use chrono::SecondsFormat;
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::mpsc::unbounded_channel,
+};
+
+use crate::cli::{EXIT_ERROR, EXIT_OK, FollowArgs, query::build_filters, sources};
+use crate::common::structs::{IngestConfig, Log, Memory};
+use crate::alert::AlertStatus;
+use crate::reader::{
+    command::CommandStatus,
+    tail::{ListenerStatus, tail_stream},
+    throughput::Throughput,
+};
+
+pub async fn run(args: FollowArgs) -> i32 {
+    let result = match Memory::load() {
+        Ok(memory) => {
+            let mut out = BufWriter::new(io::stdout());
+            follow(args, memory, &mut out, shutdown_signal()).await
+        }
+        Err(e) => Err(e.into()),
+    };
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("logscraper: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+// Пути из аргументов заменяют сохранённые, фильтры активного профиля применяются всегда
+pub async fn follow(
+    args: FollowArgs,
+    mut memory: Memory,
+    out: &mut impl Write,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn Error>> {
+    if !args.paths.is_empty() || !args.pods.is_empty() {
+        memory.paths = sources(&args.paths, &args.pods)?;
+    }
+    // Адреса syslog из аргументов заменяют настройку активного профиля
+    if args.syslog_udp.is_some() || args.syslog_tcp.is_some() {
+        let mut config = memory.syslog.take().unwrap_or_default();
+        config.udp = args.syslog_udp.clone();
+        config.tcp = args.syslog_tcp.clone();
+        memory.syslog = Some(config);
+    }
+    if let Some(addr) = &args.ingest {
+        let mut config = memory
+            .ingest
+            .take()
+            .unwrap_or_else(|| IngestConfig::new(addr.clone()));
+        config.addr = addr.clone();
+        if let Some(spool) = &args.ingest_spool {
+            config.spool = Some(spool.clone());
+        }
+        memory.ingest = Some(config);
+    }
+    if memory.paths.is_empty()
+        && memory.commands.is_empty()
+        && memory.syslog.is_none()
+        && memory.ingest.is_none()
+        && memory.agents.is_empty()
+    {
+        return Err(
+            "nothing to follow: pass --path, --syslog-udp/--syslog-tcp or --ingest, or add paths in the TUI"
+                .into(),
+        );
+    }
+    memory.filters.extend(build_filters(&args.filters)?);
+
+    let (tx, mut rx) = unbounded_channel::<Log>();
+    let mut tail = tokio::spawn(tail_stream(
+        memory,
+        tx,
+        Arc::new(CommandStatus::default()),
+        Arc::new(Throughput::default()),
+        Arc::new(AlertStatus::default()),
+        Arc::new(ListenerStatus::stderr()),
+    ));
+    tokio::pin!(shutdown);
+
+    let mut finished = false;
+    let result = loop {
+        tokio::select! {
+            _ = &mut shutdown => break Ok(()),
+            res = &mut tail => {
+                finished = true;
+                break match res {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(e)) => Err(e.to_string().into()),
+                    Err(e) => Err(e.into()),
+                };
+            }
+            Some(log) = rx.recv() => {
+                // Сбрасываем после каждой строки: читатель ждёт её сразу, а не по заполнению буфера
+                match write_log(out, &log, &args).and_then(|_| out.flush()) {
+                    Ok(()) => {}
+                    // Читатель закрыл трубу — просто заканчиваем
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => break Ok(()),
+                    Err(e) => break Err(e.into()),
+                }
+            }
+        }
+    };
+    // main сразу зовёт process::exit: ждём отменённую задачу, чтобы её guard'ы успели
+    // остановить группы процессов команд
+    if !finished {
+        tail.abort();
+        let _ = tail.await;
+    }
+    result
+}
+
+fn write_log(out: &mut impl Write, log: &Log, args: &FollowArgs) -> io::Result<()> {
+    if args.timestamp {
+        write!(
+            out,
+            "{} ",
+            log.date_time.to_rfc3339_opts(SecondsFormat::Millis, true)
+        )?;
+    }
+    if args.source {
+        write!(out, "[{}] ", log.source_name)?;
+    }
+    writeln!(out, "{}", log.data)
+}
+
+pub async fn shutdown_signal() {
+    match signal(SignalKind::terminate()) {
+        Ok(mut term) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = term.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use clap::Parser;
+    use tempdir::TempDir;
+    use tokio::sync::oneshot;
+
+    use crate::cli::{Cli, Command};
+    use crate::common::{enums::Filter, structs::SearchFilter};
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> FollowArgs {
+        let cli = Cli::try_parse_from(args).expect("Не удалось разобрать аргументы");
+        match cli.command {
+            Some(Command::Follow(args)) => args,
+            _ => panic!("Ожидалась подкоманда follow"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follow_merges_and_filters() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+        let a = tmp_dir.path().join("a.log");
+        let b = tmp_dir.path().join("b.log");
+        std::fs::write(&a, "old error\n").expect("Не удалось создать временный файл");
+        std::fs::write(&b, "").expect("Не удалось создать временный файл");
+        let a = a.to_str().unwrap().to_string();
+        let b = b.to_str().unwrap().to_string();
+
+        // Фильтр профиля и фильтр из аргументов действуют вместе
+        let memory = Memory {
+            filters: vec![Filter::Search(SearchFilter {
+                substr: "error".to_string(),
+            })],
+            ..Default::default()
+        };
+        let args = parse(&[
+            "logscraper",
+            "follow",
+            "--path",
+            &a,
+            "--path",
+            &b,
+            "--regex",
+            "disk",
+            "--source",
+        ]);
+
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        let writer = {
+            let (a, b) = (a.clone(), b.clone());
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let mut f = std::fs::OpenOptions::new().append(true).open(&a).unwrap();
+                writeln!(f, "disk error").unwrap();
+                writeln!(f, "net error").unwrap();
+                drop(f);
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let mut f = std::fs::OpenOptions::new().append(true).open(&b).unwrap();
+                writeln!(f, "disk error two").unwrap();
+                drop(f);
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                let _ = stop_tx.send(());
+            })
+        };
+
+        let mut out = Vec::new();
+        let shutdown = async {
+            let _ = stop_rx.await;
+        };
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            follow(args, memory, &mut out, shutdown),
+        )
+        .await
+        .expect("follow не остановился")
+        .expect("follow завершился с ошибкой");
+        writer.await.unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("[{}] disk error\n[{}] disk error two\n", a, b)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_follow_errors() {
+        let memory = Memory::default();
+        let args = parse(&["logscraper", "follow"]);
+        assert!(
+            follow(
+                args,
+                memory.clone(),
+                &mut Vec::new(),
+                std::future::pending()
+            )
+            .await
+            .is_err()
+        );
+
+        let args = parse(&["logscraper", "follow", "--path", "missing.log"]);
+        assert!(
+            follow(args, memory, &mut Vec::new(), std::future::pending())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use std::io;
+
+use clap::{Args, Parser, Subcommand, ValueEnum, builder::RangedU64ValueParser};
+
+use crate::common::{
+    constants::{
+        AGENT_ADDR, AGENT_TOKEN_ENV, DEFAULT_DATE_FORMAT, DEFAULT_PROFILE, PROFILE_ENV, SERVE_ADDR,
+        SERVE_HISTORY, SERVE_HISTORY_MAX,
+    },
+    enums::{ExportFormat, Order},
+    structs::Path,
+};
+use crate::reader::container;
+
+pub mod agent;
+pub mod export;
+pub mod follow;
+pub mod query;
+pub mod serve;
+
+// Коды выхода подкоманд, как у grep: 0 — успех, 1 — query ничего не нашёл, 2 — ошибка
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_NO_MATCH: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
+
+// Источники из --path и --pods: файл называется своим путём, контейнер — "namespace/pod/container"
+pub fn sources(paths: &[String], pods: &[String]) -> io::Result<Vec<Path>> {
+    let mut sources: Vec<Path> = paths
+        .iter()
+        .map(|p| Path::new(p.clone(), p.clone()))
+        .collect();
+    for root in pods {
+        sources.extend(container::discover_pods(root)?);
+    }
+    Ok(sources)
+}
+
+// Без подкоманды запускается TUI
+#[derive(Parser)]
+#[command(
+    name = "logscraper",
+    version,
+    about = "Log viewer: TUI by default, headless subcommands for scripts"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[arg(
+        long = "agent",
+        help = "Also show logs of a remote agent, [name=]host:port; may be repeated. There is no TLS: the token and logs travel in plain text"
+    )]
+    pub agents: Vec<String>,
+    #[arg(
+        long,
+        env = AGENT_TOKEN_ENV,
+        hide_env_values = true,
+        help = "Token for the --agent connections"
+    )]
+    pub token: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        env = PROFILE_ENV,
+        default_value = DEFAULT_PROFILE,
+        help = "Configuration profile: paths, filters and view settings under the XDG config directory"
+    )]
+    pub profile: String,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    // Отфильтрованная страница логов в stdout
+    #[command(
+        about = "Print filtered lines to stdout; exit status 0 if anything matched, 1 if nothing, 2 on error"
+    )]
+    Query(QueryArgs),
+    // Хвост файлов без TUI: для tmux, systemd и пайпов
+    #[command(
+        about = "Follow files and print new lines to stdout until SIGINT/SIGTERM or a closed pipe"
+    )]
+    Follow(FollowArgs),
+    // Хвост для нескольких зрителей сразу: SSE и WebSocket
+    #[command(
+        about = "Serve the live tail over Server-Sent Events (/sse) and WebSocket (/ws)"
+    )]
+    Serve(ServeArgs),
+    // Отдаёт свои файлы TUI на другой машине
+    #[command(about = "Share log files with remote TUI clients (logscraper --agent), without TLS")]
+    Agent(AgentArgs),
+    // Та же выгрузка, что по клавише x в TUI
+    #[command(about = "Export filtered lines to a file as text, NDJSON or CSV")]
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+pub struct QueryArgs {
+    #[arg(
+        long = "path",
+        required_unless_present = "pods",
+        help = "Log file to read, may be repeated"
+    )]
+    pub paths: Vec<String>,
+    #[arg(
+        long = "pods",
+        help = "Kubernetes pod log directory such as /var/log/pods, may be repeated"
+    )]
+    pub pods: Vec<String>,
+    #[command(flatten)]
+    pub filters: FilterArgs,
+    #[arg(long, value_enum, default_value_t = OrderArg::Asc)]
+    pub order: OrderArg,
+    #[arg(
+        long,
+        default_value_t = 100,
+        help = "Maximum number of lines, 0 for no limit"
+    )]
+    pub limit: usize,
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[arg(
+        long = "path",
+        required_unless_present = "pods",
+        help = "Log file to read, may be repeated"
+    )]
+    pub paths: Vec<String>,
+    #[arg(
+        long = "pods",
+        help = "Kubernetes pod log directory such as /var/log/pods, may be repeated"
+    )]
+    pub pods: Vec<String>,
+    #[command(flatten)]
+    pub filters: FilterArgs,
+    #[arg(long, value_enum, default_value_t = OrderArg::Asc)]
+    pub order: OrderArg,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Maximum number of lines, 0 for all matching lines"
+    )]
+    pub limit: usize,
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+    #[arg(long, value_enum, default_value_t = ExportFormatArg::Text)]
+    pub format: ExportFormatArg,
+    #[arg(
+        short,
+        long,
+        default_value = "-",
+        help = "File to write, - for stdout"
+    )]
+    pub output: String,
+    #[arg(
+        long,
+        help = "Mask secrets and personal data: built-in detectors and the redaction rules of the profile"
+    )]
+    pub redact: bool,
+}
+
+#[derive(Args)]
+pub struct FollowArgs {
+    #[arg(
+        long = "path",
+        help = "Log file to follow, may be repeated; defaults to the paths saved in the active profile (--profile)"
+    )]
+    pub paths: Vec<String>,
+    #[arg(
+        long = "pods",
+        help = "Kubernetes pod log directory such as /var/log/pods, may be repeated"
+    )]
+    pub pods: Vec<String>,
+    #[command(flatten)]
+    pub filters: FilterArgs,
+    #[arg(long, help = "Prefix each line with its source name")]
+    pub source: bool,
+    #[arg(long, help = "Prefix each line with the time it was read")]
+    pub timestamp: bool,
+    #[arg(
+        long,
+        help = "Receive syslog over UDP on this address, e.g. 0.0.0.0:5514"
+    )]
+    pub syslog_udp: Option<String>,
+    #[arg(
+        long,
+        help = "Receive syslog over TCP on this address, e.g. 0.0.0.0:5514"
+    )]
+    pub syslog_tcp: Option<String>,
+    #[arg(
+        long,
+        help = "Accept logs over HTTP on this address: POST /ingest/<source>"
+    )]
+    pub ingest: Option<String>,
+    #[arg(
+        long,
+        requires = "ingest",
+        help = "Write received lines to this spool file [default: logscraper/ingest.log in the cache dir]"
+    )]
+    pub ingest_spool: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    #[arg(
+        long = "path",
+        help = "Log file to follow, may be repeated; defaults to the paths saved in the active profile (--profile)"
+    )]
+    pub paths: Vec<String>,
+    #[arg(
+        long = "pods",
+        help = "Kubernetes pod log directory such as /var/log/pods, may be repeated"
+    )]
+    pub pods: Vec<String>,
+    #[command(flatten)]
+    pub filters: FilterArgs,
+    #[arg(long, default_value = SERVE_ADDR, help = "Address to listen on")]
+    pub listen: String,
+    #[arg(
+        long,
+        default_value_t = SERVE_HISTORY,
+        value_parser = RangedU64ValueParser::<usize>::new().range(0..=SERVE_HISTORY_MAX as u64),
+        help = "Number of recent lines replayed to a client on connect"
+    )]
+    pub history: usize,
+}
+
+#[derive(Args)]
+pub struct AgentArgs {
+    #[arg(
+        long = "path",
+        help = "Log file to share, may be repeated; defaults to the paths saved in the active profile (--profile)"
+    )]
+    pub paths: Vec<String>,
+    #[arg(
+        long = "pods",
+        help = "Kubernetes pod log directory such as /var/log/pods, may be repeated"
+    )]
+    pub pods: Vec<String>,
+    #[arg(long, default_value = AGENT_ADDR, help = "Address to listen on")]
+    pub listen: String,
+    #[arg(
+        long,
+        env = AGENT_TOKEN_ENV,
+        hide_env_values = true,
+        help = "Token clients must present"
+    )]
+    pub token: String,
+}
+
+// Фильтры, общие для всех подкоманд
+#[derive(Args)]
+pub struct FilterArgs {
+    #[arg(
+        long = "search",
+        help = "Keep lines containing the substring, may be repeated"
+    )]
+    pub searches: Vec<String>,
+    #[arg(
+        long = "regex",
+        help = "Keep lines matching the regex, may be repeated"
+    )]
+    pub regexes: Vec<String>,
+    #[arg(
+        long = "field",
+        value_name = "NAME=VALUE",
+        help = "Keep lines with the key=value field (just NAME: the field is present), may be repeated"
+    )]
+    pub fields: Vec<String>,
+    #[arg(long, help = "Keep lines dated at or after this RFC3339 time")]
+    pub after: Option<String>,
+    #[arg(long, help = "Keep lines dated at or before this RFC3339 time")]
+    pub before: Option<String>,
+    #[arg(long, default_value = DEFAULT_DATE_FORMAT, help = "chrono format of the date at the start of a line")]
+    pub date_format: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OrderArg {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormatArg {
+    Text,
+    Ndjson,
+    Csv,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Text => ExportFormat::Text,
+            ExportFormatArg::Ndjson => ExportFormat::Ndjson,
+            ExportFormatArg::Csv => ExportFormat::Csv,
+        }
+    }
+}
+
+impl From<OrderArg> for Order {
+    fn from(order: OrderArg) -> Self {
+        match order {
+            OrderArg::Asc => Order::OrderByDate,
+            OrderArg::Desc => Order::OrderByDateReverse,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    error::Error,
+    io::{self, BufWriter, Write},
+};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use crate::cli::{EXIT_ERROR, EXIT_NO_MATCH, EXIT_OK, FilterArgs, QueryArgs, sources};
+use crate::common::{
+    enums::Filter,
+    structs::{DateFilter, DateFilterType, FieldFilter, RegexFilter, SearchFilter},
+};
+use crate::reader::file::read_from_paths;
+
+// Код выхода как у grep: 0 — что-то нашлось, 1 — ничего, 2 — ошибка
+pub async fn run(args: QueryArgs) -> i32 {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match query(args, &mut out).await {
+        Ok(true) => EXIT_OK,
+        Ok(false) => EXIT_NO_MATCH,
+        Err(e) => {
+            eprintln!("logscraper: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+pub async fn query(args: QueryArgs, out: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let filters = build_filters(&args.filters)?;
+    let paths = sources(&args.paths, &args.pods)?;
+    let limit = if args.limit == 0 {
+        usize::MAX
+    } else {
+        args.limit
+    };
+
+    let lines =
+        read_from_paths(paths, limit, args.offset, Some(filters), args.order.into()).await?;
+
+    for line in &lines {
+        if let Err(e) = writeln!(out, "{}", line) {
+            // Читатель закрыл трубу (head, less) — это не ошибка
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return Ok(true);
+            }
+            return Err(e.into());
+        }
+    }
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+        _ => {}
+    }
+    Ok(!lines.is_empty())
+}
+
+pub fn build_filters(args: &FilterArgs) -> Result<Vec<Filter>, Box<dyn Error>> {
+    let mut filters = Vec::new();
+    for substr in &args.searches {
+        filters.push(Filter::Search(SearchFilter {
+            substr: substr.clone(),
+        }));
+    }
+    for pattern in &args.regexes {
+        // Ошибку в регулярке показываем сразу, а не молча отбрасываем все строки
+        Regex::new(pattern)?;
+        filters.push(Filter::Regex(RegexFilter {
+            pattern: pattern.clone(),
+        }));
+    }
+    for field in &args.fields {
+        filters.push(Filter::Field(FieldFilter::parse(field)));
+    }
+
+    let date_start = args.after.as_deref().map(parse_time).transpose()?;
+    let date_finish = args.before.as_deref().map(parse_time).transpose()?;
+    let filter_type = match (date_start, date_finish) {
+        (Some(_), Some(_)) => Some(DateFilterType::Between),
+        (Some(_), None) => Some(DateFilterType::After),
+        (None, Some(_)) => Some(DateFilterType::Before),
+        (None, None) => None,
+    };
+    if let Some(filter_type) = filter_type {
+        filters.push(Filter::Date(DateFilter {
+            date_format: args.date_format.clone(),
+            date_start,
+            date_finish,
+            filter_type,
+        }));
+    }
+    Ok(filters)
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, Box<dyn Error>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| format!("invalid time {:?}: {}", value, e).into())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use clap::Parser;
+    use tempdir::TempDir;
+
+    use crate::cli::{Cli, Command};
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> QueryArgs {
+        let cli = Cli::try_parse_from(args).expect("Не удалось разобрать аргументы");
+        match cli.command {
+            Some(Command::Query(args)) => args,
+            _ => panic!("Ожидалась подкоманда query"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_filters_and_orders() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+        let mut paths = Vec::new();
+        for name in ["a.log", "b.log"] {
+            let file_path = tmp_dir.path().join(name);
+            let mut tmp_file =
+                std::fs::File::create(&file_path).expect("Не удалось создать временный файл");
+            for i in 1..=4 {
+                let level = if i % 2 == 0 { "ERR" } else { "INFO" };
+                writeln!(tmp_file, "2026-10-1{}T00:00:00+00:00 {} {}", i, level, name)
+                    .expect("Не удалось записать строку в файл");
+            }
+            paths.push(file_path.to_str().unwrap().to_string());
+        }
+
+        let args = parse(&[
+            "logscraper",
+            "query",
+            "--path",
+            &paths[0],
+            "--path",
+            &paths[1],
+            "--regex",
+            "ERR",
+            "--after",
+            "2026-10-12T00:00:00Z",
+            "--order",
+            "desc",
+            "--limit",
+            "3",
+        ]);
+        let mut out = Vec::new();
+        assert!(query(args, &mut out).await.expect("Запрос не выполнился"));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "2026-10-14T00:00:00+00:00 ERR b.log\n\
+             2026-10-14T00:00:00+00:00 ERR a.log\n\
+             2026-10-12T00:00:00+00:00 ERR b.log\n"
+        );
+
+        let args = parse(&[
+            "logscraper",
+            "query",
+            "--path",
+            &paths[0],
+            "--search",
+            "WARN",
+        ]);
+        let mut out = Vec::new();
+        assert!(!query(args, &mut out).await.expect("Запрос не выполнился"));
+        assert!(out.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_errors() {
+        let args = parse(&["logscraper", "query", "--path", "x.log", "--regex", "("]);
+        assert!(query(args, &mut Vec::new()).await.is_err());
+        let args = parse(&[
+            "logscraper",
+            "query",
+            "--path",
+            "x.log",
+            "--after",
+            "yesterday",
+        ]);
+        assert!(query(args, &mut Vec::new()).await.is_err());
+        assert!(
+            Cli::try_parse_from(["logscraper"])
+                .unwrap()
+                .command
+                .is_none()
+        );
+    }
+}
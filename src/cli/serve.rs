@@ -0,0 +1,474 @@
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    error::Error,
+    future::{self, Future},
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Router,
+    extract::{
+        Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event as SseEvent, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use chrono::SecondsFormat;
+use futures_util::stream::{self, StreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::unbounded_channel,
+    },
+};
+
+use crate::cli::{
+    EXIT_ERROR, EXIT_OK, ServeArgs,
+    follow::shutdown_signal,
+    query::build_filters,
+    sources,
+};
+use crate::common::{
+    constants::SERVE_BROADCAST_CAPACITY,
+    enums::Filter,
+    structs::{Log, Memory},
+};
+use crate::alert::AlertStatus;
+use crate::reader::{
+    command::CommandStatus,
+    tail::{ListenerStatus, tail_stream},
+    throughput::Throughput,
+};
+
+// Один процесс хвостит файлы, а зрители подключаются по SSE (/sse) или WebSocket (/ws).
+// Каждая строка уходит клиенту JSON-объектом {"time", "source", "line"}.
+// Параметры подключения: ?filters=<массив фильтров в формате файла профиля>&history=<сколько строк
+// истории повторить>. По WebSocket клиент может прислать новый массив фильтров в любой момент.
+
+pub async fn run(args: ServeArgs) -> i32 {
+    let result = match (Memory::load(), TcpListener::bind(&args.listen).await) {
+        (Ok(memory), Ok(listener)) => {
+            if let Ok(addr) = listener.local_addr() {
+                eprintln!("logscraper: serving on http://{}", addr);
+            }
+            serve(args, memory, listener, shutdown_signal()).await
+        }
+        (Err(e), _) => Err(e.into()),
+        (_, Err(e)) => Err(e.into()),
+    };
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("logscraper: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+pub async fn serve(
+    args: ServeArgs,
+    mut memory: Memory,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Box<dyn Error>> {
+    if !args.paths.is_empty() || !args.pods.is_empty() {
+        memory.paths = sources(&args.paths, &args.pods)?;
+    }
+    if memory.paths.is_empty()
+        && memory.commands.is_empty()
+        && memory.syslog.is_none()
+        && memory.ingest.is_none()
+        && memory.agents.is_empty()
+    {
+        return Err("nothing to serve: pass --path or add paths in the TUI".into());
+    }
+    memory.filters.extend(build_filters(&args.filters)?);
+
+    let hub = Arc::new(Hub::new(args.history));
+    let (tx, mut rx) = unbounded_channel::<Log>();
+    let mut tail = tokio::spawn(tail_stream(
+        memory,
+        tx,
+        Arc::new(CommandStatus::default()),
+        Arc::new(Throughput::default()),
+        Arc::new(AlertStatus::default()),
+        Arc::new(ListenerStatus::stderr()),
+    ));
+    let pump = tokio::spawn({
+        let hub = hub.clone();
+        async move {
+            while let Some(log) = rx.recv().await {
+                hub.push(log);
+            }
+        }
+    });
+    // Плавную остановку axum не используем: SSE-соединения бесконечны, и она ждала бы их вечно
+    let server = axum::serve(listener, router(hub));
+    tokio::pin!(shutdown);
+
+    let result = tokio::select! {
+        _ = &mut shutdown => Ok(()),
+        res = server => res.map_err(|e| e.into()),
+        res = &mut tail => match res {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string().into()),
+            Err(e) => Err(e.into()),
+        },
+    };
+    tail.abort();
+    pump.abort();
+    result
+}
+
+// Строка хвоста в том виде, в каком её получают клиенты
+#[derive(Serialize, Debug)]
+pub struct Event {
+    pub time: String,
+    pub source: String,
+    pub line: String,
+}
+
+// Последние строки для новых клиентов и рассылка новых всем подключённым
+pub struct Hub {
+    history: Mutex<VecDeque<Arc<Event>>>,
+    capacity: usize,
+    tx: broadcast::Sender<Arc<Event>>,
+}
+
+impl Hub {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(SERVE_BROADCAST_CAPACITY);
+        Self {
+            // Буфер растёт по мере прихода строк, а не выделяется под всю историю сразу
+            history: Mutex::new(VecDeque::new()),
+            capacity,
+            tx,
+        }
+    }
+
+    pub fn push(&self, log: Log) {
+        let event = Arc::new(Event {
+            time: log.date_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+            source: log.source_name,
+            line: log.data,
+        });
+        // Рассылка под той же блокировкой, что и история: подписчик не увидит строку дважды
+        // и не пропустит её между историей и живым потоком
+        let mut history = self.history.lock().unwrap();
+        if self.capacity > 0 {
+            if history.len() == self.capacity {
+                history.pop_front();
+            }
+            history.push_back(event.clone());
+        }
+        let _ = self.tx.send(event);
+    }
+
+    // Не больше replay последних строк истории и подписка на новые
+    pub fn subscribe(&self, replay: usize) -> (Vec<Arc<Event>>, broadcast::Receiver<Arc<Event>>) {
+        let history = self.history.lock().unwrap();
+        let skip = history.len().saturating_sub(replay);
+        (
+            history.iter().skip(skip).cloned().collect(),
+            self.tx.subscribe(),
+        )
+    }
+}
+
+fn router(hub: Arc<Hub>) -> Router {
+    Router::new()
+        .route("/sse", get(sse))
+        .route("/ws", get(ws))
+        .with_state(hub)
+}
+
+#[derive(Deserialize)]
+struct ClientParams {
+    filters: Option<String>,
+    history: Option<usize>,
+}
+
+impl ClientParams {
+    fn filters(&self) -> Result<Vec<Filter>, String> {
+        match &self.filters {
+            Some(json) => parse_filters(json),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // Без параметра — вся история, которую помнит сервер
+    fn replay(&self) -> usize {
+        self.history.unwrap_or(usize::MAX)
+    }
+}
+
+fn parse_filters(json: &str) -> Result<Vec<Filter>, String> {
+    let filters: Vec<Filter> =
+        serde_json::from_str(json).map_err(|e| format!("invalid filters: {}", e))?;
+    // Ошибку в регулярке сообщаем клиенту, а не молча отбрасываем все строки
+    for filter in &filters {
+        if let Filter::Regex(f) = filter
+            && let Err(e) = Regex::new(&f.pattern)
+        {
+            return Err(format!("invalid regex: {}", e));
+        }
+    }
+    Ok(filters)
+}
+
+fn matches(filters: &[Filter], event: &Event) -> bool {
+    filters.iter().all(|f| f.is_include(&event.line))
+}
+
+async fn sse(State(hub): State<Arc<Hub>>, Query(params): Query<ClientParams>) -> Response {
+    let filters = match params.filters() {
+        Ok(filters) => filters,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let (history, rx) = hub.subscribe(params.replay());
+    let live = stream::unfold(rx, |mut rx| async move {
+        match rx.recv().await {
+            Ok(event) => Some((Ok(event), rx)),
+            Err(RecvError::Lagged(skipped)) => Some((Err(skipped), rx)),
+            Err(RecvError::Closed) => None,
+        }
+    });
+    let events = stream::iter(history.into_iter().map(Ok))
+        .chain(live)
+        .filter_map(move |item| {
+            let event = match item {
+                Ok(event) if matches(&filters, &event) => {
+                    SseEvent::default().json_data(&*event).ok()
+                }
+                Ok(_) => None,
+                // Клиент не успевал читать — сообщаем, сколько строк он потерял
+                Err(skipped) => Some(
+                    SseEvent::default()
+                        .event("lagged")
+                        .data(json!({ "skipped": skipped }).to_string()),
+                ),
+            };
+            future::ready(event.map(Ok::<_, Infallible>))
+        });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn ws(
+    State(hub): State<Arc<Hub>>,
+    Query(params): Query<ClientParams>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let filters = match params.filters() {
+        Ok(filters) => filters,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let replay = params.replay();
+    upgrade.on_upgrade(move |socket| ws_client(socket, hub, filters, replay))
+}
+
+async fn ws_client(mut socket: WebSocket, hub: Arc<Hub>, mut filters: Vec<Filter>, replay: usize) {
+    let (history, mut rx) = hub.subscribe(replay);
+    for event in history.iter().filter(|e| matches(&filters, e)) {
+        if socket.send(event_message(event)).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        let message = tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) if matches(&filters, &event) => event_message(&event),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => text_message(json!({ "lagged": skipped })),
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Новый массив фильтров заменяет прежний; клиент получает подтверждение
+                Some(Ok(Message::Text(text))) => match parse_filters(&text) {
+                    Ok(new) => {
+                        filters = new;
+                        text_message(json!({ "filters": filters.len() }))
+                    }
+                    Err(e) => text_message(json!({ "error": e })),
+                },
+                Some(Ok(_)) => continue,
+                // Клиент закрыл соединение или оно оборвалось
+                _ => break,
+            },
+        };
+        if socket.send(message).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn event_message(event: &Event) -> Message {
+    text_message(json!(event))
+}
+
+fn text_message(value: serde_json::Value) -> Message {
+    Message::Text(value.to_string().into())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use futures_util::SinkExt;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite};
+
+    use super::*;
+
+    fn log(line: &str) -> Log {
+        Log {
+            date_time: Utc::now(),
+            data: line.to_string(),
+            source_name: "app".to_string(),
+        }
+    }
+
+    async fn start(hub: Arc<Hub>) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router(hub)).await;
+        });
+        addr
+    }
+
+    async fn next(socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) -> serde_json::Value {
+        let message = tokio::time::timeout(Duration::from_secs(2), socket.next())
+            .await
+            .expect("Сообщение не пришло")
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_hub_keeps_last_lines() {
+        let hub = Hub::new(2);
+        for line in ["one", "two", "three"] {
+            hub.push(log(line));
+        }
+        let lines = |replay| -> Vec<String> {
+            hub.subscribe(replay)
+                .0
+                .iter()
+                .map(|e| e.line.clone())
+                .collect()
+        };
+        assert_eq!(lines(usize::MAX), vec!["two", "three"]);
+        assert_eq!(lines(1), vec!["three"]);
+        assert!(lines(0).is_empty());
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        use clap::Parser;
+
+        use crate::cli::Cli;
+        use crate::common::constants::SERVE_HISTORY_MAX;
+
+        let parse = |history: usize| {
+            Cli::try_parse_from(["logscraper", "serve", "--history", &history.to_string()])
+        };
+        assert!(parse(SERVE_HISTORY_MAX).is_ok());
+        assert!(parse(SERVE_HISTORY_MAX + 1).is_err());
+
+        // Память под историю не выделяется заранее
+        let hub = Hub::new(SERVE_HISTORY_MAX);
+        assert_eq!(hub.history.lock().unwrap().capacity(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sse_replays_history_with_client_filters() {
+        let hub = Arc::new(Hub::new(10));
+        hub.push(log("keep old"));
+        hub.push(log("drop old"));
+        let addr = start(hub.clone()).await;
+
+        // [{"type":"Search","substr":"keep"}]
+        let filters = "%5B%7B%22type%22%3A%22Search%22%2C%22substr%22%3A%22keep%22%7D%5D";
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET /sse?filters={} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            filters
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        hub.push(log("drop new"));
+        hub.push(log("keep new"));
+
+        let mut response = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !String::from_utf8_lossy(&response).contains("keep new") {
+            let read = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+                .await
+                .expect("События не пришли")
+                .unwrap();
+            assert!(read > 0, "Сервер закрыл соединение");
+            response.extend_from_slice(&buf[..read]);
+        }
+        let response = String::from_utf8_lossy(&response);
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains(r#""source":"app""#));
+        assert!(response.find("keep old").unwrap() < response.find("keep new").unwrap());
+        assert!(!response.contains("drop"));
+    }
+
+    #[tokio::test]
+    async fn test_sse_rejects_bad_filters() {
+        let addr = start(Arc::new(Hub::new(10))).await;
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /sse?filters=nope HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"));
+    }
+
+    #[tokio::test]
+    async fn test_ws_history_and_filter_change() {
+        let hub = Arc::new(Hub::new(10));
+        hub.push(log("first"));
+        hub.push(log("second"));
+        let addr = start(hub.clone()).await;
+        let (mut socket, _) = connect_async(format!("ws://{}/ws?history=1", addr))
+            .await
+            .unwrap();
+
+        assert_eq!(next(&mut socket).await["line"], "second");
+
+        socket
+            .send(tungstenite::Message::text(
+                r#"[{"type":"Regex","pattern":"^keep"}]"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(next(&mut socket).await["filters"], 1);
+        hub.push(log("drop this"));
+        hub.push(log("keep this"));
+        assert_eq!(next(&mut socket).await["line"], "keep this");
+    }
+}
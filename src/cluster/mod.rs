@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::Ipv6Addr,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
+
+use crate::common::{
+    constants::{CLUSTER_SAMPLES, CLUSTER_SIMILARITY, STATS_PROGRESS_STEP},
+    enums::Filter,
+    fields,
+    structs::{Path, RegexFilter},
+};
+use crate::reader::file::visit_lines;
+
+// Шаблоны сообщений в духе Drain: изменчивые части (числа, IP, UUID, hex) маскируются,
+// строки одной длины с тем же первым словом сливаются в шаблон, где несовпавшие слова — <*>
+
+const WILDCARD: &str = "<*>";
+const PLACEHOLDERS: [&str; 5] = [WILDCARD, "<UUID>", "<IP>", "<HEX>", "<NUM>"];
+
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?P<uuid>\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b)",
+        r"|(?P<ip4>\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b)",
+        r"|(?P<ip6>[0-9a-fA-F]*:[0-9a-fA-F]*:[0-9a-fA-F:]*)",
+        r"|(?P<hex>\b(?:0x[0-9a-fA-F]+|[0-9a-fA-F]{8,})\b)",
+        r"|(?P<num>\b\d+(?:\.\d+)?\b)",
+    ))
+    .unwrap()
+});
+
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d+(?:\.\d+)?\b").unwrap());
+
+// Сообщение без времени в начале, изменчивые части заменены метками
+pub fn mask(line: &str) -> String {
+    VARIABLE
+        .replace_all(fields::message(line), |caps: &Captures| {
+            if caps.name("uuid").is_some() {
+                "<UUID>".to_string()
+            } else if caps.name("ip4").is_some() {
+                "<IP>".to_string()
+            } else if let Some(ip6) = caps.name("ip6") {
+                // Время 12:00:01 тоже похоже на IPv6 — в нём маскируются только числа
+                match ip6.as_str().parse::<Ipv6Addr>() {
+                    Ok(_) => "<IP>".to_string(),
+                    Err(_) => NUMBER.replace_all(ip6.as_str(), "<NUM>").into_owned(),
+                }
+            } else if caps.name("hex").is_some() {
+                "<HEX>".to_string()
+            } else {
+                "<NUM>".to_string()
+            }
+        })
+        .into_owned()
+}
+
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    pub template: Vec<String>,
+    pub count: u64,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    // Первые CLUSTER_SAMPLES строк как есть
+    pub samples: Vec<String>,
+}
+
+impl Cluster {
+    pub fn text(&self) -> String {
+        self.template.join(" ")
+    }
+
+    // Фильтр по шаблону: метки становятся \S+, остальное ищется буквально
+    pub fn filter(&self) -> Filter {
+        let tokens: Vec<String> = self
+            .template
+            .iter()
+            .map(|token| {
+                PLACEHOLDERS
+                    .iter()
+                    .fold(regex::escape(token), |pattern, placeholder| {
+                        pattern.replace(&regex::escape(placeholder), r"\S+")
+                    })
+            })
+            .collect();
+        Filter::Regex(RegexFilter {
+            pattern: tokens.join(r"\s+"),
+        })
+    }
+
+    fn similarity(&self, tokens: &[String]) -> f64 {
+        if tokens.is_empty() {
+            return 1.0;
+        }
+        let same = self
+            .template
+            .iter()
+            .zip(tokens)
+            .filter(|(a, b)| a == b)
+            .count();
+        same as f64 / tokens.len() as f64
+    }
+
+    fn merge(&mut self, tokens: Vec<String>, line: &str, time: Option<DateTime<Utc>>) {
+        for (template, token) in self.template.iter_mut().zip(tokens) {
+            if *template != token {
+                *template = WILDCARD.to_string();
+            }
+        }
+        self.count += 1;
+        self.seen(line, time);
+    }
+
+    fn seen(&mut self, line: &str, time: Option<DateTime<Utc>>) {
+        if let Some(time) = time {
+            self.first = Some(self.first.map_or(time, |first| first.min(time)));
+            self.last = Some(self.last.map_or(time, |last| last.max(time)));
+        }
+        if self.samples.len() < CLUSTER_SAMPLES {
+            self.samples.push(line.to_string());
+        }
+    }
+}
+
+// Дерево Drain глубины два: длина сообщения и первое слово
+#[derive(Default, Debug)]
+pub struct Drain {
+    pub total: u64,
+    clusters: Vec<Cluster>,
+    groups: HashMap<(usize, String), Vec<usize>>,
+}
+
+impl Drain {
+    // received — время получения строки, если в ней самой времени нет (хвост)
+    pub fn add(&mut self, line: &str, received: Option<DateTime<Utc>>) {
+        self.total += 1;
+        let time = fields::timestamp(line).or(received);
+        let tokens: Vec<String> = mask(line).split_whitespace().map(str::to_string).collect();
+        let key = (tokens.len(), tokens.first().cloned().unwrap_or_default());
+        let group = self.groups.entry(key).or_default();
+        let best = group
+            .iter()
+            .map(|&i| (i, self.clusters[i].similarity(&tokens)))
+            .filter(|(_, similarity)| *similarity >= CLUSTER_SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((i, _)) => self.clusters[i].merge(tokens, line, time),
+            None => {
+                group.push(self.clusters.len());
+                let mut cluster = Cluster {
+                    template: tokens,
+                    count: 1,
+                    first: None,
+                    last: None,
+                    samples: Vec::new(),
+                };
+                cluster.seen(line, time);
+                self.clusters.push(cluster);
+            }
+        }
+    }
+
+    // Самые частые шаблоны первыми
+    pub fn sorted(&self) -> Vec<&Cluster> {
+        let mut clusters: Vec<&Cluster> = self.clusters.iter().collect();
+        clusters.sort_by(|a, b| b.count.cmp(&a.count).then(a.first.cmp(&b.first)));
+        clusters
+    }
+
+    // Доля строк, которую покрывают top самых частых шаблонов, в процентах
+    pub fn coverage(&self, top: usize) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let covered: u64 = self.sorted().iter().take(top).map(|c| c.count).sum();
+        covered as f64 * 100.0 / self.total as f64
+    }
+}
+
+// Состояние фонового разбора для экрана шаблонов
+#[derive(Default)]
+pub struct ClusterProgress {
+    pub running: AtomicBool,
+    pub lines: AtomicU64,
+    pub result: Mutex<Option<Drain>>,
+    pub error: Mutex<Option<String>>,
+}
+
+// Блокирующий проход по всем источникам; вызывать из отдельного потока
+pub fn collect(
+    paths: &[Path],
+    filters: &[Filter],
+    progress: &ClusterProgress,
+) -> io::Result<Drain> {
+    progress.lines.store(0, Ordering::Relaxed);
+    let mut drain = Drain::default();
+    let filters = Some(filters.to_vec());
+    visit_lines(paths, &filters, |_, line| {
+        drain.add(&line, None);
+        if drain.total.is_multiple_of(STATS_PROGRESS_STEP) {
+            progress.lines.store(drain.total, Ordering::Relaxed);
+        }
+    })?;
+    progress.lines.store(drain.total, Ordering::Relaxed);
+    Ok(drain)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(
+            mask(
+                "2026-10-17T12:00:01Z user 42 from 10.0.0.7:5432 req=0x1f id=3f2b8c1e-0a4d-4b7e-9c11-5d6e7f8a9b0c"
+            ),
+            "user <NUM> from <IP> req=<HEX> id=<UUID>"
+        );
+        assert_eq!(
+            mask("peer fe80::1 at 12:00:01 sha deadbeef42 http2"),
+            "peer <IP> at <NUM>:<NUM>:<NUM> sha <HEX> http2"
+        );
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut drain = Drain::default();
+        for line in [
+            "2026-10-17T12:00:01Z connected to 10.0.0.1 as alice",
+            "2026-10-17T12:00:03Z connected to 10.0.0.2 as bob",
+            "2026-10-17T12:00:02Z connected to 10.0.0.3 as carol",
+            "2026-10-17T12:00:04Z disk full on /var",
+        ] {
+            drain.add(line, None);
+        }
+        drain.add(
+            "connected to 10.0.0.4 as dave",
+            DateTime::from_timestamp(1_792_238_405, 0),
+        );
+
+        let clusters = drain.sorted();
+        assert_eq!(clusters.len(), 2);
+        let connected = clusters[0];
+        assert_eq!(connected.text(), "connected to <IP> as <*>");
+        assert_eq!(connected.count, 4);
+        assert_eq!(connected.samples.len(), CLUSTER_SAMPLES);
+        assert_eq!(
+            connected.first.unwrap().to_rfc3339(),
+            "2026-10-17T12:00:01+00:00"
+        );
+        assert_eq!(
+            connected.last.unwrap().to_rfc3339(),
+            "2026-10-17T12:00:05+00:00"
+        );
+        assert_eq!(clusters[1].text(), "disk full on /var");
+        assert_eq!(drain.coverage(1), 80.0);
+
+        // Фильтр по шаблону находит строки шаблона и только их
+        let filter = connected.filter();
+        assert!(
+            filter.is_include(&"2026-10-18T00:00:00Z connected to 192.168.1.1 as eve".to_string())
+        );
+        assert!(!filter.is_include(&"2026-10-17T12:00:04Z disk full on /var".to_string()));
+    }
+}
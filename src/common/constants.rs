@@ -1,3 +1,109 @@
+use std::time::Duration;
+
 // pub const PATHS_FILE: &str = "paths.json";
 // pub const FILTERS_FILE: &str = "filters.json";
 pub const MEMORY_FILE: &str = "memory.json";
+// Профиль, если --profile не задан; в него переносится старый ./memory.json
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILE_ENV: &str = "LOGSCRAPER_PROFILE";
+// Каталог профилей в рабочем каталоге, если не найден каталог настроек (см. profile::dir)
+pub const PROFILE_DIR: &str = "profiles";
+// Строк на странице Page Mode в новом профиле
+pub const PAGE_SIZE: usize = 30;
+// Каталог индексов в рабочем каталоге, если не найден каталог кэша (см. index::dir)
+pub const INDEX_DIR: &str = ".logscraper/index";
+// Файлы меньше этого размера быстрее просканировать целиком, чем индексировать
+pub const INDEX_MIN_FILE_SIZE: u64 = 8 * 1024 * 1024;
+// Сколько записей словаря полнотекстовый индекс держит в памяти при сборке до сброса на диск
+pub const TEXT_INDEX_RUN_POSTINGS: usize = 4 * 1024 * 1024;
+// Page Mode считает совпадения фильтров в индексированном файле блоками по столько строк
+pub const PAGE_BLOCK_LINES: usize = 4096;
+// Для скольких наборов фильтров помнить посчитанные блоки
+pub const PAGE_COUNTS_CACHE: usize = 16;
+// Формат даты, которым пользуются датовые фильтры по умолчанию
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
+// Размер куска файла, который сканируется одной задачей в Page-режиме
+pub const SCAN_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+// Путь источника, который читается из stdin
+pub const STDIN_PATH: &str = "-";
+// Файл выгрузки, вместо которого пишется stdout
+pub const EXPORT_STDOUT: &str = "-";
+// Как часто выгрузка обновляет прогресс и проверяет отмену, в строках
+pub const EXPORT_PROGRESS_STEP: usize = 1024;
+// Сколько байт строк выгрузка сортирует в памяти, прежде чем сбросить кусок во временный файл
+pub const EXPORT_RUN_BYTES: usize = 64 * 1024 * 1024;
+// Как часто подсчёт статистики обновляет прогресс, в строках
+pub const STATS_PROGRESS_STEP: u64 = 4096;
+// Как часто `logscraper export` перерисовывает прогресс в терминале
+pub const EXPORT_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+// Сколько байт stdin держать в памяти, прежде чем перенести их во временный файл
+pub const STDIN_SPOOL_MEMORY: usize = 4 * 1024 * 1024;
+// Предел временного файла stdin: дальше строки идут только в Tail-режим
+pub const STDIN_SPOOL_MAX_BYTES: usize = 1024 * 1024 * 1024;
+// Сколько строк stdin может отстать подписчик Tail-режима, прежде чем он их потеряет
+pub const STDIN_BROADCAST_CAPACITY: usize = 4096;
+// Пауза перед перезапуском упавшей команды-источника: растёт вдвое до максимума
+pub const COMMAND_BACKOFF_MIN: Duration = Duration::from_secs(1);
+pub const COMMAND_BACKOFF_MAX: Duration = Duration::from_secs(30);
+// Команда, проработавшая дольше этого, перезапускается снова с минимальной паузой
+pub const COMMAND_STABLE_PERIOD: Duration = Duration::from_secs(10);
+// Файл спула (syslog, HTTP) переименовывается в .1, .2 ... по достижении этого размера
+pub const SPOOL_MAX_BYTES: u64 = 64 * 1024 * 1024;
+// Сколько старых файлов спула хранить
+pub const SPOOL_FILES: usize = 5;
+// Сообщения длиннее обрезаются (UDP) или считаются ошибкой кадра (TCP)
+pub const SYSLOG_MAX_MESSAGE: usize = 64 * 1024;
+// Длина кадра TCP с пробелом после неё, в байтах
+pub const SYSLOG_MAX_LENGTH_PREFIX: usize = 10;
+// Спул HTTP-приёма по умолчанию, рядом с каталогом индексов (см. index::dir)
+pub const INGEST_SPOOL: &str = "ingest.log";
+// Предел тела запроса к /ingest
+pub const INGEST_MAX_BODY: usize = 8 * 1024 * 1024;
+// Сколько принятых по HTTP строк может ждать обработки; дальше клиент получает 429
+pub const INGEST_BUFFER_LINES: usize = 10_000;
+// Сколько принятых syslog-сообщений может ждать обработки; дальше новые отбрасываются
+pub const SYSLOG_BUFFER_LINES: usize = 10_000;
+// Адрес, на котором serve по умолчанию раздаёт хвост
+pub const SERVE_ADDR: &str = "127.0.0.1:8515";
+// Сколько последних строк serve помнит для новых клиентов
+pub const SERVE_HISTORY: usize = 1000;
+// Больше строк истории serve не держит, сколько бы ни попросили в --history
+pub const SERVE_HISTORY_MAX: usize = 100_000;
+// Сколько строк может отстать клиент serve, прежде чем он их потеряет
+pub const SERVE_BROADCAST_CAPACITY: usize = 4096;
+// Адрес, на котором agent по умолчанию принимает TUI-клиентов
+pub const AGENT_ADDR: &str = "127.0.0.1:8516";
+// Переменная окружения с токеном агента, чтобы он не светился в списке процессов
+pub const AGENT_TOKEN_ENV: &str = "LOGSCRAPER_TOKEN";
+// Предел одного кадра протокола агента
+pub const REMOTE_MAX_FRAME: usize = 16 * 1024 * 1024;
+// Предел кадра до проверки токена: в Hello только версия и токен
+pub const REMOTE_HELLO_MAX_FRAME: usize = 4 * 1024;
+// Больше строк агент на один запрос страницы не отдаёт; дальше клиент спрашивает следующие
+pub const REMOTE_PAGE_LINES: usize = 10_000;
+// Сколько агент ждёт приветствия от клиента
+pub const REMOTE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+// Сколько клиент ждёт подключения к агенту и ответа на запрос страницы
+pub const REMOTE_TIMEOUT: Duration = Duration::from_secs(30);
+// Пауза перед повторным подключением хвоста к агенту
+pub const REMOTE_RETRY: Duration = Duration::from_secs(5);
+// Сколько последних секунд показывает график скорости хвоста
+pub const RATE_WINDOW: usize = 60;
+// Сколько строк-примеров хранит шаблон сообщений
+pub const CLUSTER_SAMPLES: usize = 3;
+// Доля совпавших слов, при которой строка попадает в существующий шаблон
+pub const CLUSTER_SIMILARITY: f64 = 0.5;
+// Правило оповещения по умолчанию: одно совпадение за минуту, потом минута тишины
+pub const ALERT_COUNT: usize = 1;
+pub const ALERT_WINDOW_SECS: u64 = 60;
+pub const ALERT_COOLDOWN_SECS: u64 = 60;
+// Окно и cooldown правила длиннее этого урезаются при разборе: 30 дней
+pub const ALERT_MAX_SECS: u64 = 30 * 24 * 3600;
+// Сколько последних срабатываний помнит TUI
+pub const ALERT_HISTORY: usize = 100;
+// Доставка оповещений на webhook: очередь, попытки и пауза между ними (удваивается до максимума)
+pub const WEBHOOK_QUEUE: usize = 256;
+pub const WEBHOOK_ATTEMPTS: u32 = 5;
+pub const WEBHOOK_BACKOFF_MIN: Duration = Duration::from_millis(500);
+pub const WEBHOOK_BACKOFF_MAX: Duration = Duration::from_secs(30);
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
@@ -0,0 +1,49 @@
+// Свёртка подряд идущих одинаковых строк в одну с числом повторов
+
+use chrono::{DateTime, Utc};
+
+use crate::common::{enums::Dedup, fields};
+
+// Повторы одной строки: lines[start..start + count]
+#[derive(Debug, PartialEq)]
+pub struct Run {
+    pub start: usize,
+    pub count: usize,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl Run {
+    pub fn end(&self) -> usize {
+        self.start + self.count
+    }
+}
+
+// Дописывает строки lines[from..] к уже свёрнутым: хвост растёт, заново всё не считается
+pub fn collapse(runs: &mut Vec<Run>, lines: &[String], from: usize, mode: Dedup) {
+    for (i, line) in lines.iter().enumerate().skip(from) {
+        let time = fields::timestamp(line);
+        match runs.last_mut() {
+            Some(run) if run.end() == i && same(&lines[run.start], line, mode) => {
+                run.count += 1;
+                if time.is_some() {
+                    run.last = time;
+                }
+            }
+            _ => runs.push(Run {
+                start: i,
+                count: 1,
+                first: time,
+                last: time,
+            }),
+        }
+    }
+}
+
+fn same(a: &str, b: &str, mode: Dedup) -> bool {
+    match mode {
+        Dedup::Off => false,
+        Dedup::Exact => a == b,
+        Dedup::IgnoreTime => fields::message(a) == fields::message(b),
+    }
+}
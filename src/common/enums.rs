@@ -4,7 +4,10 @@ use chrono::{DateTime, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::common::structs::{DateFilter, RegexFilter, SearchFilter};
+use crate::common::{
+    fields,
+    structs::{DateFilter, FieldFilter, RegexFilter, SearchFilter},
+};
 
 #[derive(Debug)]
 pub enum MemoryError {
@@ -22,19 +25,152 @@ impl fmt::Display for MemoryError {
 
 impl Error for MemoryError {}
 
-#[derive(PartialEq, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum Mode {
     Page,
     Tail,
     Stopped,
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum Order {
     OrderByDate,
     OrderByDateReverse,
 }
 
+// Кодировка файла лога; BOM в начале файла имеет приоритет над настройкой
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8Lossy,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Cp1251,
+}
+
+impl Encoding {
+    // '\n' — отдельный байт, значит файл можно резать и индексировать по байтам
+    pub fn is_ascii_compatible(&self) -> bool {
+        !matches!(self, Encoding::Utf16Le | Encoding::Utf16Be)
+    }
+
+    pub fn next(&self) -> Encoding {
+        match self {
+            Encoding::Utf8Lossy => Encoding::Utf16Le,
+            Encoding::Utf16Le => Encoding::Utf16Be,
+            Encoding::Utf16Be => Encoding::Latin1,
+            Encoding::Latin1 => Encoding::Cp1251,
+            Encoding::Cp1251 => Encoding::Utf8Lossy,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Encoding::Utf8Lossy => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin-1",
+            Encoding::Cp1251 => "cp1251",
+        }
+    }
+}
+
+// Формат строк файла: контейнерные логи разворачиваются в "<время> stream=<поток> <текст>"
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum LogFormat {
+    #[default]
+    Plain,
+    // json-file драйвер Docker: {"log":"...\n","stream":"stderr","time":"..."}
+    DockerJson,
+    // Kubernetes CRI: "<время> <поток> <P|F> <текст>"
+    Cri,
+    // systemd journal: `journalctl -o export` или сам файл .journal
+    Journal,
+}
+
+impl LogFormat {
+    pub fn next(&self) -> LogFormat {
+        match self {
+            LogFormat::Plain => LogFormat::DockerJson,
+            LogFormat::DockerJson => LogFormat::Cri,
+            LogFormat::Cri => LogFormat::Journal,
+            LogFormat::Journal => LogFormat::Plain,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogFormat::Plain => "plain",
+            LogFormat::DockerJson => "docker",
+            LogFormat::Cri => "cri",
+            LogFormat::Journal => "journal",
+        }
+    }
+}
+
+// Формат выгрузки: строки как есть, NDJSON или CSV с разобранными полями
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ExportFormat {
+    #[default]
+    Text,
+    Ndjson,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn next(&self) -> ExportFormat {
+        match self {
+            ExportFormat::Text => ExportFormat::Ndjson,
+            ExportFormat::Ndjson => ExportFormat::Csv,
+            ExportFormat::Csv => ExportFormat::Text,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Text => "text",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Text => "log",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+// Свёртка подряд идущих повторов: выключена, одинаковые строки или одинаковые без времени в начале
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Dedup {
+    #[default]
+    Off,
+    Exact,
+    IgnoreTime,
+}
+
+impl Dedup {
+    pub fn next(&self) -> Dedup {
+        match self {
+            Dedup::Off => Dedup::Exact,
+            Dedup::Exact => Dedup::IgnoreTime,
+            Dedup::IgnoreTime => Dedup::Off,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Dedup::Off => "off",
+            Dedup::Exact => "exact",
+            Dedup::IgnoreTime => "ignore time",
+        }
+    }
+}
+
 // Фильтры в один модуль
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type")]
@@ -42,6 +178,7 @@ pub enum Filter {
     Date(DateFilter),
     Regex(RegexFilter),
     Search(SearchFilter),
+    Field(FieldFilter),
 }
 
 impl Filter {
@@ -49,7 +186,10 @@ impl Filter {
         match self {
             Filter::Date(f) => {
                 let date_str = line.split_whitespace().next().unwrap_or("");
-                let parsed_date = match DateTime::parse_from_str(date_str, &f.date_format) {
+                // RFC3339 с долями секунды и "Z" (контейнерные логи) формат по умолчанию не разбирает
+                let parsed_date = match DateTime::parse_from_str(date_str, &f.date_format)
+                    .or_else(|_| DateTime::parse_from_rfc3339(date_str))
+                {
                     Ok(date) => date.with_timezone(&Utc),
                     Err(_) => return false,
                 };
@@ -89,6 +229,9 @@ impl Filter {
                 re.is_match(line)
             }
             Filter::Search(f) => line.contains(&f.substr),
+            Filter::Field(f) => fields::parse(line)
+                .iter()
+                .any(|(name, value)| name == &f.name && (f.value.is_empty() || value == &f.value)),
         }
     }
 }
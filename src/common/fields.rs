@@ -0,0 +1,156 @@
+// Поля строки в виде key=value (как в logfmt): так записываются поток контейнера, поля журнала
+// и разобранный NDJSON. Значение с пробелами или кавычками берётся в кавычки.
+
+use chrono::{DateTime, Utc};
+
+use crate::common::constants::DEFAULT_DATE_FORMAT;
+
+// Все поля строки по порядку; слова без '=' пропускаются
+pub fn parse(line: &str) -> Vec<(String, String)> {
+    scan(line)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Pair(key, value) if is_key(key) => Some((key.to_string(), value)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Слова без '=' вне значений в кавычках — флаги вроде bell в правиле оповещения
+pub fn words(line: &str) -> Vec<&str> {
+    scan(line)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Word(word) => Some(word),
+            Token::Pair(..) => None,
+        })
+        .collect()
+}
+
+enum Token<'a> {
+    Word(&'a str),
+    Pair(&'a str, String),
+}
+
+fn scan(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = line;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let token_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let Some(eq) = rest[..token_end].find('=') else {
+            tokens.push(Token::Word(&rest[..token_end]));
+            rest = &rest[token_end..];
+            continue;
+        };
+        let key = &rest[..eq];
+        let after = &rest[eq + 1..];
+        let (value, consumed) = match after.strip_prefix('"') {
+            Some(quoted) => unquote(quoted),
+            None => {
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                (after[..end].to_string(), end)
+            }
+        };
+        tokens.push(Token::Pair(key, value));
+        rest = &after[consumed..];
+    }
+    tokens
+}
+
+pub fn pair(name: &str, value: &str) -> String {
+    if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"') {
+        return format!("{}={}", name, value);
+    }
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{}=\"{}\"", name, escaped)
+}
+
+fn is_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '@'))
+}
+
+// Значение после открывающей кавычки и сколько байт занято вместе с обеими кавычками
+fn unquote(quoted: &str) -> (String, usize) {
+    let mut value = String::new();
+    let mut escaped = false;
+    for (i, c) in quoted.char_indices() {
+        match c {
+            _ if escaped => {
+                value.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            '"' => return (value, i + 2),
+            _ => value.push(c),
+        }
+    }
+    // Кавычка не закрыта — значение до конца строки
+    (value, quoted.len() + 1)
+}
+
+// Время в начале строки: формат датовых фильтров по умолчанию или RFC3339 с долями секунды
+pub fn timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let date_str = line.split_whitespace().next()?;
+    DateTime::parse_from_str(date_str, DEFAULT_DATE_FORMAT)
+        .or_else(|_| DateTime::parse_from_rfc3339(date_str))
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+// Строка без времени в начале
+pub fn message(line: &str) -> &str {
+    match timestamp(line) {
+        Some(_) => line
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest),
+        None => line,
+    }
+}
+
+// Уровень строки: поле level/severity, PRIORITY журнала или слово уровня в начале строки
+pub fn level(line: &str) -> Option<&'static str> {
+    for (name, value) in parse(line) {
+        match name.to_lowercase().as_str() {
+            "level" | "lvl" | "severity" => return normalize_level(&value),
+            "priority" => return priority_level(&value),
+            _ => {}
+        }
+    }
+    line.split_whitespace()
+        .take(LEVEL_WORDS)
+        .find_map(|word| normalize_level(word.trim_matches(|c: char| !c.is_alphabetic())))
+}
+
+// Слово уровня ищем только среди первых слов: дальше идёт текст сообщения
+const LEVEL_WORDS: usize = 5;
+
+fn normalize_level(word: &str) -> Option<&'static str> {
+    match word.to_lowercase().as_str() {
+        "fatal" | "panic" | "crit" | "critical" | "alert" | "emerg" => Some("fatal"),
+        "error" | "err" => Some("error"),
+        "warn" | "warning" => Some("warn"),
+        "info" | "notice" => Some("info"),
+        "debug" => Some("debug"),
+        "trace" => Some("trace"),
+        _ => None,
+    }
+}
+
+// syslog: 0-2 аварии, 3 ошибки, 4 предупреждения, 5-6 информация, 7 отладка
+fn priority_level(value: &str) -> Option<&'static str> {
+    match value.parse::<u8>().ok()? {
+        0..=2 => Some("fatal"),
+        3 => Some("error"),
+        4 => Some("warn"),
+        5 | 6 => Some("info"),
+        7 => Some("debug"),
+        _ => None,
+    }
+}
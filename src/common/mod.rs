@@ -1,5 +1,9 @@
 pub mod constants;
+pub mod dedup;
 pub mod enums;
+pub mod fields;
+pub mod private_dir;
+pub mod profile;
 pub mod structs;
 pub mod traits;
 
@@ -205,4 +209,199 @@ mod test {
         let line = "2022-01-08T11:27:44+09:00 test line in log".to_string();
         assert_eq!(f.is_include(&line), true)
     }
+
+    #[test]
+    fn test_date_filter_rfc3339_fallback() {
+        let date_start =
+            DateTime::parse_from_str("2026-10-17T12:00:00+00:00", "%Y-%m-%dT%H:%M:%S%z")
+                .expect("Не удалось сформировать DateTime для теста");
+        let f = enums::Filter::Date(structs::DateFilter {
+            date_format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
+            date_start: Some(date_start.with_timezone(&Utc)),
+            date_finish: None,
+            filter_type: structs::DateFilterType::After,
+        });
+        // Время контейнерного лога: доли секунды и "Z" формат по умолчанию не разбирает
+        let line = "2026-10-17T12:00:01.123000000Z stream=stdout test".to_string();
+        assert!(f.is_include(&line));
+        let line = "2026-10-17T11:59:59.999000000Z stream=stdout test".to_string();
+        assert!(!f.is_include(&line));
+    }
+
+    #[test]
+    fn test_fields_parse() {
+        let line = r#"2026-10-16T00:00:00Z PRIORITY=3 SYSLOG_IDENTIFIER="my \"app\"" =x a:b=1 msg= tail"#;
+        assert_eq!(
+            fields::parse(line),
+            vec![
+                ("PRIORITY".to_string(), "3".to_string()),
+                ("SYSLOG_IDENTIFIER".to_string(), "my \"app\"".to_string()),
+                ("msg".to_string(), "".to_string()),
+            ]
+        );
+        assert_eq!(fields::pair("unit", "a b"), r#"unit="a b""#);
+        assert_eq!(fields::pair("unit", "sshd.service"), "unit=sshd.service");
+        // Значение из pair разбирается обратно как было
+        let value = r#"say "hi" \o/"#;
+        let line = fields::pair("MESSAGE", value);
+        assert_eq!(fields::parse(&line), vec![("MESSAGE".to_string(), value.to_string())]);
+    }
+
+    #[test]
+    fn test_field_filter() {
+        let line = "2026-10-16T00:00:00.000000Z PRIORITY=3 _SYSTEMD_UNIT=nginx.service upstream timed out"
+            .to_string();
+        let f = enums::Filter::Field(structs::FieldFilter::parse("PRIORITY=3"));
+        assert!(f.is_include(&line));
+        let f = enums::Filter::Field(structs::FieldFilter::parse("_SYSTEMD_UNIT=nginx"));
+        assert!(!f.is_include(&line));
+        // Без значения достаточно, чтобы поле было
+        let f = enums::Filter::Field(structs::FieldFilter::parse("_SYSTEMD_UNIT"));
+        assert!(f.is_include(&line));
+        let f = enums::Filter::Field(structs::FieldFilter::parse("SYSLOG_IDENTIFIER"));
+        assert!(!f.is_include(&line));
+    }
+
+    #[test]
+    fn test_dedup_collapse() {
+        let lines: Vec<String> = [
+            "2026-10-17T12:00:01Z retry connect",
+            "2026-10-17T12:00:02Z retry connect",
+            "2026-10-17T12:00:03Z retry connect",
+            "connected",
+            "connected",
+            "2026-10-17T12:00:04Z retry connect",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        let counts = |runs: &[dedup::Run]| runs.iter().map(|r| r.count).collect::<Vec<_>>();
+
+        let mut runs = Vec::new();
+        dedup::collapse(&mut runs, &lines, 0, enums::Dedup::Exact);
+        assert_eq!(counts(&runs), vec![1, 1, 1, 2, 1]);
+
+        // Хвост дописывается по частям: повтор продолжает последнюю свёртку
+        let mut runs = Vec::new();
+        dedup::collapse(&mut runs, &lines[..4], 0, enums::Dedup::IgnoreTime);
+        dedup::collapse(&mut runs, &lines, 4, enums::Dedup::IgnoreTime);
+        assert_eq!(counts(&runs), vec![3, 2, 1]);
+        assert_eq!(runs[1].start, 3);
+        assert_eq!(runs[0].first.unwrap().to_rfc3339(), "2026-10-17T12:00:01+00:00");
+        assert_eq!(runs[0].last.unwrap().to_rfc3339(), "2026-10-17T12:00:03+00:00");
+        assert_eq!(runs[1].first, None);
+    }
+
+    #[test]
+    fn test_alert_rule_parse() {
+        let r = structs::AlertRule::parse(
+            r#"name=oom match="OutOfMemory(Error)?" source=api count=3 window=1m cooldown=300 bell highlight run="./notify.sh --loud" webhook=http://127.0.0.1:9000/hook"#,
+        );
+        assert_eq!(r.name, "oom");
+        assert_eq!(r.pattern, "OutOfMemory(Error)?");
+        assert_eq!(r.source.as_deref(), Some("api"));
+        assert_eq!((r.count, r.window_secs, r.cooldown_secs), (3, 60, 300));
+        assert!(r.bell && r.highlight);
+        assert_eq!(r.file, None);
+        assert_eq!(r.run.as_deref(), Some("./notify.sh --loud"));
+        assert_eq!(r.webhook.as_deref(), Some("http://127.0.0.1:9000/hook"));
+        // Ввод из to_input разбирается в то же правило
+        assert_eq!(structs::AlertRule::parse(&r.to_input()), r);
+
+        // Без имени именем служит шаблон, остальное по умолчанию
+        let r = structs::AlertRule::parse("match=panic window=2h");
+        assert_eq!(r.name, "panic");
+        assert_eq!((r.count, r.window_secs), (1, 7200));
+        assert!(!r.bell && !r.highlight);
+
+        // Слишком длинные окно и cooldown урезаются, переполнение оставляет значение по умолчанию
+        let r = structs::AlertRule::parse("match=panic window=99999999999999999h cooldown=9999999h");
+        assert_eq!((r.window_secs, r.cooldown_secs), (60, 30 * 24 * 3600));
+    }
+
+    #[test]
+    fn test_set_bookmark() {
+        let bookmark = |mark: char, line: &str| structs::Bookmark {
+            mark,
+            path: Some("/var/log/api.log".to_string()),
+            source: "api".to_string(),
+            offset: Some(42),
+            time: DateTime::from_timestamp(1_792_238_400, 0),
+            note: String::new(),
+            line: line.to_string(),
+        };
+        let mut memory = structs::Memory::default();
+        memory.set_bookmark(bookmark('c', "third"));
+        memory.set_bookmark(bookmark('a', "first"));
+        // Та же буква переставляет закладку
+        memory.set_bookmark(bookmark('c', "moved"));
+        let marks: Vec<(char, &str)> = memory
+            .bookmarks
+            .iter()
+            .map(|b| (b.mark, b.line.as_str()))
+            .collect();
+        assert_eq!(marks, vec![('a', "first"), ('c', "moved")]);
+
+        // Закладки переживают сохранение памяти
+        let json = serde_json::to_string(&memory.bookmarks).unwrap();
+        let loaded: Vec<structs::Bookmark> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, memory.bookmarks);
+    }
+
+    #[test]
+    fn test_command_source_parse() {
+        let c = structs::CommandSource::parse("nginx: journalctl -f -u nginx");
+        assert_eq!(c.name, "nginx");
+        assert_eq!(c.command, "journalctl -f -u nginx");
+        assert_eq!(c.to_input(), "nginx: journalctl -f -u nginx");
+
+        // Двоеточие внутри команды не делает её именем
+        let c = structs::CommandSource::parse("sh -c 'echo a: b'");
+        assert_eq!(c.name, "sh -c 'echo a: b'");
+        assert_eq!(c.command, "sh -c 'echo a: b'");
+        assert_eq!(c.to_input(), "sh -c 'echo a: b'");
+    }
+
+    #[test]
+    fn test_agent_source_parse() {
+        let a = structs::AgentSource::parse("web=10.0.0.5:8516", "secret".to_string());
+        assert_eq!(a.name, "web");
+        assert_eq!(a.addr, "10.0.0.5:8516");
+        assert!(a.session);
+
+        let a = structs::AgentSource::parse("10.0.0.5:8516", "secret".to_string());
+        assert_eq!(a.name, "10.0.0.5:8516");
+        assert_eq!(a.addr, "10.0.0.5:8516");
+
+        // Признак session в файл профиля не пишется
+        let memory = structs::Memory {
+            agents: vec![a],
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&memory).unwrap();
+        let loaded: structs::Memory = serde_json::from_str(&json).unwrap();
+        assert!(!loaded.agents[0].session);
+    }
+
+    #[test]
+    fn test_date_filter_roundtrip() {
+        let mut memory = structs::Memory::default();
+        memory.add_filter(enums::Filter::Date(structs::DateFilter {
+            date_format: constants::DEFAULT_DATE_FORMAT.to_string(),
+            date_start: DateTime::from_timestamp(1_792_238_400, 0),
+            date_finish: None,
+            filter_type: structs::DateFilterType::After,
+        }));
+        // Сохранённый фильтр по дате читается обратно с теми же датами
+        let json = serde_json::to_string(&memory).unwrap();
+        let loaded: structs::Memory = serde_json::from_str(&json).unwrap();
+        match &loaded.filters[..] {
+            [enums::Filter::Date(f)] => {
+                assert_eq!(f.date_start, DateTime::from_timestamp(1_792_238_400, 0));
+                assert_eq!(f.date_finish, None);
+                assert_eq!(f.filter_type, structs::DateFilterType::After);
+            }
+            _ => panic!("Ожидался один фильтр по дате"),
+        }
+    }
 }
@@ -0,0 +1,61 @@
+// Временный каталог, доступный только владельцу: спул stdin, куски сортировки выгрузки.
+// Каталог удаляется вместе со значением
+
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::{
+    fs::{self, DirBuilder, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub struct PrivateDir {
+    path: PathBuf,
+}
+
+impl PrivateDir {
+    // Каталог создаётся без перезаписи: подложенный заранее каталог или символическая ссылка
+    // не подхватятся
+    pub fn create(parent: &Path, prefix: &str) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let mut attempt = 0;
+        loop {
+            let path = parent.join(format!(
+                "logscraper-{}-{}-{}",
+                prefix,
+                std::process::id(),
+                nanos.wrapping_add(attempt)
+            ));
+            let mut builder = DirBuilder::new();
+            #[cfg(unix)]
+            builder.mode(0o700);
+            match builder.create(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < 16 => attempt += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Новый файл в каталоге, тоже только для владельца
+    pub fn create_file(&self, name: &str) -> io::Result<File> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        options.open(self.path.join(name))
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,130 @@
+// Профили: у каждого свой файл памяти в $XDG_CONFIG_HOME/logscraper/profiles (или ~/.config),
+// поэтому настройки не зависят от каталога, из которого запущен logscraper
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::common::constants::{DEFAULT_PROFILE, MEMORY_FILE, PROFILE_DIR};
+
+// Профиль этого запуска: --profile, затем переключатель в TUI
+static ACTIVE: Mutex<String> = Mutex::new(String::new());
+
+pub fn select(name: &str) {
+    *ACTIVE.lock().unwrap() = name.to_string();
+}
+
+pub fn active() -> String {
+    let active = ACTIVE.lock().unwrap();
+    match active.is_empty() {
+        true => DEFAULT_PROFILE.to_string(),
+        false => active.clone(),
+    }
+}
+
+// Имя становится именем файла, поэтому без разделителей пути и точек
+pub fn is_valid(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// Без HOME и XDG_CONFIG_HOME профили лежат в рабочем каталоге, но каждый в своём файле:
+// иначе переключение профиля ничего бы не меняло
+pub fn dir() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map_or_else(
+            || PathBuf::from(PROFILE_DIR),
+            |config| config.join("logscraper").join("profiles"),
+        )
+}
+
+pub fn file(name: &str) -> PathBuf {
+    dir().join(format!("{}.json", name))
+}
+
+pub fn list() -> Vec<String> {
+    list_in(&dir())
+}
+
+fn list_in(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            match path.extension()? == "json" {
+                true => Some(path.file_stem()?.to_str()?.to_string()),
+                false => None,
+            }
+        })
+        .filter(|name| is_valid(name))
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn delete(name: &str) -> io::Result<()> {
+    fs::remove_file(file(name))
+}
+
+// Один раз, пока профилей ещё нет: ./memory.json копируется в профиль по умолчанию.
+// Сам файл остаётся на месте — его могут читать старые версии
+pub fn migrate() -> io::Result<Option<PathBuf>> {
+    migrate_in(&dir(), Path::new(MEMORY_FILE))
+}
+
+fn migrate_in(dir: &Path, legacy: &Path) -> io::Result<Option<PathBuf>> {
+    if !legacy.exists() || !list_in(dir).is_empty() {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir)?;
+    let target = dir.join(format!("{}.json", DEFAULT_PROFILE));
+    fs::copy(legacy, &target)?;
+    Ok(Some(target))
+}
+
+#[cfg(test)]
+mod test {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_and_list() {
+        let tmp_dir = TempDir::new("profile").expect("Не получилось создать временную директорию");
+        let dir = tmp_dir.path().join("logscraper").join("profiles");
+        let legacy = tmp_dir.path().join(MEMORY_FILE);
+
+        // Нечего переносить
+        assert_eq!(migrate_in(&dir, &legacy).unwrap(), None);
+        assert!(list_in(&dir).is_empty());
+
+        fs::write(&legacy, r#"{"paths":[],"filters":[]}"#).unwrap();
+        let target = migrate_in(&dir, &legacy).unwrap().unwrap();
+        assert_eq!(target, dir.join("default.json"));
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            r#"{"paths":[],"filters":[]}"#
+        );
+        assert!(legacy.exists());
+
+        // Второй раз не переносится: профиль уже есть и мог измениться
+        fs::write(dir.join("work.json"), "{}").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(&target, "{}").unwrap();
+        assert_eq!(migrate_in(&dir, &legacy).unwrap(), None);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "{}");
+        assert_eq!(list_in(&dir), vec!["default", "work"]);
+
+        assert!(is_valid("prod-eu_1"));
+        assert!(!is_valid("../etc") && !is_valid("") && !is_valid("a.b"));
+    }
+}
@@ -1,44 +1,107 @@
 use chrono::{DateTime, Utc};
 use std::fs;
-use std::path::Path as StdPath;
 
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::{UnboundedSender, error::SendError};
 
 use crate::common::{
-    constants::MEMORY_FILE,
-    enums::{Filter, MemoryError},
+    constants::{
+        ALERT_COOLDOWN_SECS, ALERT_COUNT, ALERT_MAX_SECS, ALERT_WINDOW_SECS, INGEST_BUFFER_LINES,
+        INGEST_MAX_BODY, INGEST_SPOOL, PAGE_SIZE, SPOOL_FILES, SPOOL_MAX_BYTES, STDIN_PATH, SYSLOG_BUFFER_LINES,
+    },
+    enums::{Encoding, Filter, LogFormat, MemoryError, Mode, Order},
+    fields, profile,
 };
 
 use super::*;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Memory {
     pub paths: Vec<Path>,
     pub filters: Vec<Filter>,
+    #[serde(default)]
+    pub commands: Vec<CommandSource>,
+    #[serde(default)]
+    pub syslog: Option<SyslogConfig>,
+    #[serde(default)]
+    pub ingest: Option<IngestConfig>,
+    #[serde(default)]
+    pub agents: Vec<AgentSource>,
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    #[serde(default)]
+    pub redaction: Redaction,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub view: View,
+}
+
+// Как профиль показывает логи: порядок, размер страницы и режим при запуске
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct View {
+    pub order: Order,
+    pub page_size: usize,
+    pub mode: Mode,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            order: Order::OrderByDate,
+            page_size: PAGE_SIZE,
+            mode: Mode::Page,
+        }
+    }
 }
 
 #[allow(dead_code)]
 impl Memory {
+    // Память активного профиля; при первом запуске с профилями переносит ./memory.json
     pub fn load() -> Result<Memory, MemoryError> {
-        if !StdPath::new(MEMORY_FILE).exists() {
-            return Ok(Memory {
-                paths: Vec::new(),
-                filters: Vec::new(),
-            });
+        profile::migrate().map_err(|_| MemoryError::FSError)?;
+        let file = profile::file(&profile::active());
+        if !file.exists() {
+            return Ok(Memory::default());
         }
 
-        let json = fs::read_to_string(MEMORY_FILE).map_err(|_| MemoryError::FSError)?;
+        let json = fs::read_to_string(file).map_err(|_| MemoryError::FSError)?;
         let data: Memory = serde_json::from_str(&json).map_err(|_| MemoryError::SerdeError)?;
         Ok(data)
     }
 
     pub fn save(&self) -> Result<(), MemoryError> {
-        let json = serde_json::to_string(self).map_err(|_| MemoryError::SerdeError)?;
-        fs::write(MEMORY_FILE, json).map_err(|_| MemoryError::FSError)?;
+        // stdin существует только в этом запуске — не сохраняем
+        let mut saved = self.clone();
+        saved.paths.retain(|p| p.path != STDIN_PATH);
+        // Агенты из командной строки тоже
+        saved.agents.retain(|a| !a.session);
+        let json = serde_json::to_string(&saved).map_err(|_| MemoryError::SerdeError)?;
+        let file = profile::file(&profile::active());
+        if let Some(dir) = file.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|_| MemoryError::FSError)?;
+        }
+        fs::write(file, json).map_err(|_| MemoryError::FSError)?;
         Ok(())
     }
 
+    // Источники Page Mode: пути профиля и спул HTTP-приёма, если он уже есть на диске
+    pub fn page_paths(&self) -> Vec<Path> {
+        let mut paths = self.paths.clone();
+        if let Some(config) = &self.ingest
+            && let Some(spool) = &config.spool
+        {
+            let rotated = (1..=config.spool_files).rev().map(|n| format!("{}.{}", spool, n));
+            paths.extend(
+                rotated
+                    .chain([spool.clone()])
+                    .filter(|p| fs::metadata(p).is_ok())
+                    .map(|p| Path::new(p, "ingest".to_string())),
+            );
+        }
+        paths
+    }
+
     pub fn add_filter(&mut self, filter: Filter) {
         self.filters.push(filter);
     }
@@ -47,6 +110,48 @@ impl Memory {
         self.paths.push(path);
     }
 
+    pub fn add_command(&mut self, command: CommandSource) {
+        self.commands.push(command);
+    }
+
+    pub fn add_alert(&mut self, alert: AlertRule) {
+        self.alerts.push(alert);
+    }
+
+    pub fn remove_alert(&mut self, index: usize) -> Result<(), MemoryError> {
+        if index < self.alerts.len() {
+            self.alerts.remove(index);
+            Ok(())
+        } else {
+            Err(MemoryError::FSError) // Index out of bounds
+        }
+    }
+
+    pub fn update_alert(&mut self, index: usize, alert: AlertRule) -> Result<(), MemoryError> {
+        if index < self.alerts.len() {
+            self.alerts[index] = alert;
+            Ok(())
+        } else {
+            Err(MemoryError::FSError) // Index out of bounds
+        }
+    }
+
+    // Закладка с той же буквой заменяется; список держим по буквам
+    pub fn set_bookmark(&mut self, bookmark: Bookmark) {
+        self.bookmarks.retain(|b| b.mark != bookmark.mark);
+        self.bookmarks.push(bookmark);
+        self.bookmarks.sort_by_key(|b| b.mark);
+    }
+
+    pub fn remove_bookmark(&mut self, index: usize) -> Result<(), MemoryError> {
+        if index < self.bookmarks.len() {
+            self.bookmarks.remove(index);
+            Ok(())
+        } else {
+            Err(MemoryError::FSError) // Index out of bounds
+        }
+    }
+
     pub fn remove_filter(&mut self, index: usize) -> Result<(), MemoryError> {
         if index < self.filters.len() {
             self.filters.remove(index);
@@ -65,6 +170,15 @@ impl Memory {
         }
     }
 
+    pub fn remove_command(&mut self, index: usize) -> Result<(), MemoryError> {
+        if index < self.commands.len() {
+            self.commands.remove(index);
+            Ok(())
+        } else {
+            Err(MemoryError::FSError) // Index out of bounds
+        }
+    }
+
     pub fn get_paths(&self) -> &Vec<Path> {
         &self.paths
     }
@@ -82,6 +196,15 @@ impl Memory {
         }
     }
 
+    pub fn update_command(&mut self, index: usize, command: CommandSource) -> Result<(), MemoryError> {
+        if index < self.commands.len() {
+            self.commands[index] = command;
+            Ok(())
+        } else {
+            Err(MemoryError::FSError) // Index out of bounds
+        }
+    }
+
     pub fn update_filter(&mut self, index: usize, filter: Filter) -> Result<(), MemoryError> {
         if index < self.filters.len() {
             self.filters[index] = filter;
@@ -207,7 +330,7 @@ pub struct Source {
 impl Source {
     pub fn new(path: String, name: String) -> Self {
         Self {
-            path: Path { path, name },
+            path: Path::new(path, name),
             size: None,
         }
     }
@@ -223,14 +346,340 @@ impl Source {
 pub struct Path {
     pub path: String,
     pub name: String,
+    #[serde(default)]
+    pub encoding: Encoding,
+    #[serde(default)]
+    pub format: LogFormat,
 }
 
 impl Path {
     pub fn new(path: String, name: String) -> Self {
-        Self { path, name }
+        Self {
+            path,
+            name,
+            encoding: Encoding::default(),
+            format: LogFormat::default(),
+        }
     }
 }
 
+// Строка страницы Page Mode и её место в файле источника. Места нет у строк агентов,
+// stdin и форматов, где строка лога собирается из нескольких строк файла
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageLine {
+    pub line: String,
+    pub place: Option<Place>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub path: String,
+    // Начало строки в байтах от начала файла
+    pub offset: u64,
+}
+
+// Закладка на строку (b + буква в TUI): где строка лежит и когда случилась, плюс заметка.
+// path и offset пусты для строк не из файлов — хвоста команд, syslog, агентов
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub mark: char,
+    #[serde(default)]
+    pub path: Option<String>,
+    pub source: String,
+    #[serde(default)]
+    pub offset: Option<u64>,
+    #[serde(default, with = "option_datetime_utc")]
+    pub time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub note: String,
+    pub line: String,
+}
+
+// Маскирование на экране и в выгрузке; enabled — включено при запуске TUI
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Redaction {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<RedactRule>,
+}
+
+// Своё правило маскирования: совпадение pattern (или его группа value) заменяется на <NAME>
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RedactRule {
+    pub name: String,
+    pub pattern: String,
+}
+
+// Команда, вывод которой читается как лог: journalctl -f, docker logs -f, скрипты
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommandSource {
+    pub name: String,
+    pub command: String,
+}
+
+impl CommandSource {
+    // Ввод из TUI: "name: command"; без имени именем служит сама команда
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim();
+        match input.split_once(": ") {
+            Some((name, command)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                Self {
+                    name: name.to_string(),
+                    command: command.trim().to_string(),
+                }
+            }
+            _ => Self {
+                name: input.to_string(),
+                command: input.to_string(),
+            },
+        }
+    }
+
+    pub fn to_input(&self) -> String {
+        if self.name == self.command {
+            self.command.clone()
+        } else {
+            format!("{}: {}", self.name, self.command)
+        }
+    }
+}
+
+// Правило оповещения для хвоста: count совпадений pattern за window секунд
+// в источнике source (или в любом) — звонок, подсветка строки, запись в файл, команда
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub name: String,
+    // Регулярное выражение; пустое правило не срабатывает
+    pub pattern: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    pub count: usize,
+    pub window_secs: u64,
+    // Сколько молчать после срабатывания
+    pub cooldown_secs: u64,
+    #[serde(default)]
+    pub bell: bool,
+    #[serde(default)]
+    pub highlight: bool,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub run: Option<String>,
+    // http:// адрес, куда срабатывание уходит POST-запросом с JSON
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+impl AlertRule {
+    // Ввод из TUI: "name=oom match=OutOfMemoryError source=api count=3 window=1m cooldown=5m
+    // bell highlight file=alerts.log run=./notify.sh webhook=http://host/hook"; без имени именем служит шаблон
+    pub fn parse(input: &str) -> Self {
+        let mut rule = Self {
+            name: String::new(),
+            pattern: String::new(),
+            source: None,
+            count: ALERT_COUNT,
+            window_secs: ALERT_WINDOW_SECS,
+            cooldown_secs: ALERT_COOLDOWN_SECS,
+            bell: false,
+            highlight: false,
+            file: None,
+            run: None,
+            webhook: None,
+        };
+        for (key, value) in fields::parse(input) {
+            match key.as_str() {
+                "name" => rule.name = value,
+                "match" => rule.pattern = value,
+                "source" => rule.source = Some(value).filter(|v| !v.is_empty()),
+                "count" => rule.count = value.parse().unwrap_or(rule.count).max(1),
+                "window" => {
+                    rule.window_secs = seconds(&value)
+                        .unwrap_or(rule.window_secs)
+                        .min(ALERT_MAX_SECS)
+                }
+                "cooldown" => {
+                    rule.cooldown_secs = seconds(&value)
+                        .unwrap_or(rule.cooldown_secs)
+                        .min(ALERT_MAX_SECS)
+                }
+                "file" => rule.file = Some(value).filter(|v| !v.is_empty()),
+                "run" => rule.run = Some(value).filter(|v| !v.is_empty()),
+                "webhook" => rule.webhook = Some(value).filter(|v| !v.is_empty()),
+                _ => {}
+            }
+        }
+        for word in fields::words(input) {
+            match word {
+                "bell" => rule.bell = true,
+                "highlight" => rule.highlight = true,
+                _ => {}
+            }
+        }
+        if rule.name.is_empty() {
+            rule.name = rule.pattern.clone();
+        }
+        rule
+    }
+
+    pub fn to_input(&self) -> String {
+        let mut parts = vec![
+            fields::pair("name", &self.name),
+            fields::pair("match", &self.pattern),
+        ];
+        if let Some(source) = &self.source {
+            parts.push(fields::pair("source", source));
+        }
+        parts.push(format!("count={}", self.count));
+        parts.push(format!("window={}s", self.window_secs));
+        parts.push(format!("cooldown={}s", self.cooldown_secs));
+        if self.bell {
+            parts.push("bell".to_string());
+        }
+        if self.highlight {
+            parts.push("highlight".to_string());
+        }
+        if let Some(file) = &self.file {
+            parts.push(fields::pair("file", file));
+        }
+        if let Some(run) = &self.run {
+            parts.push(fields::pair("run", run));
+        }
+        if let Some(webhook) = &self.webhook {
+            parts.push(fields::pair("webhook", webhook));
+        }
+        parts.join(" ")
+    }
+}
+
+// "90", "90s", "5m", "1h"; None и при переполнении
+fn seconds(value: &str) -> Option<u64> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "s" => Some(number),
+        "m" => number.checked_mul(60),
+        "h" => number.checked_mul(3600),
+        _ => None,
+    }
+}
+
+// Удалённый агент (`logscraper agent`), чьи файлы TUI показывает наравне со своими
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AgentSource {
+    pub name: String,
+    // "host:port"
+    pub addr: String,
+    pub token: String,
+    // Добавлен через --agent и не сохраняется в профиль
+    #[serde(skip)]
+    pub session: bool,
+}
+
+impl AgentSource {
+    // Аргумент --agent: "name=host:port"; без имени именем служит адрес
+    pub fn parse(input: &str, token: String) -> Self {
+        let (name, addr) = input.split_once('=').unwrap_or((input, input));
+        Self {
+            name: name.to_string(),
+            addr: addr.to_string(),
+            token,
+            session: true,
+        }
+    }
+}
+
+// Приём syslog в Tail Mode; адреса вида "0.0.0.0:5514", без адреса протокол не слушается
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyslogConfig {
+    #[serde(default)]
+    pub udp: Option<String>,
+    #[serde(default)]
+    pub tcp: Option<String>,
+    // Файл, куда пишутся все принятые сообщения, чтобы потом листать их в Page Mode
+    #[serde(default)]
+    pub spool: Option<String>,
+    #[serde(default = "default_spool_max_bytes")]
+    pub spool_max_bytes: u64,
+    #[serde(default = "default_spool_files")]
+    pub spool_files: usize,
+    #[serde(default = "default_syslog_buffer_lines")]
+    pub buffer_lines: usize,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        Self {
+            udp: None,
+            tcp: None,
+            spool: None,
+            spool_max_bytes: default_spool_max_bytes(),
+            spool_files: default_spool_files(),
+            buffer_lines: default_syslog_buffer_lines(),
+        }
+    }
+}
+
+fn default_syslog_buffer_lines() -> usize {
+    SYSLOG_BUFFER_LINES
+}
+
+// HTTP-приём в Tail Mode: POST /ingest/<source> с NDJSON или текстом
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IngestConfig {
+    // Адрес вида "127.0.0.1:8514"
+    pub addr: String,
+    // Файл, куда пишутся все принятые строки, чтобы листать их в Page Mode; null — не писать
+    #[serde(default = "default_ingest_spool")]
+    pub spool: Option<String>,
+    #[serde(default = "default_spool_max_bytes")]
+    pub spool_max_bytes: u64,
+    #[serde(default = "default_spool_files")]
+    pub spool_files: usize,
+    #[serde(default = "default_ingest_max_body")]
+    pub max_body_bytes: usize,
+    #[serde(default = "default_ingest_buffer_lines")]
+    pub buffer_lines: usize,
+}
+
+impl IngestConfig {
+    pub fn new(addr: String) -> Self {
+        Self {
+            addr,
+            spool: default_ingest_spool(),
+            spool_max_bytes: default_spool_max_bytes(),
+            spool_files: default_spool_files(),
+            max_body_bytes: default_ingest_max_body(),
+            buffer_lines: default_ingest_buffer_lines(),
+        }
+    }
+}
+
+fn default_ingest_spool() -> Option<String> {
+    let spool = crate::index::dir().with_file_name(INGEST_SPOOL);
+    Some(spool.to_string_lossy().into_owned())
+}
+
+fn default_ingest_max_body() -> usize {
+    INGEST_MAX_BODY
+}
+
+fn default_ingest_buffer_lines() -> usize {
+    INGEST_BUFFER_LINES
+}
+
+fn default_spool_max_bytes() -> u64 {
+    SPOOL_MAX_BYTES
+}
+
+fn default_spool_files() -> usize {
+    SPOOL_FILES
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct DateFilter {
     pub date_format: String,
@@ -257,8 +706,26 @@ pub struct RegexFilter {
 pub struct SearchFilter {
     pub substr: String,
 }
+
+// Поле key=value в строке; пустое значение — поле просто есть
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FieldFilter {
+    pub name: String,
+    pub value: String,
+}
+
+impl FieldFilter {
+    // "name=value" или просто "name"
+    pub fn parse(s: &str) -> Self {
+        let (name, value) = s.split_once('=').unwrap_or((s, ""));
+        Self {
+            name: name.trim().to_string(),
+            value: value.to_string(),
+        }
+    }
+}
 mod option_datetime_utc {
-    use chrono::{DateTime, Utc};
+    use chrono::{DateTime, NaiveDateTime, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
 
     const FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
@@ -280,9 +747,10 @@ mod option_datetime_utc {
         let s: Option<String> = Option::deserialize(deserializer)?;
         match s {
             Some(s) => {
-                let dt = DateTime::parse_from_str(&s, FORMAT)
+                // В FORMAT смещение — буква Z, а не %z: читаем как время UTC без зоны
+                let dt = NaiveDateTime::parse_from_str(&s, FORMAT)
                     .map_err(serde::de::Error::custom)?
-                    .with_timezone(&Utc);
+                    .and_utc();
                 Ok(Some(dt))
             }
             None => Ok(None),
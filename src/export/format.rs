@@ -0,0 +1,118 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    sync::atomic::Ordering,
+};
+
+use chrono::SecondsFormat;
+use serde_json::{Map, Value};
+
+use crate::common::{constants::EXPORT_PROGRESS_STEP, enums::ExportFormat, fields};
+use crate::export::{ExportProgress, Record};
+
+// false — выгрузку отменили на середине. count — сколько строк придёт, для прогресса;
+// columns — поля CSV, собранные csv_columns по тем же строкам
+pub fn write(
+    records: impl Iterator<Item = io::Result<Record>>,
+    count: usize,
+    columns: &[String],
+    format: ExportFormat,
+    out: &mut impl Write,
+    progress: &ExportProgress,
+) -> io::Result<bool> {
+    progress.total.store(count as u64, Ordering::Relaxed);
+    progress.done.store(0, Ordering::Relaxed);
+    if format == ExportFormat::Csv {
+        let header = ["source", "timestamp", "line"]
+            .into_iter()
+            .chain(columns.iter().map(String::as_str));
+        csv_row(out, header)?;
+    }
+
+    for (i, record) in records.enumerate() {
+        let record = record?;
+        if i.is_multiple_of(EXPORT_PROGRESS_STEP) {
+            progress.done.store(i as u64, Ordering::Relaxed);
+            if progress.cancelled.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+        }
+        match format {
+            ExportFormat::Text => writeln!(out, "{}", record.line)?,
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *out, &ndjson(&record))?;
+                writeln!(out)?;
+            }
+            ExportFormat::Csv => {
+                let parsed: HashMap<String, String> =
+                    fields::parse(&record.line).into_iter().rev().collect();
+                let timestamp = timestamp(&record.line).unwrap_or_default();
+                let row = [
+                    record.source.as_deref().unwrap_or(""),
+                    timestamp.as_str(),
+                    record.line.as_str(),
+                ]
+                .into_iter()
+                .chain(
+                    columns
+                        .iter()
+                        .map(|c| parsed.get(c).map_or("", String::as_str)),
+                );
+                csv_row(out, row)?;
+            }
+        }
+    }
+    progress.done.store(count as u64, Ordering::Relaxed);
+    out.flush()?;
+    Ok(true)
+}
+
+fn timestamp(line: &str) -> Option<String> {
+    fields::timestamp(line).map(|t| t.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+fn ndjson(record: &Record) -> Value {
+    let mut parsed = Map::new();
+    // Повторное поле не затирает первое, как и в фильтре по полю
+    for (name, value) in fields::parse(&record.line) {
+        parsed.entry(name).or_insert(Value::String(value));
+    }
+    let mut object = Map::new();
+    object.insert("source".to_string(), record.source.clone().into());
+    object.insert("timestamp".to_string(), timestamp(&record.line).into());
+    object.insert("line".to_string(), record.line.clone().into());
+    object.insert("fields".to_string(), Value::Object(parsed));
+    Value::Object(object)
+}
+
+// Поля в порядке первого появления. У CSV колонки общие на весь файл: все поля,
+// которые встретились хоть в одной строке, поэтому строки проходятся дважды
+pub fn csv_columns(records: impl Iterator<Item = io::Result<Record>>) -> io::Result<Vec<String>> {
+    let mut seen = HashSet::new();
+    let mut columns = Vec::new();
+    for record in records {
+        for (name, _) in fields::parse(&record?.line) {
+            if seen.insert(name.clone()) {
+                columns.push(name);
+            }
+        }
+    }
+    Ok(columns)
+}
+
+// RFC 4180: значение с запятой, кавычкой или переводом строки берётся в кавычки
+fn csv_row<'a>(out: &mut impl Write, values: impl Iterator<Item = &'a str>) -> io::Result<()> {
+    let mut first = true;
+    for value in values {
+        if !first {
+            out.write_all(b",")?;
+        }
+        first = false;
+        if value.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", value.replace('"', "\"\""))?;
+        } else {
+            out.write_all(value.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")
+}
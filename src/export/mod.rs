@@ -0,0 +1,459 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::task;
+
+use crate::common::{
+    constants::{EXPORT_PROGRESS_STEP, EXPORT_RUN_BYTES, EXPORT_STDOUT, REMOTE_PAGE_LINES},
+    enums::{ExportFormat, Filter, Order},
+    structs::{AgentSource, Path},
+};
+use crate::reader::file::try_visit_lines;
+use crate::redact::Redactor;
+use crate::remote::client::Connection;
+
+// Выгрузка в файл для тикета или коллеги: из TUI (клавиша x) и из `logscraper export`
+pub mod format;
+pub mod sort;
+
+use sort::Runs;
+
+// Строка выгрузки; source нет у строк хвоста — на экране он не хранится
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub source: Option<String>,
+    pub line: String,
+}
+
+// Что выгружать: запрос как у страницы Page Mode или уже готовые строки
+pub enum Selection {
+    Lines(Vec<String>),
+    Query(Query),
+}
+
+pub struct Query {
+    pub paths: Vec<Path>,
+    pub agents: Vec<AgentSource>,
+    pub filters: Vec<Filter>,
+    pub order: Order,
+    pub offset: usize,
+    // usize::MAX — все строки после offset
+    pub limit: usize,
+}
+
+pub struct ExportRequest {
+    pub selection: Selection,
+    pub format: ExportFormat,
+    // EXPORT_STDOUT — в stdout
+    pub output: String,
+    // Маскирование строк перед записью, как на экране
+    pub redactor: Option<Arc<Redactor>>,
+}
+
+#[derive(Default)]
+pub struct ExportProgress {
+    pub running: AtomicBool,
+    pub cancelled: AtomicBool,
+    pub total: AtomicU64,
+    pub done: AtomicU64,
+    pub current: Mutex<String>,
+    pub error: Mutex<Option<String>>,
+    pub saved: Mutex<Option<String>>,
+}
+
+impl ExportProgress {
+    // Перед новой выгрузкой: итог прошлой больше не показываем
+    pub fn start(&self) {
+        self.running.store(true, Ordering::Relaxed);
+        self.cancelled.store(false, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+        self.done.store(0, Ordering::Relaxed);
+        *self.error.lock().unwrap() = None;
+        *self.saved.lock().unwrap() = None;
+    }
+
+    fn percent(&self) -> u64 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0;
+        }
+        self.done.load(Ordering::Relaxed).min(total) * 100 / total
+    }
+
+    // Пока строки читаются, сколько их всего, неизвестно — показываем, сколько прочитано
+    pub fn text(&self) -> String {
+        match self.total.load(Ordering::Relaxed) {
+            0 => format!("{} lines", self.done.load(Ordering::Relaxed)),
+            _ => format!("{}%", self.percent()),
+        }
+    }
+
+    fn set_current(&self, current: String) {
+        *self.current.lock().unwrap() = current;
+    }
+}
+
+// Число выгруженных строк; None — выгрузку отменили, файла нет
+pub async fn export(
+    request: ExportRequest,
+    progress: Arc<ExportProgress>,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    let records = match request.selection {
+        Selection::Lines(lines) => Output::Lines(
+            lines
+                .into_iter()
+                .map(|line| Record { source: None, line })
+                .collect(),
+        ),
+        Selection::Query(query) => match collect(&query, &progress).await? {
+            Some(runs) => Output::Query {
+                runs,
+                offset: query.offset,
+                limit: query.limit,
+            },
+            None => return Ok(None),
+        },
+    };
+    let count = records.len();
+    progress.set_current(format!("writing {}", request.output));
+    let done = task::spawn_blocking(move || {
+        write_output(
+            &records,
+            request.redactor.as_deref(),
+            request.format,
+            &request.output,
+            &progress,
+        )
+    })
+    .await??;
+    Ok(done.then_some(count))
+}
+
+// Что записать: готовые строки или слияние кусков сортировки со срезом запроса
+enum Output {
+    Lines(Vec<Record>),
+    Query {
+        runs: Runs,
+        offset: usize,
+        limit: usize,
+    },
+}
+
+type Records<'a> = Box<dyn Iterator<Item = io::Result<Record>> + 'a>;
+
+impl Output {
+    fn len(&self) -> usize {
+        match self {
+            Output::Lines(lines) => lines.len(),
+            Output::Query {
+                runs,
+                offset,
+                limit,
+            } => runs.len().saturating_sub(*offset).min(*limit),
+        }
+    }
+
+    // Строки по порядку, замаскированные перед записью; CSV проходит их дважды
+    fn records<'a>(&'a self, redactor: Option<&'a Redactor>) -> io::Result<Records<'a>> {
+        let records: Records<'a> = match self {
+            Output::Lines(lines) => Box::new(lines.iter().cloned().map(Ok)),
+            Output::Query {
+                runs,
+                offset,
+                limit,
+            } => {
+                let mut merge = runs.merge()?;
+                for _ in 0..*offset {
+                    if merge.next().transpose()?.is_none() {
+                        break;
+                    }
+                }
+                Box::new(merge.take(*limit))
+            }
+        };
+        Ok(match redactor {
+            Some(redactor) => Box::new(records.map(|record| {
+                record.map(|mut record| {
+                    record.line = redactor.redact(&record.line);
+                    record
+                })
+            })),
+            None => records,
+        })
+    }
+}
+
+// Строки каждого источника читаются отдельно, чтобы знать, откуда строка;
+// порядок и срез — как в read_from_sources, поэтому страница совпадает с экраном.
+// Строки не копятся в памяти: их сортируют куски Runs. None — выгрузку отменили
+async fn collect(
+    query: &Query,
+    progress: &Arc<ExportProgress>,
+) -> Result<Option<Runs>, Box<dyn Error>> {
+    let top = query.offset.saturating_add(query.limit);
+    let mut runs = Runs::new(&query.order, top, EXPORT_RUN_BYTES);
+
+    // Свои файлы — одним проходом в отдельном потоке. Отмена и прогресс проверяются
+    // каждые EXPORT_PROGRESS_STEP строк, так что отменить можно и чтение одного большого файла
+    let paths = query.paths.clone();
+    let filters = Some(query.filters.clone());
+    let reading = progress.clone();
+    let (result, returned) = task::spawn_blocking(move || {
+        let mut read: u64 = 0;
+        let mut current = String::new();
+        let result = try_visit_lines(&paths, &filters, |source, line| {
+            if read.is_multiple_of(EXPORT_PROGRESS_STEP as u64) {
+                reading.done.store(read, Ordering::Relaxed);
+                if reading.cancelled.load(Ordering::Relaxed) {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+            }
+            if current != source {
+                current = source.to_string();
+                reading.set_current(format!("reading {}", source));
+            }
+            read += 1;
+            runs.push(Record {
+                source: Some(source.to_string()),
+                line,
+            })
+        });
+        (result, runs)
+    })
+    .await?;
+    runs = returned;
+    if progress.cancelled.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    result?;
+
+    // Агент отдаёт страницу не больше REMOTE_PAGE_LINES строк: источник читается кусками
+    for agent in &query.agents {
+        let mut connection = Connection::connect(agent).await?;
+        for source in connection.paths.clone() {
+            let name = format!("{}/{}", agent.name, source);
+            progress.set_current(format!("reading {}", name));
+            let mut offset = 0;
+            while offset < top {
+                if progress.cancelled.load(Ordering::Relaxed) {
+                    return Ok(None);
+                }
+                let want = (top - offset).min(REMOTE_PAGE_LINES);
+                let lines = connection
+                    .page(
+                        vec![source.clone()],
+                        query.filters.clone(),
+                        query.order.clone(),
+                        offset,
+                        want,
+                    )
+                    .await?;
+                let last = lines.len() < want;
+                offset += lines.len();
+                progress
+                    .done
+                    .fetch_add(lines.len() as u64, Ordering::Relaxed);
+                let name = name.clone();
+                runs = task::spawn_blocking(move || {
+                    for line in lines {
+                        runs.push(Record {
+                            source: Some(name.clone()),
+                            line,
+                        })?;
+                    }
+                    Ok::<_, io::Error>(runs)
+                })
+                .await??;
+                if last {
+                    break;
+                }
+            }
+        }
+    }
+
+    runs.finish();
+    Ok(Some(runs))
+}
+
+// Файл пишется рядом под именем .part и переименовывается в конце:
+// отменённая или упавшая выгрузка не оставляет половину файла
+fn write_output(
+    records: &Output,
+    redactor: Option<&Redactor>,
+    export_format: ExportFormat,
+    output: &str,
+    progress: &ExportProgress,
+) -> io::Result<bool> {
+    let columns = match export_format {
+        ExportFormat::Csv => format::csv_columns(records.records(redactor)?)?,
+        _ => Vec::new(),
+    };
+    let write = |out: &mut dyn Write| {
+        format::write(
+            records.records(redactor)?,
+            records.len(),
+            &columns,
+            export_format,
+            &mut BufWriter::new(out),
+            progress,
+        )
+    };
+    if output == EXPORT_STDOUT {
+        return write(&mut io::stdout().lock());
+    }
+    let part = format!("{}.part", output);
+    let result = File::create(&part).and_then(|mut file| write(&mut file));
+    match result {
+        Ok(true) => fs::rename(&part, output).map(|_| true),
+        other => {
+            let _ = fs::remove_file(&part);
+            other
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::common::structs::FieldFilter;
+
+    fn log_file(tmp_dir: &TempDir, name: &str, content: &str) -> Path {
+        let file_path = tmp_dir.path().join(format!("{}.log", name));
+        fs::write(&file_path, content).unwrap();
+        Path::new(file_path.to_str().unwrap().to_string(), name.to_string())
+    }
+
+    fn request(
+        tmp_dir: &TempDir,
+        format: ExportFormat,
+        offset: usize,
+        limit: usize,
+    ) -> ExportRequest {
+        let paths = vec![
+            log_file(
+                tmp_dir,
+                "api",
+                "2026-10-17T12:00:02Z level=error msg=\"db, down\"\n2026-10-17T12:00:04Z level=info ok\n",
+            ),
+            log_file(
+                tmp_dir,
+                "web",
+                "2026-10-17T12:00:01Z level=error status=502\n2026-10-17T12:00:03Z level=info\n",
+            ),
+        ];
+        ExportRequest {
+            selection: Selection::Query(Query {
+                paths,
+                agents: Vec::new(),
+                filters: vec![Filter::Field(FieldFilter::parse("level=error"))],
+                order: Order::OrderByDate,
+                offset,
+                limit,
+            }),
+            format,
+            output: tmp_dir
+                .path()
+                .join(format!("out.{}", format.extension()))
+                .to_str()
+                .unwrap()
+                .to_string(),
+            redactor: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let tmp_dir = TempDir::new("export").expect("Не получилось создать временную директорию");
+        let progress = Arc::new(ExportProgress::default());
+
+        let csv = request(&tmp_dir, ExportFormat::Csv, 0, usize::MAX);
+        let output = csv.output.clone();
+        assert_eq!(export(csv, progress.clone()).await.unwrap(), Some(2));
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "source,timestamp,line,level,status,msg\r\n\
+             web,2026-10-17T12:00:01Z,2026-10-17T12:00:01Z level=error status=502,error,502,\r\n\
+             api,2026-10-17T12:00:02Z,\"2026-10-17T12:00:02Z level=error msg=\"\"db, down\"\"\",error,,\"db, down\"\r\n"
+        );
+        assert!(!fs::exists(format!("{}.part", output)).unwrap());
+
+        // Вторая страница по одной строке — как на экране
+        let ndjson = request(&tmp_dir, ExportFormat::Ndjson, 1, 1);
+        let output = ndjson.output.clone();
+        assert_eq!(export(ndjson, progress.clone()).await.unwrap(), Some(1));
+        let record: serde_json::Value =
+            serde_json::from_str(fs::read_to_string(&output).unwrap().trim_end()).unwrap();
+        assert_eq!(
+            record,
+            serde_json::json!({
+                "source": "api",
+                "timestamp": "2026-10-17T12:00:02Z",
+                "line": "2026-10-17T12:00:02Z level=error msg=\"db, down\"",
+                "fields": {"level": "error", "msg": "db, down"},
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_cancel() {
+        let tmp_dir = TempDir::new("export").expect("Не получилось создать временную директорию");
+        let output = tmp_dir
+            .path()
+            .join("tail.log")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let progress = Arc::new(ExportProgress::default());
+        progress.cancelled.store(true, Ordering::Relaxed);
+        let lines = ExportRequest {
+            selection: Selection::Lines(vec!["a".to_string(), "b".to_string()]),
+            format: ExportFormat::Text,
+            output: output.clone(),
+            redactor: None,
+        };
+        assert_eq!(export(lines, progress.clone()).await.unwrap(), None);
+        assert!(!fs::exists(&output).unwrap());
+        assert!(!fs::exists(format!("{}.part", output)).unwrap());
+
+        // Чтение источника тоже останавливается на отмене, не дочитывая файл
+        let query = request(&tmp_dir, ExportFormat::Text, 0, usize::MAX);
+        let output = query.output.clone();
+        assert_eq!(export(query, progress).await.unwrap(), None);
+        assert!(!fs::exists(&output).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_export_redacted() {
+        let tmp_dir = TempDir::new("export").expect("Не получилось создать временную директорию");
+        let progress = Arc::new(ExportProgress::default());
+        let mut ndjson = request(&tmp_dir, ExportFormat::Ndjson, 0, 1);
+        if let Selection::Query(query) = &mut ndjson.selection {
+            query.paths.push(log_file(
+                &tmp_dir,
+                "auth",
+                "2026-10-17T12:00:00Z level=error user=bob@example.com ip=10.1.2.3\n",
+            ));
+        }
+        ndjson.redactor = Some(Arc::new(Redactor::new(&[])));
+        let output = ndjson.output.clone();
+        assert_eq!(export(ndjson, progress).await.unwrap(), Some(1));
+        // Поля разбираются уже из замаскированной строки
+        let record: serde_json::Value =
+            serde_json::from_str(fs::read_to_string(&output).unwrap().trim_end()).unwrap();
+        assert_eq!(
+            record["line"],
+            "2026-10-17T12:00:00Z level=error user=<EMAIL> ip=<IP>"
+        );
+        assert_eq!(record["fields"]["user"], "<EMAIL>");
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::common::{enums::Order, private_dir::PrivateDir};
+use crate::export::Record;
+
+// Внешняя сортировка строк выгрузки. Строки копятся в памяти до run_bytes, потом кусок
+// сортируется и уходит во временный файл, а при записи куски сливаются. В памяти — последний
+// кусок и по строке из каждого файла
+pub struct Runs {
+    reverse: bool,
+    // Больше строк из одного куска выгрузке не понадобится
+    top: usize,
+    buffer: Vec<Record>,
+    bytes: usize,
+    run_bytes: usize,
+    // Каталог создаётся с первым куском и удаляется вместе с Runs
+    dir: Option<PrivateDir>,
+    files: Vec<PathBuf>,
+    count: usize,
+}
+
+impl Runs {
+    pub fn new(order: &Order, top: usize, run_bytes: usize) -> Self {
+        Self {
+            reverse: *order == Order::OrderByDateReverse,
+            top,
+            buffer: Vec::new(),
+            bytes: 0,
+            run_bytes,
+            dir: None,
+            files: Vec::new(),
+            count: 0,
+        }
+    }
+
+    pub fn push(&mut self, record: Record) -> io::Result<()> {
+        self.bytes += record.line.len() + record.source.as_ref().map_or(0, String::len);
+        self.buffer.push(record);
+        self.count += 1;
+        if self.bytes >= self.run_bytes {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    // Сколько строк пришло всего
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    // Последний кусок остаётся в памяти, его нужно только отсортировать
+    pub fn finish(&mut self) {
+        self.sort_buffer();
+    }
+
+    // Сортировка устойчивая: одинаковые строки остаются в порядке чтения
+    fn sort_buffer(&mut self) {
+        let reverse = self.reverse;
+        self.buffer
+            .sort_by(|a, b| compare(reverse, &a.line, &b.line));
+        self.buffer.truncate(self.top);
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.sort_buffer();
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => self
+                .dir
+                .insert(PrivateDir::create(&env::temp_dir(), "export")?),
+        };
+        let name = format!("{}.ndjson", self.files.len());
+        let mut out = BufWriter::new(dir.create_file(&name)?);
+        for record in self.buffer.drain(..) {
+            serde_json::to_writer(&mut out, &record)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        self.files.push(dir.path().join(name));
+        self.bytes = 0;
+        Ok(())
+    }
+
+    // Все строки по порядку; слияние можно начать заново, например для второго прохода CSV
+    pub fn merge(&self) -> io::Result<Merge<'_>> {
+        let mut sources: Vec<Box<dyn Iterator<Item = io::Result<Record>> + '_>> =
+            Vec::with_capacity(self.files.len() + 1);
+        for path in &self.files {
+            let lines = BufReader::new(File::open(path)?).lines();
+            sources.push(Box::new(
+                lines.map(|line| serde_json::from_str(&line?).map_err(io::Error::from)),
+            ));
+        }
+        sources.push(Box::new(self.buffer.iter().cloned().map(Ok)));
+
+        let mut heads = BinaryHeap::with_capacity(sources.len());
+        for (source, records) in sources.iter_mut().enumerate() {
+            if let Some(record) = records.next() {
+                heads.push(Head {
+                    record: record?,
+                    source,
+                    reverse: self.reverse,
+                });
+            }
+        }
+        Ok(Merge { sources, heads })
+    }
+}
+
+fn compare(reverse: bool, a: &str, b: &str) -> Ordering {
+    match reverse {
+        true => b.cmp(a),
+        false => a.cmp(b),
+    }
+}
+
+pub struct Merge<'a> {
+    sources: Vec<Box<dyn Iterator<Item = io::Result<Record>> + 'a>>,
+    heads: BinaryHeap<Head>,
+}
+
+// Первая непрочитанная строка куска. Куча отдаёт наибольший элемент, поэтому сравнение
+// обращено: сверху оказывается следующая по порядку строка, при равенстве — из раннего куска
+struct Head {
+    record: Record,
+    source: usize,
+    reverse: bool,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(self.reverse, &other.record.line, &self.record.line)
+            .then_with(|| other.source.cmp(&self.source))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+impl Iterator for Merge<'_> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.heads.pop()?;
+        match self.sources[head.source].next() {
+            Some(Ok(record)) => self.heads.push(Head {
+                record,
+                source: head.source,
+                reverse: head.reverse,
+            }),
+            Some(Err(e)) => return Some(Err(e)),
+            None => {}
+        }
+        Some(Ok(head.record))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn records(runs: &Runs) -> Vec<(String, String)> {
+        runs.merge()
+            .unwrap()
+            .map(|record| {
+                let record = record.unwrap();
+                (record.source.unwrap(), record.line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_spilled_runs() {
+        let lines = ["c", "a", "d", "b", "a", "e", "c"];
+        for order in [Order::OrderByDate, Order::OrderByDateReverse] {
+            // Строка с источником — два байта: по куску на каждые две строки, последняя в памяти
+            let mut runs = Runs::new(&order, usize::MAX, 4);
+            for (n, line) in lines.iter().enumerate() {
+                runs.push(Record {
+                    source: Some(n.to_string()),
+                    line: line.to_string(),
+                })
+                .unwrap();
+            }
+            runs.finish();
+            assert_eq!(runs.files.len(), 3);
+            let dir = runs.dir.as_ref().unwrap().path().to_path_buf();
+
+            // Одинаковые строки — в порядке чтения
+            let mut expected = vec![
+                ("1", "a"),
+                ("4", "a"),
+                ("3", "b"),
+                ("0", "c"),
+                ("6", "c"),
+                ("2", "d"),
+                ("5", "e"),
+            ];
+            if order == Order::OrderByDateReverse {
+                expected = vec![
+                    ("5", "e"),
+                    ("2", "d"),
+                    ("0", "c"),
+                    ("6", "c"),
+                    ("3", "b"),
+                    ("1", "a"),
+                    ("4", "a"),
+                ];
+            }
+            let expected: Vec<(String, String)> = expected
+                .into_iter()
+                .map(|(source, line)| (source.to_string(), line.to_string()))
+                .collect();
+            assert_eq!(records(&runs), expected);
+            // Второй проход отдаёт то же самое
+            assert_eq!(records(&runs), expected);
+
+            drop(runs);
+            assert!(!dir.exists());
+        }
+    }
+
+    #[test]
+    fn test_runs_keep_top() {
+        // Из каждого куска нужны только первые две строки
+        let mut runs = Runs::new(&Order::OrderByDate, 2, 6);
+        for line in ["e", "d", "c", "b", "a"] {
+            runs.push(Record {
+                source: Some("x".to_string()),
+                line: line.to_string(),
+            })
+            .unwrap();
+        }
+        runs.finish();
+        assert_eq!(runs.len(), 5);
+        let lines: Vec<String> = records(&runs).into_iter().map(|(_, line)| line).collect();
+        assert_eq!(lines, vec!["a", "b", "c", "d"]);
+    }
+}
@@ -0,0 +1,539 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, Metadata, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path as StdPath, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::UNIX_EPOCH,
+};
+
+use crate::common::constants::INDEX_MIN_FILE_SIZE;
+use crate::common::enums::Encoding;
+use crate::index;
+use crate::reader::encoding;
+
+// Формат файла индекса: заголовок фиксированной длины, за ним массив u64 LE
+// со смещениями начала каждой полной строки. Благодаря фиксированному размеру
+// записи смещение строки N читается одним seek, без загрузки всего индекса.
+const MAGIC: &[u8; 4] = b"LSLI";
+const VERSION: u32 = 1;
+const HEADER_LEN: u64 = 64;
+// Сколько байт перед indexed_len хешируем, чтобы заметить перезапись файла
+const TAIL_HASH_LEN: u64 = 64;
+
+// Пути, для которых сейчас идёт фоновая (пере)сборка индекса
+static BUILDS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+// Последняя ошибка фоновой сборки: путь и текст. TUI показывает её в строке индекса
+static BUILD_ERROR: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileKey {
+    pub inode: u64,
+    pub size: u64,
+    pub mtime_ns: u64,
+}
+
+impl FileKey {
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+
+        let mtime_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Self {
+            inode,
+            size: metadata.len(),
+            mtime_ns,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Freshness {
+    Fresh,
+    // Файл дописан в конец, индекс можно продолжить с indexed_len
+    Grown,
+    // Файл заменён, обрезан или перезаписан — индекс нужно собрать заново
+    Stale,
+}
+
+#[derive(Debug, Clone)]
+struct Header {
+    key: FileKey,
+    indexed_len: u64,
+    line_count: u64,
+    tail_hash: u64,
+    sorted: bool,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER_LEN as usize] {
+        let mut buf = [0u8; HEADER_LEN as usize];
+        buf[0..4].copy_from_slice(MAGIC);
+        buf[4..8].copy_from_slice(&VERSION.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.key.inode.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.key.size.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.key.mtime_ns.to_le_bytes());
+        buf[32..40].copy_from_slice(&self.indexed_len.to_le_bytes());
+        buf[40..48].copy_from_slice(&self.line_count.to_le_bytes());
+        buf[48..56].copy_from_slice(&self.tail_hash.to_le_bytes());
+        buf[56] = self.sorted as u8;
+        buf
+    }
+
+    fn decode(buf: &[u8; HEADER_LEN as usize]) -> Option<Self> {
+        if &buf[0..4] != MAGIC || u32::from_le_bytes(buf[4..8].try_into().ok()?) != VERSION {
+            return None;
+        }
+        let read_u64 = |at: usize| u64::from_le_bytes(buf[at..at + 8].try_into().unwrap());
+        Some(Self {
+            key: FileKey {
+                inode: read_u64(8),
+                size: read_u64(16),
+                mtime_ns: read_u64(24),
+            },
+            indexed_len: read_u64(32),
+            line_count: read_u64(40),
+            tail_hash: read_u64(48),
+            sorted: buf[56] != 0,
+        })
+    }
+}
+
+pub struct LineIndex {
+    index: File,
+    header: Header,
+}
+
+impl LineIndex {
+    // Количество полных (завершённых '\n') строк
+    pub fn line_count(&self) -> usize {
+        self.header.line_count as usize
+    }
+
+    // Последняя строка файла не завершена переводом строки
+    pub fn has_partial_tail(&self) -> bool {
+        self.header.key.size > self.header.indexed_len
+    }
+
+    // Все строки с учётом незавершённой последней
+    pub fn total_lines(&self) -> usize {
+        self.line_count() + self.has_partial_tail() as usize
+    }
+
+    // Состояние файла, для которого построен индекс
+    pub fn key(&self) -> &FileKey {
+        &self.header.key
+    }
+
+    // Строки файла уже идут в лексикографическом порядке (как после sort())
+    pub fn is_sorted(&self) -> bool {
+        self.header.sorted
+    }
+
+    pub fn freshness(&self, key: &FileKey) -> Freshness {
+        let header = &self.header;
+        if key.inode != header.key.inode || key.size < header.indexed_len {
+            return Freshness::Stale;
+        }
+        if key.size == header.key.size && key.mtime_ns == header.key.mtime_ns {
+            return Freshness::Fresh;
+        }
+        if key.size > header.key.size {
+            return Freshness::Grown;
+        }
+        Freshness::Stale
+    }
+
+    pub fn offset_of(&mut self, line: usize) -> io::Result<u64> {
+        if line >= self.line_count() {
+            return Ok(self.header.indexed_len);
+        }
+        let mut buf = [0u8; 8];
+        self.index
+            .seek(SeekFrom::Start(HEADER_LEN + 8 * line as u64))?;
+        self.index.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    // Читает count строк, начиная со строки start, сразу переходя к нужному смещению
+    pub fn read_lines(
+        &mut self,
+        source: &str,
+        encoding: Encoding,
+        start: usize,
+        count: usize,
+    ) -> io::Result<Vec<String>> {
+        if start >= self.total_lines() || count == 0 {
+            return Ok(Vec::new());
+        }
+        let offset = self.offset_of(start)?;
+        let mut file = File::open(source)?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        let reader = BufReader::new(file.take(self.header.key.size - offset));
+        let mut lines = Vec::with_capacity(std::cmp::min(count, 1000));
+        for line in encoding::lines(reader, encoding, offset == 0)?.take(count) {
+            lines.push(line?);
+        }
+        Ok(lines)
+    }
+}
+
+pub fn index_path(dir: &StdPath, source: &str) -> PathBuf {
+    let canonical = fs::canonicalize(source)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| source.to_string());
+    dir.join(format!("{:016x}.lines", fnv1a(canonical.as_bytes())))
+}
+
+// Открывает индекс файла, не проверяя его актуальность
+pub fn open(dir: &StdPath, source: &str) -> io::Result<Option<LineIndex>> {
+    let mut index = match File::open(index_path(dir, source)) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut buf = [0u8; HEADER_LEN as usize];
+    if index.read_exact(&mut buf).is_err() {
+        return Ok(None);
+    }
+    Ok(Header::decode(&buf).map(|header| LineIndex { index, header }))
+}
+
+// Полная сборка индекса: пишем во временный файл и атомарно переименовываем
+pub fn build(dir: &StdPath, source: &str) -> io::Result<LineIndex> {
+    fs::create_dir_all(dir)?;
+    let target = index_path(dir, source);
+    let tmp = target.with_extension("lines.tmp");
+
+    let mut file = File::open(source)?;
+    let key = FileKey::from_metadata(&file.metadata()?);
+
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    out.write_all(&[0u8; HEADER_LEN as usize])?;
+    let scanned = scan(&mut file, 0, key.size, None, &mut out)?;
+
+    let header = Header {
+        tail_hash: tail_hash(&mut file, scanned.indexed_len)?,
+        key,
+        indexed_len: scanned.indexed_len,
+        line_count: scanned.lines,
+        sorted: scanned.sorted,
+    };
+    let mut index = out.into_inner().map_err(|e| e.into_error())?;
+    index.seek(SeekFrom::Start(0))?;
+    index.write_all(&header.encode())?;
+    index.sync_all()?;
+    drop(index);
+    fs::rename(&tmp, &target)?;
+
+    let index = File::open(&target)?;
+    Ok(LineIndex { index, header })
+}
+
+// Дописывает в индекс строки, появившиеся после indexed_len.
+// None — файл перезаписан, индекс нужно собрать заново
+pub fn extend(dir: &StdPath, source: &str, current: LineIndex) -> io::Result<Option<LineIndex>> {
+    let mut file = File::open(source)?;
+    let key = FileKey::from_metadata(&file.metadata()?);
+    let mut header = current.header;
+
+    if tail_hash(&mut file, header.indexed_len)? != header.tail_hash {
+        return Ok(None);
+    }
+
+    let mut index = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(index_path(dir, source))?;
+
+    let previous = if header.line_count > 0 {
+        let mut buf = [0u8; 8];
+        index.seek(SeekFrom::Start(HEADER_LEN + 8 * (header.line_count - 1)))?;
+        index.read_exact(&mut buf)?;
+        let start = u64::from_le_bytes(buf);
+        let mut line = vec![0u8; (header.indexed_len - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut line)?;
+        Some(strip_line_end(&line).to_vec())
+    } else {
+        None
+    };
+
+    index.seek(SeekFrom::Start(HEADER_LEN + 8 * header.line_count))?;
+    let mut out = BufWriter::new(index);
+    let scanned = scan(&mut file, header.indexed_len, key.size, previous, &mut out)?;
+    let mut index = out.into_inner().map_err(|e| e.into_error())?;
+
+    header.sorted &= scanned.sorted;
+    header.line_count += scanned.lines;
+    header.indexed_len = scanned.indexed_len;
+    header.tail_hash = tail_hash(&mut file, scanned.indexed_len)?;
+    header.key = key;
+
+    // Заголовок пишем последним: до этого читатели видят старый, но корректный индекс
+    index.seek(SeekFrom::Start(0))?;
+    index.write_all(&header.encode())?;
+    index.sync_data()?;
+    Ok(Some(LineIndex { index, header }))
+}
+
+// Индекс для Page-режима: актуальный или None, если индекс отсутствует, устарел или
+// отстал от дописанного файла. Тогда сборка или продолжение индекса уходит в фон,
+// а чтение идёт по-старому: страница не ждёт индексации дописанного хвоста
+pub fn lookup(source: &str) -> Option<LineIndex> {
+    let metadata = fs::metadata(source).ok()?;
+    if !metadata.is_file() || metadata.len() < INDEX_MIN_FILE_SIZE {
+        return None;
+    }
+    let dir = &index::dir();
+    let key = FileKey::from_metadata(&metadata);
+
+    match open(dir, source) {
+        Ok(Some(index)) => match index.freshness(&key) {
+            Freshness::Fresh => Some(index),
+            Freshness::Grown => {
+                extend_in_background(source, index);
+                None
+            }
+            Freshness::Stale => {
+                rebuild_in_background(source);
+                None
+            }
+        },
+        _ => {
+            rebuild_in_background(source);
+            None
+        }
+    }
+}
+
+pub fn rebuild_in_background(source: &str) {
+    in_background(source, |dir, source| build(dir, source).map(|_| ()));
+}
+
+// Продолжает индекс дописанного файла; перезаписанный файл индексируется заново
+fn extend_in_background(source: &str, current: LineIndex) {
+    in_background(source, move |dir, source| match extend(dir, source, current)? {
+        Some(_) => Ok(()),
+        None => build(dir, source).map(|_| ()),
+    });
+}
+
+// Одна фоновая сборка на файл; её ошибку TUI показывает в строке индекса
+fn in_background(
+    source: &str,
+    job: impl FnOnce(&StdPath, &str) -> io::Result<()> + Send + 'static,
+) {
+    if !begin_build(source) {
+        return;
+    }
+    let source = source.to_string();
+    std::thread::spawn(move || {
+        let res = job(&index::dir(), &source);
+        let mut error = BUILD_ERROR.lock().unwrap();
+        match res {
+            Err(e) => *error = Some((source.clone(), e.to_string())),
+            Ok(_) if error.as_ref().is_some_and(|(path, _)| *path == source) => *error = None,
+            Ok(_) => {}
+        }
+        drop(error);
+        finish_build(&source);
+    });
+}
+
+pub fn build_error() -> Option<String> {
+    BUILD_ERROR
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|(source, e)| format!("line index for {}: {}", source, e))
+}
+
+fn begin_build(source: &str) -> bool {
+    let builds = BUILDS.get_or_init(|| Mutex::new(HashSet::new()));
+    builds.lock().unwrap().insert(source.to_string())
+}
+
+fn finish_build(source: &str) {
+    if let Some(builds) = BUILDS.get() {
+        builds.lock().unwrap().remove(source);
+    }
+}
+
+struct Scanned {
+    indexed_len: u64,
+    lines: u64,
+    sorted: bool,
+}
+
+// Проходит файл от from до end и пишет смещения полных строк в out
+fn scan(
+    file: &mut File,
+    from: u64,
+    end: u64,
+    mut previous: Option<Vec<u8>>,
+    out: &mut impl Write,
+) -> io::Result<Scanned> {
+    file.seek(SeekFrom::Start(from))?;
+    let mut reader = BufReader::with_capacity(1 << 16, file.take(end.saturating_sub(from)));
+
+    let mut position = from;
+    let mut lines = 0;
+    let mut sorted = true;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf)?;
+        if read == 0 || buf.last() != Some(&b'\n') {
+            break;
+        }
+        out.write_all(&position.to_le_bytes())?;
+
+        let line = strip_line_end(&buf);
+        if let Some(prev) = &previous
+            && line < prev.as_slice()
+        {
+            sorted = false;
+        }
+        previous = Some(line.to_vec());
+
+        position += read as u64;
+        lines += 1;
+    }
+    Ok(Scanned {
+        indexed_len: position,
+        lines,
+        sorted,
+    })
+}
+
+// Та же обрезка, что делает BufRead::lines(): "\n" или "\r\n"
+fn strip_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+pub(crate) fn tail_hash(file: &mut File, indexed_len: u64) -> io::Result<u64> {
+    let start = indexed_len.saturating_sub(TAIL_HASH_LEN);
+    let mut buf = vec![0u8; (indexed_len - start) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut buf)?;
+    Ok(fnv1a(&buf))
+}
+
+// Стабильный между сборками хеш (DefaultHasher такой гарантии не даёт)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::OpenOptions, io::Write};
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn write_log(path: &StdPath, lines: &[&str]) {
+        let mut f = File::create(path).expect("Не удалось создать временный файл");
+        for l in lines {
+            writeln!(f, "{}", l).expect("Не удалось записать строку в файл");
+        }
+    }
+
+    #[test]
+    fn test_build_and_seek() {
+        let tmp_dir =
+            TempDir::new("line_index").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("test.log");
+        write_log(&log, &["a-1", "a-2", "a-3", "a-4"]);
+        let source = log.to_str().unwrap();
+
+        let mut index = build(tmp_dir.path(), source).expect("Не удалось построить индекс");
+        assert_eq!(index.line_count(), 4);
+        assert!(index.is_sorted());
+        assert!(!index.has_partial_tail());
+        assert_eq!(index.offset_of(2).unwrap(), 8);
+        assert_eq!(index.read_lines(source, Encoding::Utf8Lossy, 1, 2).unwrap(), vec!["a-2", "a-3"]);
+        assert_eq!(index.read_lines(source, Encoding::Utf8Lossy, 3, 10).unwrap(), vec!["a-4"]);
+        assert!(index.read_lines(source, Encoding::Utf8Lossy, 4, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reopen_and_detect_unsorted() {
+        let tmp_dir =
+            TempDir::new("line_index").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("test.log");
+        write_log(&log, &["b", "a", "c"]);
+        let source = log.to_str().unwrap();
+
+        build(tmp_dir.path(), source).expect("Не удалось построить индекс");
+        let index = open(tmp_dir.path(), source)
+            .unwrap()
+            .expect("Индекс должен читаться с диска");
+        assert_eq!(index.line_count(), 3);
+        assert!(!index.is_sorted());
+
+        let key = FileKey::from_metadata(&fs::metadata(&log).unwrap());
+        assert_eq!(index.freshness(&key), Freshness::Fresh);
+    }
+
+    #[test]
+    fn test_extend_on_append() {
+        let tmp_dir =
+            TempDir::new("line_index").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("test.log");
+        write_log(&log, &["a-1", "a-2"]);
+        let source = log.to_str().unwrap();
+        build(tmp_dir.path(), source).expect("Не удалось построить индекс");
+
+        let mut f = OpenOptions::new().append(true).open(&log).unwrap();
+        write!(f, "a-3\na-4\npartial").unwrap();
+        drop(f);
+
+        let index = open(tmp_dir.path(), source).unwrap().unwrap();
+        let key = FileKey::from_metadata(&fs::metadata(&log).unwrap());
+        assert_eq!(index.freshness(&key), Freshness::Grown);
+
+        let mut index = extend(tmp_dir.path(), source, index)
+            .expect("Не удалось дописать индекс")
+            .expect("Дописанный файл не должен считаться перезаписанным");
+        assert_eq!(index.line_count(), 4);
+        assert!(index.has_partial_tail());
+        assert_eq!(index.total_lines(), 5);
+        assert!(index.is_sorted());
+        assert_eq!(
+            index.read_lines(source, Encoding::Utf8Lossy, 3, 5).unwrap(),
+            vec!["a-4", "partial"]
+        );
+    }
+
+    #[test]
+    fn test_stale_on_truncate() {
+        let tmp_dir =
+            TempDir::new("line_index").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("test.log");
+        write_log(&log, &["a-1", "a-2", "a-3"]);
+        let source = log.to_str().unwrap();
+        build(tmp_dir.path(), source).expect("Не удалось построить индекс");
+
+        write_log(&log, &["b"]);
+        let index = open(tmp_dir.path(), source).unwrap().unwrap();
+        let key = FileKey::from_metadata(&fs::metadata(&log).unwrap());
+        assert_eq!(index.freshness(&key), Freshness::Stale);
+    }
+}
@@ -0,0 +1,19 @@
+use std::{env, path::PathBuf};
+
+use crate::common::constants::INDEX_DIR;
+
+pub mod lines;
+pub mod text;
+
+// Индексы лежат в $XDG_CACHE_HOME/logscraper/index (или ~/.cache), как профили в каталоге
+// настроек: они общие для всех рабочих каталогов. Без HOME и XDG_CACHE_HOME — в рабочем каталоге
+pub fn dir() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map_or_else(
+            || PathBuf::from(INDEX_DIR),
+            |cache| cache.join("logscraper").join("index"),
+        )
+}
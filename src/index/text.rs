@@ -0,0 +1,934 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path as StdPath, PathBuf},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use regex_syntax::hir::literal::Extractor;
+
+use crate::common::{
+    constants::{DEFAULT_DATE_FORMAT, TEXT_INDEX_RUN_POSTINGS},
+    fields,
+    enums::{Encoding, Filter, LogFormat},
+    structs::{DateFilter, DateFilterType, Path},
+};
+use crate::index::{
+    self,
+    lines::{FileKey, tail_hash},
+};
+use crate::reader::encoding;
+
+// Полнотекстовый индекс: файл режется на блоки по BLOCK_LINES строк, для
+// каждого токена хранится список блоков, где он встречается, а для блока —
+// байтовый диапазон и интервал времени. Фильтры Search/Regex/Date по индексу
+// отбрасывают блоки, в которых совпадений заведомо нет; оставшиеся блоки
+// читаются и фильтруются как обычно, так что результат не меняется.
+// Токены поиска, которые могут оказаться серединой или концом токена строки
+// (Suffix/Contains), индекс не отбирает: перебор всего словаря не быстрее чтения файла.
+const MAGIC: &[u8; 4] = b"LSTI";
+const VERSION: u32 = 2;
+pub const BLOCK_LINES: usize = 256;
+
+// Загруженные индексы: ключ — файл индекса, значение — его mtime и содержимое
+type Cache = Mutex<HashMap<PathBuf, (SystemTime, Arc<TextIndex>)>>;
+static CACHE: OnceLock<Cache> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    start: u64,
+    end: u64,
+    // Минимальная и максимальная дата строк блока (мс), если хоть одна распарсилась
+    time: Option<(i64, i64)>,
+}
+
+pub struct TextIndex {
+    key: FileKey,
+    indexed_len: u64,
+    tail_hash: u64,
+    blocks: Vec<Block>,
+    // Отсортированы по токену, чтобы префиксный поиск шёл бинарным поиском
+    tokens: Vec<(String, Vec<u32>)>,
+}
+
+// Прогресс фоновой сборки индекса для отображения в TUI
+#[derive(Default)]
+pub struct IndexProgress {
+    pub running: AtomicBool,
+    pub total_bytes: AtomicU64,
+    pub done_bytes: AtomicU64,
+    pub current: Mutex<String>,
+    pub error: Mutex<Option<String>>,
+}
+
+impl IndexProgress {
+    pub fn percent(&self) -> u64 {
+        let total = self.total_bytes.load(Ordering::Relaxed);
+        if total == 0 {
+            return 100;
+        }
+        self.done_bytes.load(Ordering::Relaxed).min(total) * 100 / total
+    }
+}
+
+// Ограничение на токен из поисковой строки: где он должен встретиться в токене строки лога
+#[derive(Debug, PartialEq)]
+enum Term {
+    Exact(String),
+    Prefix(String),
+    Suffix(String),
+    Contains(String),
+}
+
+impl TextIndex {
+    // Байтовые диапазоны, которые нужно прочитать для заданных фильтров.
+    // None — индекс не помогает, файл читается целиком.
+    pub fn ranges(&self, filters: &[Filter]) -> Option<Vec<(u64, u64)>> {
+        let blocks = self.candidate_blocks(filters)?;
+
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for id in blocks {
+            let block = &self.blocks[id];
+            match ranges.last_mut() {
+                Some(last) if last.1 == block.start => last.1 = block.end,
+                _ => ranges.push((block.start, block.end)),
+            }
+        }
+        // Всё, что дописано после сборки индекса, читаем без отбора
+        match ranges.last_mut() {
+            Some(last) if last.1 == self.indexed_len => last.1 = u64::MAX,
+            _ => ranges.push((self.indexed_len, u64::MAX)),
+        }
+        Some(ranges)
+    }
+
+    pub fn candidate_blocks(&self, filters: &[Filter]) -> Option<Vec<usize>> {
+        let mut result: Option<Vec<usize>> = None;
+        for filter in filters {
+            let blocks = match filter {
+                Filter::Search(f) => self.search_blocks(&f.substr),
+                Filter::Regex(f) => self.regex_blocks(&f.pattern),
+                Filter::Date(f) => self.date_blocks(f),
+                // Имя и значение поля стоят в строке как есть
+                Filter::Field(f) => self.search_blocks(&format!("{}={}", f.name, f.value)),
+            };
+            if let Some(blocks) = blocks {
+                result = Some(match result {
+                    Some(prev) => intersect(&prev, &blocks),
+                    None => blocks,
+                });
+            }
+        }
+        result
+    }
+
+    fn search_blocks(&self, substr: &str) -> Option<Vec<usize>> {
+        let terms = terms(substr);
+        if terms.is_empty() {
+            return None;
+        }
+        let mut result: Option<Vec<usize>> = None;
+        for term in terms {
+            let Some(blocks) = self.term_blocks(&term) else {
+                continue;
+            };
+            result = Some(match result {
+                Some(prev) => intersect(&prev, &blocks),
+                None => blocks,
+            });
+        }
+        result
+    }
+
+    // Каждое совпадение регулярки начинается с одного из литералов,
+    // значит строка обязана содержать хотя бы один из них
+    fn regex_blocks(&self, pattern: &str) -> Option<Vec<usize>> {
+        let hir = regex_syntax::Parser::new().parse(pattern).ok()?;
+        let seq = Extractor::new().extract(&hir);
+        let literals = seq.literals()?;
+
+        let mut result = Vec::new();
+        for literal in literals {
+            let literal = std::str::from_utf8(literal.as_bytes()).ok()?;
+            let blocks = self.search_blocks(literal)?;
+            result = union(&result, &blocks);
+        }
+        Some(result)
+    }
+
+    // Индекс знает только даты в формате по умолчанию
+    fn date_blocks(&self, f: &DateFilter) -> Option<Vec<usize>> {
+        if f.date_format != DEFAULT_DATE_FORMAT {
+            return None;
+        }
+        let start = f.date_start.map(|d| d.timestamp_millis());
+        let finish = f.date_finish.map(|d| d.timestamp_millis());
+        let blocks = self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| {
+                // Время блока не определилось — читаем его, пусть решает сам фильтр
+                let Some((min, max)) = block.time else {
+                    return true;
+                };
+                let after_ok = start.is_none_or(|s| max >= s);
+                let before_ok = finish.is_none_or(|e| min <= e);
+                match f.filter_type {
+                    DateFilterType::Before => before_ok,
+                    DateFilterType::After => after_ok,
+                    DateFilterType::Between => after_ok && before_ok,
+                }
+            })
+            .map(|(i, _)| i)
+            .collect();
+        Some(blocks)
+    }
+
+    // None — термин отбирается только перебором всего словаря, такие читаем без индекса
+    fn term_blocks(&self, term: &Term) -> Option<Vec<usize>> {
+        let postings = |i: usize| self.tokens[i].1.iter().map(|b| *b as usize);
+        match term {
+            Term::Exact(t) => Some(match self.tokens.binary_search_by(|(tok, _)| tok.as_str().cmp(t)) {
+                Ok(i) => postings(i).collect(),
+                Err(_) => Vec::new(),
+            }),
+            Term::Prefix(t) => {
+                let from = self
+                    .tokens
+                    .partition_point(|(tok, _)| tok.as_str() < t.as_str());
+                let mut result = Vec::new();
+                for i in from..self.tokens.len() {
+                    if !self.tokens[i].0.starts_with(t.as_str()) {
+                        break;
+                    }
+                    result = union(&result, &postings(i).collect::<Vec<_>>());
+                }
+                Some(result)
+            }
+            Term::Suffix(_) | Term::Contains(_) => None,
+        }
+    }
+}
+
+pub fn index_path(dir: &StdPath, source: &str) -> PathBuf {
+    crate::index::lines::index_path(dir, source).with_extension("text")
+}
+
+pub fn build(
+    dir: &StdPath,
+    source: &str,
+    encoding: Encoding,
+    progress: &IndexProgress,
+) -> io::Result<()> {
+    build_with(dir, source, encoding, progress, TEXT_INDEX_RUN_POSTINGS)
+}
+
+// Списки блоков копятся в памяти, пока записей в них меньше run_postings, а потом
+// сбрасываются отсортированным куском во временный файл. В конце куски сливаются в индекс,
+// так что память сборки не растёт вместе с файлом
+fn build_with(
+    dir: &StdPath,
+    source: &str,
+    encoding: Encoding,
+    progress: &IndexProgress,
+    run_postings: usize,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut file = File::open(source)?;
+    let key = FileKey::from_metadata(&file.metadata()?);
+
+    let mut reader = BufReader::with_capacity(1 << 16, file.try_clone()?.take(key.size));
+    let mut postings: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut posting_count = 0;
+    let mut runs = Runs {
+        base: index_path(dir, source),
+        files: Vec::new(),
+    };
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut position: u64 = 0;
+    let mut lines_in_block = 0;
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf)?;
+        // Незавершённая строка ещё может дописываться — оставляем её вне индекса
+        if read == 0 || buf.last() != Some(&b'\n') {
+            break;
+        }
+        if lines_in_block == 0 {
+            blocks.push(Block {
+                start: position,
+                end: position,
+                time: None,
+            });
+        }
+        let block_id = (blocks.len() - 1) as u32;
+        let block = blocks.last_mut().unwrap();
+
+        // BOM не попадает в первый токен
+        let raw = if position == 0 {
+            buf.strip_prefix(&[0xEF, 0xBB, 0xBF][..]).unwrap_or(&buf)
+        } else {
+            &buf
+        };
+        let line = encoding::decode(encoding, encoding::strip_terminator(encoding, raw));
+        for token in tokenize(&line.to_lowercase()) {
+            let list = postings.entry(token.to_string()).or_default();
+            if list.last() != Some(&block_id) {
+                list.push(block_id);
+                posting_count += 1;
+            }
+        }
+        if let Some(ts) = line_time(&line) {
+            block.time = Some(match block.time {
+                Some((min, max)) => (min.min(ts), max.max(ts)),
+                None => (ts, ts),
+            });
+        }
+
+        position += read as u64;
+        block.end = position;
+        lines_in_block = (lines_in_block + 1) % BLOCK_LINES;
+        progress
+            .done_bytes
+            .fetch_add(read as u64, Ordering::Relaxed);
+        if posting_count >= run_postings {
+            runs.write(std::mem::take(&mut postings))?;
+            posting_count = 0;
+        }
+    }
+
+    let tail_hash = tail_hash(&mut file, position)?;
+    if runs.files.is_empty() {
+        let tokens = sorted(postings).into_iter().map(Ok);
+        return save(dir, source, &key, position, tail_hash, &blocks, tokens);
+    }
+    runs.write(postings)?;
+    let merge = runs.merge()?;
+    save(dir, source, &key, position, tail_hash, &blocks, merge)
+}
+
+fn sorted(postings: HashMap<String, Vec<u32>>) -> Vec<(String, Vec<u32>)> {
+    let mut tokens: Vec<(String, Vec<u32>)> = postings.into_iter().collect();
+    tokens.sort_by(|a, b| a.0.cmp(&b.0));
+    tokens
+}
+
+// Отсортированные куски словаря во временных файлах рядом с индексом; удаляются вместе с Runs
+struct Runs {
+    base: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+impl Runs {
+    fn write(&mut self, postings: HashMap<String, Vec<u32>>) -> io::Result<()> {
+        let path = self
+            .base
+            .with_extension(format!("text.run{}", self.files.len()));
+        self.files.push(path.clone());
+        let mut out = BufWriter::new(File::create(&path)?);
+        for (token, blocks) in sorted(postings) {
+            write_token(&mut out, &token, &blocks)?;
+        }
+        out.flush()
+    }
+
+    fn merge(&self) -> io::Result<Merge> {
+        let mut readers = Vec::with_capacity(self.files.len());
+        let mut heads = BinaryHeap::new();
+        for (run, path) in self.files.iter().enumerate() {
+            let mut reader = BufReader::new(File::open(path)?);
+            if let Some((token, blocks)) = read_token(&mut reader)? {
+                heads.push(Reverse((token, run, blocks)));
+            }
+            readers.push(reader);
+        }
+        Ok(Merge { readers, heads })
+    }
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in &self.files {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// Слияние кусков: токены по порядку, списки блоков одного токена склеиваются в порядке
+// кусков — номера блоков в них растут, а общий блок на стыке встречается дважды
+struct Merge {
+    readers: Vec<BufReader<File>>,
+    heads: BinaryHeap<Reverse<(String, usize, Vec<u32>)>>,
+}
+
+impl Merge {
+    fn advance(&mut self, run: usize) -> io::Result<()> {
+        if let Some((token, blocks)) = read_token(&mut self.readers[run])? {
+            self.heads.push(Reverse((token, run, blocks)));
+        }
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> io::Result<Option<(String, Vec<u32>)>> {
+        let Some(Reverse((token, run, mut blocks))) = self.heads.pop() else {
+            return Ok(None);
+        };
+        self.advance(run)?;
+        while self.heads.peek().is_some_and(|Reverse((next, _, _))| *next == token) {
+            let Reverse((_, run, more)) = self.heads.pop().unwrap();
+            let skip = (blocks.last() == more.first()) as usize;
+            blocks.extend_from_slice(&more[skip..]);
+            self.advance(run)?;
+        }
+        Ok(Some((token, blocks)))
+    }
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<(String, Vec<u32>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+// Сборка индексов для набора файлов; запускается в отдельном потоке из TUI
+// UTF-16 пропускается: блоки индекса режутся по байту '\n'
+pub fn build_all(sources: Vec<Path>, progress: Arc<IndexProgress>) {
+    let sources: Vec<(String, Encoding)> = sources
+        .into_iter()
+        // Контейнерные логи сканируются целиком, индекс им не нужен
+        .filter(|s| s.format == LogFormat::Plain)
+        .filter_map(|s| {
+            let encoding = encoding::effective(&s.path, s.encoding).ok()?;
+            encoding.is_ascii_compatible().then_some((s.path, encoding))
+        })
+        .collect();
+    let total = sources
+        .iter()
+        .filter_map(|(s, _)| fs::metadata(s).ok())
+        .map(|m| m.len())
+        .sum();
+    progress.total_bytes.store(total, Ordering::Relaxed);
+    progress.done_bytes.store(0, Ordering::Relaxed);
+    *progress.error.lock().unwrap() = None;
+    progress.running.store(true, Ordering::Relaxed);
+
+    for (source, encoding) in sources {
+        *progress.current.lock().unwrap() = source.clone();
+        if let Err(e) = build(&index::dir(), &source, encoding, &progress) {
+            *progress.error.lock().unwrap() = Some(format!("{}: {}", source, e));
+        }
+    }
+    progress.current.lock().unwrap().clear();
+    progress.running.store(false, Ordering::Relaxed);
+}
+
+pub fn load(dir: &StdPath, source: &str) -> io::Result<Option<TextIndex>> {
+    let bytes = match fs::read(index_path(dir, source)) {
+        Ok(b) => b,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(decode(&bytes))
+}
+
+// Индекс для чтения страницы: только если файл с момента сборки лишь дописывался.
+// Сам по себе индекс не пересобирается — это делает пользователь из TUI.
+pub fn lookup(source: &str) -> Option<Arc<TextIndex>> {
+    let dir = index::dir();
+    let path = index_path(&dir, source);
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let cached = cache
+        .lock()
+        .unwrap()
+        .get(&path)
+        .filter(|(m, _)| *m == modified)
+        .map(|(_, index)| index.clone());
+    let index = match cached {
+        Some(index) => index,
+        None => {
+            let index = Arc::new(load(&dir, source).ok()??);
+            cache
+                .lock()
+                .unwrap()
+                .insert(path, (modified, index.clone()));
+            index
+        }
+    };
+
+    let mut file = File::open(source).ok()?;
+    let key = FileKey::from_metadata(&file.metadata().ok()?);
+    if key.inode != index.key.inode
+        || key.size < index.indexed_len
+        || tail_hash(&mut file, index.indexed_len).ok()? != index.tail_hash
+    {
+        return None;
+    }
+    Some(index)
+}
+
+fn tokenize(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !is_token_char(c))
+        .filter(|t| !t.is_empty())
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Разбирает подстроку поиска на токены. Токен, упирающийся в край подстроки,
+// в строке лога может быть частью более длинного токена.
+fn terms(substr: &str) -> Vec<Term> {
+    let lower = substr.to_lowercase();
+    let mut terms = Vec::new();
+    let mut start = None;
+    for (i, c) in lower
+        .char_indices()
+        .chain(std::iter::once((lower.len(), ' ')))
+    {
+        let inside = i < lower.len() && is_token_char(c);
+        match (start, inside) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let token = lower[s..i].to_string();
+                terms.push(match (s == 0, i == lower.len()) {
+                    (false, false) => Term::Exact(token),
+                    (true, false) => Term::Suffix(token),
+                    (false, true) => Term::Prefix(token),
+                    (true, true) => Term::Contains(token),
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    terms
+}
+
+// Разбор тот же, что у фильтра по дате, иначе индекс отбросит подходящие строки
+fn line_time(line: &str) -> Option<i64> {
+    fields::timestamp(line).map(|d| d.timestamp_millis())
+}
+
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+fn union(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let next = match (a.get(i), b.get(j)) {
+            (Some(x), Some(y)) if x == y => {
+                i += 1;
+                j += 1;
+                *x
+            }
+            (Some(x), Some(y)) if x < y => {
+                i += 1;
+                *x
+            }
+            (_, Some(y)) => {
+                j += 1;
+                *y
+            }
+            (Some(x), None) => {
+                i += 1;
+                *x
+            }
+            (None, None) => unreachable!(),
+        };
+        out.push(next);
+    }
+    out
+}
+
+// Бинарный формат: заголовок, таблица блоков, словарь с дельта-кодированными
+// номерами блоков (varint). Пишется во временный файл и переименовывается;
+// словарь приходит потоком, число токенов дописывается в заголовок в конце.
+fn save(
+    dir: &StdPath,
+    source: &str,
+    key: &FileKey,
+    indexed_len: u64,
+    tail_hash: u64,
+    blocks: &[Block],
+    tokens: impl Iterator<Item = io::Result<(String, Vec<u32>)>>,
+) -> io::Result<()> {
+    let target = index_path(dir, source);
+    let tmp = target.with_extension("text.tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    for v in [
+        key.inode,
+        key.size,
+        key.mtime_ns,
+        indexed_len,
+        tail_hash,
+        blocks.len() as u64,
+        0,
+    ] {
+        out.write_all(&v.to_le_bytes())?;
+    }
+    for block in blocks {
+        out.write_all(&block.start.to_le_bytes())?;
+        out.write_all(&block.end.to_le_bytes())?;
+        let (has_time, (min, max)) = match block.time {
+            Some(t) => (1u8, t),
+            None => (0u8, (0, 0)),
+        };
+        out.write_all(&[has_time])?;
+        out.write_all(&min.to_le_bytes())?;
+        out.write_all(&max.to_le_bytes())?;
+    }
+    let mut token_count: u64 = 0;
+    for entry in tokens {
+        let (token, blocks) = entry?;
+        write_token(&mut out, &token, &blocks)?;
+        token_count += 1;
+    }
+    let mut file = out.into_inner().map_err(|e| e.into_error())?;
+    // MAGIC, VERSION и шесть u64 перед числом токенов
+    file.seek(SeekFrom::Start(4 + 4 + 8 * 6))?;
+    file.write_all(&token_count.to_le_bytes())?;
+    file.flush()?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(tmp, target)
+}
+
+fn write_token(out: &mut impl Write, token: &str, blocks: &[u32]) -> io::Result<()> {
+    write_varint(out, token.len() as u64)?;
+    out.write_all(token.as_bytes())?;
+    write_varint(out, blocks.len() as u64)?;
+    let mut prev = 0;
+    for b in blocks {
+        write_varint(out, (*b - prev) as u64)?;
+        prev = *b;
+    }
+    Ok(())
+}
+
+// Обратная к write_token для кусков сборки; None — кусок дочитан
+fn read_token(reader: &mut impl BufRead) -> io::Result<Option<(String, Vec<u32>)>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let len = read_varint(reader)? as usize;
+    let mut token = vec![0; len];
+    reader.read_exact(&mut token)?;
+    let token = String::from_utf8(token).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let n = read_varint(reader)? as usize;
+    let mut blocks = Vec::with_capacity(n);
+    let mut prev = 0u32;
+    for _ in 0..n {
+        prev += read_varint(reader)? as u32;
+        blocks.push(prev);
+    }
+    Ok(Some((token, blocks)))
+}
+
+fn decode(bytes: &[u8]) -> Option<TextIndex> {
+    let mut cur = Cursor { bytes, pos: 0 };
+    if cur.take(4)? != MAGIC || u32::from_le_bytes(cur.take(4)?.try_into().ok()?) != VERSION {
+        return None;
+    }
+    let key = FileKey {
+        inode: cur.u64()?,
+        size: cur.u64()?,
+        mtime_ns: cur.u64()?,
+    };
+    let indexed_len = cur.u64()?;
+    let tail_hash = cur.u64()?;
+    let block_count = cur.u64()? as usize;
+    let token_count = cur.u64()? as usize;
+
+    let mut blocks = Vec::with_capacity(block_count.min(1 << 20));
+    for _ in 0..block_count {
+        let start = cur.u64()?;
+        let end = cur.u64()?;
+        let has_time = cur.take(1)?[0] != 0;
+        let min = cur.u64()? as i64;
+        let max = cur.u64()? as i64;
+        blocks.push(Block {
+            start,
+            end,
+            time: has_time.then_some((min, max)),
+        });
+    }
+    let mut tokens = Vec::with_capacity(token_count.min(1 << 20));
+    for _ in 0..token_count {
+        let len = cur.varint()? as usize;
+        let token = String::from_utf8(cur.take(len)?.to_vec()).ok()?;
+        let n = cur.varint()? as usize;
+        let mut list = Vec::with_capacity(n.min(block_count));
+        let mut prev = 0u32;
+        for _ in 0..n {
+            prev += cur.varint()? as u32;
+            list.push(prev);
+        }
+        tokens.push((token, list));
+    }
+    Some(TextIndex {
+        key,
+        indexed_len,
+        tail_hash,
+        blocks,
+        tokens,
+    })
+}
+
+fn write_varint(out: &mut impl Write, mut v: u64) -> io::Result<()> {
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+        v |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "varint too long"))
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            v |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(v);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use chrono::{DateTime, Utc};
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::common::structs::{RegexFilter, SearchFilter};
+
+    fn build_index(lines: &[String]) -> (TempDir, String, TextIndex) {
+        let tmp_dir =
+            TempDir::new("text_index").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("test.log");
+        let mut f = File::create(&log).expect("Не удалось создать временный файл");
+        for l in lines {
+            writeln!(f, "{}", l).expect("Не удалось записать строку в файл");
+        }
+        let source = log.to_str().unwrap().to_string();
+        build(tmp_dir.path(), &source, Encoding::Utf8Lossy, &IndexProgress::default())
+            .expect("Не удалось построить индекс");
+        let index = load(tmp_dir.path(), &source)
+            .unwrap()
+            .expect("Индекс должен читаться с диска");
+        (tmp_dir, source, index)
+    }
+
+    fn search(substr: &str) -> Filter {
+        Filter::Search(SearchFilter {
+            substr: substr.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_terms() {
+        assert_eq!(
+            terms("connection refused by"),
+            vec![
+                Term::Suffix("connection".to_string()),
+                Term::Exact("refused".to_string()),
+                Term::Prefix("by".to_string()),
+            ]
+        );
+        assert_eq!(terms("ERR"), vec![Term::Contains("err".to_string())]);
+        assert!(terms("::").is_empty());
+    }
+
+    #[test]
+    fn test_search_prefilter() {
+        // Блок 0 — только info, блок 1 содержит ошибку
+        let mut lines: Vec<String> = (0..BLOCK_LINES)
+            .map(|i| format!("info line {}", i))
+            .collect();
+        lines.push("ERROR disk full".to_string());
+        let (_tmp, _, index) = build_index(&lines);
+        assert_eq!(index.blocks.len(), 2);
+
+        // Подстрока внутри токена — без индекса
+        assert_eq!(index.candidate_blocks(&[search("ERR")]), None);
+        assert_eq!(
+            index.candidate_blocks(&[search("disk full")]),
+            Some(vec![1])
+        );
+        assert_eq!(index.candidate_blocks(&[search("missing")]), None);
+        assert_eq!(index.candidate_blocks(&[search("disk missing")]), Some(vec![]));
+        assert_eq!(index.candidate_blocks(&[search("::")]), None);
+    }
+
+    #[test]
+    fn test_regex_prefilter() {
+        let mut lines: Vec<String> = (0..BLOCK_LINES)
+            .map(|i| format!("info line {}", i))
+            .collect();
+        lines.push("connection refused by peer after 30s".to_string());
+        let (_tmp, _, index) = build_index(&lines);
+
+        let re = |p: &str| {
+            Filter::Regex(RegexFilter {
+                pattern: p.to_string(),
+            })
+        };
+        assert_eq!(
+            index.candidate_blocks(&[re(r"connection (refused|reset) by")]),
+            Some(vec![1])
+        );
+        assert_eq!(index.candidate_blocks(&[re(r"time(out|d)")]), None);
+        assert_eq!(index.candidate_blocks(&[re(r"\d+s")]), None);
+    }
+
+    #[test]
+    fn test_date_prefilter_and_roundtrip() {
+        let mut lines: Vec<String> = (0..BLOCK_LINES)
+            .map(|_| "2021-01-01T00:00:00+00:00 old".to_string())
+            .collect();
+        lines.push("2025-01-01T00:00:00+00:00 new".to_string());
+        let (tmp, source, _) = build_index(&lines);
+
+        let index = load(tmp.path(), &source)
+            .unwrap()
+            .expect("Индекс должен читаться с диска");
+        let after = DateTime::parse_from_str("2024-01-01T00:00:00+00:00", DEFAULT_DATE_FORMAT)
+            .unwrap()
+            .with_timezone(&Utc);
+        let f = Filter::Date(DateFilter {
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            date_start: Some(after),
+            date_finish: None,
+            filter_type: DateFilterType::After,
+        });
+        assert_eq!(
+            index.candidate_blocks(std::slice::from_ref(&f)),
+            Some(vec![1])
+        );
+
+        let ranges = index.ranges(&[f]).unwrap();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].1, u64::MAX);
+    }
+
+    #[test]
+    fn test_date_prefilter_rfc3339() {
+        // Блок 0 — время в формате контейнерных логов, блок 1 — строки без времени
+        let mut lines: Vec<String> = (0..BLOCK_LINES)
+            .map(|i| format!("2026-10-17T12:00:{:02}.5Z container line", i % 60))
+            .collect();
+        lines.push("no timestamp here".to_string());
+        let (tmp, source, index) = build_index(&lines);
+
+        let f = Filter::Date(DateFilter {
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            date_start: Some(
+                DateTime::parse_from_rfc3339("2026-10-17T12:00:30Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            date_finish: None,
+            filter_type: DateFilterType::After,
+        });
+        assert_eq!(
+            index.candidate_blocks(std::slice::from_ref(&f)),
+            Some(vec![0, 1])
+        );
+
+        // По индексу и без него находятся одни и те же строки
+        let content = fs::read(&source).unwrap();
+        let mut indexed = Vec::new();
+        for (start, end) in index.ranges(std::slice::from_ref(&f)).unwrap() {
+            let end = (end.min(content.len() as u64)) as usize;
+            let chunk = String::from_utf8_lossy(&content[start as usize..end]).to_string();
+            indexed.extend(chunk.lines().map(str::to_string).filter(|l| f.is_include(l)));
+        }
+        let unindexed: Vec<String> = lines.into_iter().filter(|l| f.is_include(l)).collect();
+        assert!(!unindexed.is_empty());
+        assert_eq!(indexed, unindexed);
+        drop(tmp);
+    }
+
+    #[test]
+    fn test_build_in_runs() {
+        // Сборка кусками по несколько записей даёт тот же индекс, что и в памяти
+        let lines: Vec<String> = (0..BLOCK_LINES * 5)
+            .map(|i| format!("worker {} request {} status {}", i % 7, i, i % 3))
+            .collect();
+        let (tmp, source, whole) = build_index(&lines);
+        let runs_dir = tmp.path().join("runs");
+        build_with(&runs_dir, &source, Encoding::Utf8Lossy, &IndexProgress::default(), 10)
+            .unwrap();
+        let chunked = load(&runs_dir, &source).unwrap().unwrap();
+
+        assert_eq!(chunked.tokens, whole.tokens);
+        assert_eq!(chunked.blocks.len(), whole.blocks.len());
+        assert!(fs::read_dir(&runs_dir).unwrap().count() == 1);
+    }
+}
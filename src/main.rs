@@ -7,17 +7,71 @@ use ratatui::{
     },
     prelude::CrosstermBackend,
 };
+use clap::Parser;
 use std::io;
 use tokio::sync::mpsc::unbounded_channel;
 
-use crate::{common::structs::Memory, tui::app::App};
+use crate::{
+    cli::{Cli, Command},
+    common::{
+        constants::{AGENT_TOKEN_ENV, STDIN_PATH},
+        profile,
+        structs::{AgentSource, Log, Memory, Path},
+    },
+    reader::stdin,
+    tui::app::App,
+};
 
+mod alert;
+mod bookmark;
+mod cli;
+mod cluster;
 mod common;
+mod export;
+mod index;
 mod reader;
+mod redact;
+mod remote;
+mod stats;
 mod tui;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Подкоманды работают без терминала: для скриптов и пайпов
+    let cli = Cli::parse();
+    // Профиль выбирается до первой загрузки памяти — и для подкоманд тоже
+    if !profile::is_valid(&cli.profile) {
+        eprintln!(
+            "logscraper: invalid profile name '{}': use letters, digits, - and _",
+            cli.profile
+        );
+        std::process::exit(cli::EXIT_ERROR);
+    }
+    profile::select(&cli.profile);
+    if let Some(command) = cli.command {
+        let code = match command {
+            Command::Query(args) => cli::query::run(args).await,
+            Command::Follow(args) => cli::follow::run(args).await,
+            Command::Serve(args) => cli::serve::run(args).await,
+            Command::Agent(args) => cli::agent::run(args).await,
+            Command::Export(args) => cli::export::run(args).await,
+        };
+        std::process::exit(code);
+    }
+    if !cli.agents.is_empty() && cli.token.is_none() {
+        eprintln!("logscraper: --agent needs --token or {}", AGENT_TOKEN_ENV);
+        std::process::exit(cli::EXIT_ERROR);
+    }
+
+    // `kubectl logs -f pod | logscraper`: stdin становится источником, а клавиатура
+    // читается из /dev/tty (crossterm с use-dev-tty)
+    let piped = stdin::is_piped();
+    if piped {
+        stdin::start();
+    }
+    // SIGTERM завершает run_app как обычный выход: терминал возвращается ниже, спул убирает guard
+    let _spool = stdin::CleanupGuard;
+
     enable_raw_mode()?;
     let mut stderr = io::stderr();
     execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
@@ -25,13 +79,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(stderr);
     let mut terminal = Terminal::new(backend)?;
 
-    let (tx, rx) = unbounded_channel::<String>();
+    let (tx, rx) = unbounded_channel::<Log>();
 
     // Load memory (paths and filters)
-    let memory = Memory::load()?;
+    let mut memory = Memory::load()?;
+    if piped {
+        memory.add_path(Path::new(STDIN_PATH.to_string(), "stdin".to_string()));
+    }
+    let token = cli.token.unwrap_or_default();
+    memory
+        .agents
+        .extend(cli.agents.iter().map(|a| AgentSource::parse(a, token.clone())));
 
     let mut app = App::new(rx, memory);
-    tui::run_app(&mut terminal, &mut app, tx).await?;
+    tui::run_app(&mut terminal, &mut app, tx, cli::follow::shutdown_signal()).await?;
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
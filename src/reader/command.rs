@@ -0,0 +1,337 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    process::{ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+    task,
+};
+
+use crate::common::{
+    constants::{COMMAND_BACKOFF_MAX, COMMAND_BACKOFF_MIN, COMMAND_STABLE_PERIOD},
+    enums::{Encoding, Filter},
+    structs::{CommandSource, Log},
+};
+use crate::reader::{encoding, throughput::Throughput};
+
+// Состояние команд-источников для TUI, по имени источника
+#[derive(Default)]
+pub struct CommandStatus {
+    states: Mutex<HashMap<String, CommandState>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandState {
+    Running,
+    Exited {
+        code: Option<i32>,
+        restart_in: Duration,
+    },
+    Failed {
+        error: String,
+        restart_in: Duration,
+    },
+}
+
+impl fmt::Display for CommandState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandState::Running => write!(f, "running"),
+            // Без кода — убита сигналом
+            CommandState::Exited {
+                code: Some(code),
+                restart_in,
+            } => write!(f, "exited {}, restart in {}s", code, restart_in.as_secs()),
+            CommandState::Exited {
+                code: None,
+                restart_in,
+            } => write!(f, "killed, restart in {}s", restart_in.as_secs()),
+            CommandState::Failed { error, restart_in } => {
+                write!(f, "{}, restart in {}s", error, restart_in.as_secs())
+            }
+        }
+    }
+}
+
+impl CommandStatus {
+    pub fn set(&self, name: &str, state: CommandState) {
+        self.states.lock().unwrap().insert(name.to_string(), state);
+    }
+
+    pub fn get(&self, name: &str) -> Option<CommandState> {
+        self.states.lock().unwrap().get(name).cloned()
+    }
+}
+
+// Вся группа процессов команды (sh и то, что он запустил) убивается,
+// когда задача чтения завершается или отменяется. Номер группы — pid sh, и он
+// не переиспользуется, пока sh не собран: поэтому группу гасим до child.wait(),
+// а после него guard уже ничего не шлёт
+struct GroupGuard {
+    pgid: i32,
+    done: bool,
+}
+
+impl GroupGuard {
+    fn kill(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        // Safety: killpg только отправляет сигнал, память не затрагивается
+        unsafe {
+            libc::killpg(self.pgid, libc::SIGKILL);
+        }
+    }
+}
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+// Ждёт выхода sh, не забирая его статус (WNOWAIT): зомби держит номер группы за нами
+async fn wait_exited(pid: i32) -> io::Result<()> {
+    task::spawn_blocking(move || loop {
+        // Safety: siginfo_t — обычная C-структура, нули для неё допустимы
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        // Safety: waitid только заполняет info
+        let res = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if res == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+// Запускает команду и перезапускает её с нарастающей паузой, пока жив получатель
+pub async fn run_command(
+    source: CommandSource,
+    filters: Arc<Vec<Filter>>,
+    tx: UnboundedSender<Log>,
+    status: Arc<CommandStatus>,
+    throughput: Arc<Throughput>,
+) {
+    let mut backoff = COMMAND_BACKOFF_MIN;
+    loop {
+        let started = Instant::now();
+        let result = run_once(&source, &filters, &tx, &status, &throughput).await;
+        if tx.is_closed() {
+            return;
+        }
+        if started.elapsed() >= COMMAND_STABLE_PERIOD {
+            backoff = COMMAND_BACKOFF_MIN;
+        }
+        let state = match result {
+            Ok(exit) => CommandState::Exited {
+                code: exit.code(),
+                restart_in: backoff,
+            },
+            Err(e) => CommandState::Failed {
+                error: e.to_string(),
+                restart_in: backoff,
+            },
+        };
+        status.set(&source.name, state);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(COMMAND_BACKOFF_MAX);
+    }
+}
+
+async fn run_once(
+    source: &CommandSource,
+    filters: &[Filter],
+    tx: &UnboundedSender<Log>,
+    status: &CommandStatus,
+    throughput: &Throughput,
+) -> io::Result<ExitStatus> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&source.command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut group = child.id().map(|pid| GroupGuard {
+        pgid: pid as i32,
+        done: false,
+    });
+    status.set(&source.name, CommandState::Running);
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    // Буферы живут между итерациями select: недочитанная строка не теряется
+    let (mut out_buf, mut err_buf) = (Vec::new(), Vec::new());
+    let (mut out_open, mut err_open) = (true, true);
+
+    while out_open || err_open {
+        let (read, buf, open) = tokio::select! {
+            read = stdout.read_until(b'\n', &mut out_buf), if out_open => (read?, &mut out_buf, &mut out_open),
+            read = stderr.read_until(b'\n', &mut err_buf), if err_open => (read?, &mut err_buf, &mut err_open),
+        };
+        if read == 0 {
+            *open = false;
+        }
+        // Строка целиком или последний хвост без '\n'
+        if !buf.is_empty() && (read == 0 || buf.ends_with(b"\n")) {
+            let line = encoding::decode(
+                Encoding::Utf8Lossy,
+                encoding::strip_terminator(Encoding::Utf8Lossy, buf),
+            );
+            buf.clear();
+            let passed = filters.iter().all(|f| f.is_include(&line));
+            throughput.record(&source.name, passed);
+            if !passed {
+                continue;
+            }
+            let log = Log {
+                date_time: Utc::now(),
+                data: line,
+                source_name: source.name.clone(),
+            };
+            if tx.send(log).is_err() {
+                // Получатель ушёл — команду гасит GroupGuard
+                break;
+            }
+        }
+    }
+    if let Some(group) = &mut group {
+        // Получатель ушёл — не ждём, пока sh закончит сам
+        if out_open || err_open {
+            group.kill();
+        }
+        // sh ещё не собран, группа наша: гасим оставшиеся в ней фоновые процессы
+        wait_exited(group.pgid).await?;
+        group.kill();
+    }
+    child.wait().await
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+    use crate::common::structs::SearchFilter;
+
+    fn source(command: &str) -> CommandSource {
+        CommandSource {
+            name: "test".to_string(),
+            command: command.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_output_and_exit_code() {
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let status = Arc::new(CommandStatus::default());
+        let filters = Arc::new(vec![Filter::Search(SearchFilter {
+            substr: "keep".to_string(),
+        })]);
+        let handle = tokio::spawn(run_command(
+            source("echo keep out; echo drop; echo keep err >&2; printf 'keep tail'; exit 3"),
+            filters,
+            tx,
+            status.clone(),
+            Arc::new(Throughput::default()),
+        ));
+
+        let mut lines = Vec::new();
+        for _ in 0..3 {
+            let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+                .await
+                .expect("Команда не прислала строку")
+                .unwrap();
+            assert_eq!(log.source_name, "test");
+            lines.push(log.data);
+        }
+        lines.sort();
+        assert_eq!(lines, vec!["keep err", "keep out", "keep tail"]);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            status.get("test"),
+            Some(CommandState::Exited {
+                code: Some(3),
+                restart_in: COMMAND_BACKOFF_MIN,
+            })
+        );
+        handle.abort();
+    }
+
+    // Зомби тоже отвечает на kill(pid, 0), поэтому смотрим состояние в /proc
+    #[cfg(target_os = "linux")]
+    fn is_alive(pid: i32) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_abort_kills_process_group() {
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let status = Arc::new(CommandStatus::default());
+        // Внук sh: его не убьёт kill_on_drop, только сигнал группе
+        let handle = tokio::spawn(run_command(
+            source("sleep 1000 & echo $!; wait"),
+            Arc::new(Vec::new()),
+            tx,
+            status,
+            Arc::new(Throughput::default()),
+        ));
+        let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("Команда не прислала pid")
+            .unwrap();
+        let pid: i32 = log.data.parse().expect("Ожидался pid");
+        assert!(is_alive(pid));
+
+        handle.abort();
+        let _ = handle.await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!is_alive(pid));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_exit_kills_background_group() {
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        // sh выходит сам, а фоновый sleep с отвязанным выводом остаётся в группе
+        let status = run_once(
+            &source("sleep 1000 >/dev/null 2>&1 & echo $!"),
+            &[],
+            &tx,
+            &CommandStatus::default(),
+            &Throughput::default(),
+        )
+        .await
+        .unwrap();
+        assert!(status.success());
+        let pid: i32 = rx.recv().await.unwrap().data.parse().expect("Ожидался pid");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!is_alive(pid));
+    }
+}
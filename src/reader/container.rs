@@ -0,0 +1,319 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::Path as StdPath,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+
+use crate::common::{enums::LogFormat, structs::Path};
+
+// Логи контейнеров (Docker json-file и CRI) разворачиваются в "<время> stream=<поток> <текст>":
+// время идёт первым, поэтому сортировка строк и датовые фильтры работают как для обычных логов.
+// Время пишется с наносекундами и "Z" — строки одной ширины сортируются по времени.
+
+// Одна запись контейнерного лога; partial — строка продолжится в следующей записи
+#[derive(Debug, PartialEq)]
+struct Entry {
+    time: DateTime<Utc>,
+    stream: String,
+    text: String,
+    partial: bool,
+}
+
+#[derive(Deserialize)]
+struct DockerLine {
+    log: String,
+    #[serde(default)]
+    stream: String,
+    time: String,
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// Docker режет длинные строки по 16 КиБ: у всех частей, кроме последней, нет '\n' в конце
+fn parse_docker(raw: &str) -> Option<Entry> {
+    let line: DockerLine = serde_json::from_str(raw).ok()?;
+    let (text, partial) = match line.log.strip_suffix('\n') {
+        Some(text) => (text.strip_suffix('\r').unwrap_or(text), false),
+        None => (line.log.as_str(), true),
+    };
+    Some(Entry {
+        time: parse_time(&line.time)?,
+        stream: line.stream,
+        text: text.to_string(),
+        partial,
+    })
+}
+
+fn parse_cri(raw: &str) -> Option<Entry> {
+    let mut parts = raw.splitn(4, ' ');
+    let time = parse_time(parts.next()?)?;
+    let stream = parts.next()?;
+    let partial = match parts.next()? {
+        "P" => true,
+        "F" => false,
+        _ => return None,
+    };
+    Some(Entry {
+        time,
+        stream: stream.to_string(),
+        text: parts.next().unwrap_or("").to_string(),
+        partial,
+    })
+}
+
+// Собирает части строк отдельно по потокам: stdout и stderr перемежаются
+#[derive(Debug)]
+pub struct Reassembler {
+    format: LogFormat,
+    pending: HashMap<String, (DateTime<Utc>, String)>,
+}
+
+impl Reassembler {
+    pub fn new(format: LogFormat) -> Self {
+        Self {
+            format,
+            pending: HashMap::new(),
+        }
+    }
+
+    // Готовая строка или None, если это часть ещё не законченной.
+    // Строка не в формате файла возвращается как есть.
+    pub fn push(&mut self, raw: String) -> Option<String> {
+        let entry = match self.format {
+            // Журнал читается записями целиком, а не построчно
+            LogFormat::Plain | LogFormat::Journal => return Some(raw),
+            LogFormat::DockerJson => parse_docker(&raw),
+            LogFormat::Cri => parse_cri(&raw),
+        };
+        let Some(entry) = entry else {
+            return Some(raw);
+        };
+        let (time, mut text) = self
+            .pending
+            .remove(&entry.stream)
+            .unwrap_or((entry.time, String::new()));
+        text.push_str(&entry.text);
+        if entry.partial {
+            self.pending.insert(entry.stream, (time, text));
+            return None;
+        }
+        Some(render(time, &entry.stream, &text))
+    }
+
+    // Части, которые так и не закончились (файл оборвался)
+    pub fn flush(&mut self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .pending
+            .drain()
+            .map(|(stream, (time, text))| render(time, &stream, &text))
+            .collect();
+        lines.sort();
+        lines
+    }
+}
+
+fn render(time: DateTime<Utc>, stream: &str, text: &str) -> String {
+    format!(
+        "{} stream={} {}",
+        time.to_rfc3339_opts(SecondsFormat::Nanos, true),
+        stream,
+        text
+    )
+}
+
+// Разворачивает строки файла в формате format
+pub fn lines<I>(decoded: I, format: LogFormat) -> Lines<I>
+where
+    I: Iterator<Item = io::Result<String>>,
+{
+    Lines {
+        decoded,
+        reassembler: Reassembler::new(format),
+        tail: VecDeque::new(),
+        done: false,
+    }
+}
+
+pub struct Lines<I> {
+    decoded: I,
+    reassembler: Reassembler,
+    tail: VecDeque<String>,
+    done: bool,
+}
+
+impl<I: Iterator<Item = io::Result<String>>> Iterator for Lines<I> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.decoded.next() {
+                Some(Ok(raw)) => {
+                    if let Some(line) = self.reassembler.push(raw) {
+                        return Some(Ok(line));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.done = true;
+                    self.tail = self.reassembler.flush().into();
+                }
+            }
+        }
+        self.tail.pop_front().map(Ok)
+    }
+}
+
+// Формат по первой строке файла: json-file начинается с '{'
+pub fn detect_format(path: &StdPath) -> io::Result<LogFormat> {
+    let mut first = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut first)?;
+    Ok(match first.trim_start().starts_with('{') {
+        true => LogFormat::DockerJson,
+        false => LogFormat::Cri,
+    })
+}
+
+// Логи подов kubelet: <root>/<namespace>_<pod>_<uid>/<container>/<restart>.log.
+// Источник называется "namespace/pod/container"; файлы прошлых перезапусков — с "#<номер>".
+pub fn discover_pods(root: &str) -> io::Result<Vec<Path>> {
+    let mut found = Vec::new();
+    for pod_dir in fs::read_dir(root)? {
+        let pod_dir = pod_dir?;
+        let dir_name = pod_dir.file_name().to_string_lossy().into_owned();
+        let mut parts = dir_name.splitn(3, '_');
+        let (Some(namespace), Some(pod), Some(_uid)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if !pod_dir.file_type()?.is_dir() {
+            continue;
+        }
+        for container_dir in fs::read_dir(pod_dir.path())? {
+            let container_dir = container_dir?;
+            if !container_dir.file_type()?.is_dir() {
+                continue;
+            }
+            let container = container_dir.file_name().to_string_lossy().into_owned();
+            // Сжатые и ротированные копии (0.log.20261017-120000.gz) пропускаем
+            let mut restarts: Vec<(u32, String)> = fs::read_dir(container_dir.path())?
+                .filter_map(|file| {
+                    let file = file.ok()?;
+                    let name = file.file_name();
+                    let restart = name.to_str()?.strip_suffix(".log")?.parse().ok()?;
+                    Some((restart, file.path().to_string_lossy().into_owned()))
+                })
+                .collect();
+            restarts.sort();
+            let latest = restarts.last().map(|(restart, _)| *restart);
+            for (restart, file) in restarts {
+                let mut name = format!("{}/{}/{}", namespace, pod, container);
+                if Some(restart) != latest {
+                    name = format!("{}#{}", name, restart);
+                }
+                let mut source = Path::new(file.clone(), name);
+                source.format = detect_format(StdPath::new(&file))?;
+                found.push(source);
+            }
+        }
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(found)
+}
+
+#[cfg(test)]
+mod test {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn unwrap_all(format: LogFormat, raw: &[&str]) -> Vec<String> {
+        lines(raw.iter().map(|r| Ok(r.to_string())), format)
+            .map(|l| l.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_cri_partial_lines() {
+        let got = unwrap_all(
+            LogFormat::Cri,
+            &[
+                "2026-10-17T12:00:01.123Z stdout P first ",
+                "2026-10-17T12:00:01.200+03:00 stderr F oops",
+                "2026-10-17T12:00:01.300Z stdout F half",
+                "2026-10-17T12:00:02Z stdout F ",
+                "not cri at all",
+            ],
+        );
+        assert_eq!(
+            got,
+            vec![
+                "2026-10-17T09:00:01.200000000Z stream=stderr oops",
+                "2026-10-17T12:00:01.123000000Z stream=stdout first half",
+                "2026-10-17T12:00:02.000000000Z stream=stdout ",
+                "not cri at all",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_docker_partial_lines() {
+        let got = unwrap_all(
+            LogFormat::DockerJson,
+            &[
+                r#"{"log":"long ","stream":"stdout","time":"2026-10-17T12:00:01.000000001Z"}"#,
+                r#"{"log":"line\n","stream":"stdout","time":"2026-10-17T12:00:01.000000002Z"}"#,
+                r#"{"log":"crlf\r\n","stream":"stderr","time":"2026-10-17T12:00:03Z"}"#,
+                r#"{"log":"cut","stream":"stderr","time":"2026-10-17T12:00:04Z"}"#,
+            ],
+        );
+        assert_eq!(
+            got,
+            vec![
+                "2026-10-17T12:00:01.000000001Z stream=stdout long line",
+                "2026-10-17T12:00:03.000000000Z stream=stderr crlf",
+                // Незаконченная часть в конце файла не теряется
+                "2026-10-17T12:00:04.000000000Z stream=stderr cut",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_pods() {
+        let tmp_dir = TempDir::new("pods").expect("Не получилось создать временную директорию");
+        let root = tmp_dir.path();
+        let nginx = root.join("default_web-7d4b9_0f1e2d3c-aaaa/nginx");
+        let sidecar = root.join("kube-system_dns-1_1234/coredns");
+        fs::create_dir_all(&nginx).unwrap();
+        fs::create_dir_all(&sidecar).unwrap();
+        fs::write(nginx.join("0.log"), "2026-10-17T12:00:01Z stdout F a\n").unwrap();
+        fs::write(nginx.join("1.log"), "2026-10-17T12:00:02Z stdout F b\n").unwrap();
+        fs::write(nginx.join("0.log.20261017-120000.gz"), "").unwrap();
+        fs::write(
+            sidecar.join("0.log"),
+            r#"{"log":"c\n","stream":"stdout","time":"2026-10-17T12:00:03Z"}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("not-a-pod")).unwrap();
+
+        let found = discover_pods(root.to_str().unwrap()).unwrap();
+        let got: Vec<(&str, LogFormat)> =
+            found.iter().map(|p| (p.name.as_str(), p.format)).collect();
+        assert_eq!(
+            got,
+            vec![
+                ("default/web-7d4b9/nginx", LogFormat::Cri),
+                ("default/web-7d4b9/nginx#0", LogFormat::Cri),
+                ("kube-system/dns-1/coredns", LogFormat::DockerJson),
+            ]
+        );
+        assert!(found[0].path.ends_with("nginx/1.log"));
+    }
+}
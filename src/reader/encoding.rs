@@ -0,0 +1,371 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Read},
+};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::common::enums::Encoding;
+
+// Символ, которым показываются байты, не поддающиеся декодированию
+pub const REPLACEMENT: char = char::REPLACEMENT_CHARACTER;
+
+const BOM_UTF8: &[u8] = &[0xEF, 0xBB, 0xBF];
+const BOM_UTF16LE: &[u8] = &[0xFF, 0xFE];
+const BOM_UTF16BE: &[u8] = &[0xFE, 0xFF];
+
+// Windows-1251, байты 0x80..=0xBF (0xC0..=0xFF — подряд А..я)
+const CP1251_HIGH: [char; 64] = [
+    'Ђ',
+    'Ѓ',
+    '‚',
+    'ѓ',
+    '„',
+    '…',
+    '†',
+    '‡',
+    '€',
+    '‰',
+    'Љ',
+    '‹',
+    'Њ',
+    'Ќ',
+    'Ћ',
+    'Џ', //
+    'ђ',
+    '‘',
+    '’',
+    '“',
+    '”',
+    '•',
+    '–',
+    '—',
+    REPLACEMENT,
+    '™',
+    'љ',
+    '›',
+    'њ',
+    'ќ',
+    'ћ',
+    'џ', //
+    '\u{A0}',
+    'Ў',
+    'ў',
+    'Ј',
+    '¤',
+    'Ґ',
+    '¦',
+    '§',
+    'Ё',
+    '©',
+    'Є',
+    '«',
+    '¬',
+    '\u{AD}',
+    '®',
+    'Ї', //
+    '°',
+    '±',
+    'І',
+    'і',
+    'ґ',
+    'µ',
+    '¶',
+    '·',
+    'ё',
+    '№',
+    'є',
+    '»',
+    'ј',
+    'Ѕ',
+    'ѕ',
+    'ї',
+];
+
+// Кодировка по BOM и длина BOM
+pub fn detect_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    if bytes.starts_with(BOM_UTF8) {
+        Some((Encoding::Utf8Lossy, BOM_UTF8.len()))
+    } else if bytes.starts_with(BOM_UTF16LE) {
+        Some((Encoding::Utf16Le, BOM_UTF16LE.len()))
+    } else if bytes.starts_with(BOM_UTF16BE) {
+        Some((Encoding::Utf16Be, BOM_UTF16BE.len()))
+    } else {
+        None
+    }
+}
+
+// Кодировка, которой реально написан файл: BOM важнее настройки пути
+pub fn effective(path: &str, configured: Encoding) -> io::Result<Encoding> {
+    let mut head = [0u8; 3];
+    let mut file = File::open(path)?;
+    let mut read = 0;
+    while read < head.len() {
+        let n = file.read(&mut head[read..])?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    Ok(detect_bom(&head[..read]).map_or(configured, |(e, _)| e))
+}
+
+pub fn decode(encoding: Encoding, raw: &[u8]) -> String {
+    match encoding {
+        Encoding::Utf8Lossy => String::from_utf8_lossy(raw).into_owned(),
+        Encoding::Latin1 => raw.iter().map(|b| *b as char).collect(),
+        Encoding::Cp1251 => raw
+            .iter()
+            .map(|b| match b {
+                0x00..=0x7F => *b as char,
+                0x80..=0xBF => CP1251_HIGH[(b - 0x80) as usize],
+                _ => char::from_u32(0x0410 + (b - 0xC0) as u32).unwrap_or(REPLACEMENT),
+            })
+            .collect(),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = raw.chunks(2).map(|pair| match (pair, encoding) {
+                ([lo, hi], Encoding::Utf16Le) => Ok(u16::from_le_bytes([*lo, *hi])),
+                ([hi, lo], _) => Ok(u16::from_be_bytes([*hi, *lo])),
+                // Нечётный байт в конце — обрезанный символ
+                _ => Err(()),
+            });
+            let mut out = String::with_capacity(raw.len() / 2);
+            let mut valid = Vec::with_capacity(raw.len() / 2);
+            for unit in units {
+                match unit {
+                    Ok(u) => valid.push(u),
+                    Err(()) => {
+                        out.extend(
+                            char::decode_utf16(valid.drain(..)).map(|c| c.unwrap_or(REPLACEMENT)),
+                        );
+                        out.push(REPLACEMENT);
+                    }
+                }
+            }
+            out.extend(char::decode_utf16(valid).map(|c| c.unwrap_or(REPLACEMENT)));
+            out
+        }
+    }
+}
+
+// Отрезает "\n" или "\r\n" с учётом ширины символа кодировки
+pub fn strip_terminator(encoding: Encoding, raw: &[u8]) -> &[u8] {
+    let (lf, cr): (&[u8], &[u8]) = match encoding {
+        Encoding::Utf16Le => (&[0x0A, 0x00], &[0x0D, 0x00]),
+        Encoding::Utf16Be => (&[0x00, 0x0A], &[0x00, 0x0D]),
+        _ => (b"\n", b"\r"),
+    };
+    match raw.strip_suffix(lf) {
+        Some(line) => line.strip_suffix(cr).unwrap_or(line),
+        None => raw,
+    }
+}
+
+// Закончилась ли строка переводом строки в данной кодировке
+pub fn is_terminated(encoding: Encoding, raw: &[u8]) -> bool {
+    match encoding {
+        Encoding::Utf16Le => raw.len().is_multiple_of(2) && raw.ends_with(&[0x0A, 0x00]),
+        Encoding::Utf16Be => raw.len().is_multiple_of(2) && raw.ends_with(&[0x00, 0x0A]),
+        _ => raw.ends_with(b"\n"),
+    }
+}
+
+// Дочитывает в buf сырые байты одной строки вместе с переводом строки.
+// Для UTF-16 байт 0x0A может оказаться половиной другого символа — тогда читаем дальше.
+pub fn read_raw_line(
+    reader: &mut impl BufRead,
+    encoding: Encoding,
+    buf: &mut Vec<u8>,
+) -> io::Result<usize> {
+    let before = buf.len();
+    loop {
+        // 0 байт или строка без '\n' — конец файла
+        if reader.read_until(b'\n', buf)? == 0 || buf.last() != Some(&b'\n') {
+            break;
+        }
+        if encoding == Encoding::Utf16Le && buf.len() % 2 == 1 {
+            // Нужен старший байт символа: 0x00 — перевод строки, иначе это часть другого символа
+            let next = reader.fill_buf()?.first().copied();
+            let Some(byte) = next else {
+                break;
+            };
+            reader.consume(1);
+            buf.push(byte);
+        }
+        if is_terminated(encoding, buf) {
+            break;
+        }
+    }
+    Ok(buf.len() - before)
+}
+
+// То же для асинхронного чтения в tail
+pub async fn read_raw_line_async(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    encoding: Encoding,
+    buf: &mut Vec<u8>,
+) -> io::Result<usize> {
+    let before = buf.len();
+    loop {
+        // 0 байт или строка без '\n' — конец файла
+        if reader.read_until(b'\n', buf).await? == 0 || buf.last() != Some(&b'\n') {
+            break;
+        }
+        if encoding == Encoding::Utf16Le && buf.len() % 2 == 1 {
+            let next = reader.fill_buf().await?.first().copied();
+            let Some(byte) = next else {
+                break;
+            };
+            reader.consume(1);
+            buf.push(byte);
+        }
+        if is_terminated(encoding, buf) {
+            break;
+        }
+    }
+    Ok(buf.len() - before)
+}
+
+pub struct DecodedLines<R> {
+    reader: R,
+    encoding: Encoding,
+    buf: Vec<u8>,
+    // Сколько байт прочитано от начала читателя
+    pos: u64,
+    // Пропущенный BOM считается частью первой строки
+    bom: u64,
+}
+
+impl<R> DecodedLines<R> {
+    // Смещение начала следующей строки от начала читателя
+    pub fn offset(&self) -> u64 {
+        self.pos
+    }
+}
+
+impl<R: BufRead> Iterator for DecodedLines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buf.clear();
+        match read_raw_line(&mut self.reader, self.encoding, &mut self.buf) {
+            Ok(0) => None,
+            Ok(read) => {
+                self.pos += read as u64 + std::mem::take(&mut self.bom);
+                Some(Ok(decode(
+                    self.encoding,
+                    strip_terminator(self.encoding, &self.buf),
+                )))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+// Аналог BufRead::lines(), который не падает на невалидных байтах.
+// at_start — читатель стоит в начале файла, и BOM нужно пропустить.
+pub fn lines<R: BufRead>(
+    mut reader: R,
+    encoding: Encoding,
+    at_start: bool,
+) -> io::Result<DecodedLines<R>> {
+    let mut encoding = encoding;
+    let mut bom = 0;
+    if at_start && let Some((detected, len)) = detect_bom(reader.fill_buf()?) {
+        reader.consume(len);
+        encoding = detected;
+        bom = len as u64;
+    }
+    Ok(DecodedLines {
+        reader,
+        encoding,
+        buf: Vec::new(),
+        pos: 0,
+        bom,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn collect(bytes: &[u8], encoding: Encoding) -> Vec<String> {
+        lines(Cursor::new(bytes.to_vec()), encoding, true)
+            .unwrap()
+            .map(|l| l.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_utf8_lossy_does_not_fail() {
+        let res = collect(b"ok\nbad \xFF byte\r\nlast", Encoding::Utf8Lossy);
+        assert_eq!(res, vec!["ok", "bad \u{FFFD} byte", "last"]);
+    }
+
+    #[test]
+    fn test_utf8_bom_is_skipped() {
+        let res = collect(b"\xEF\xBB\xBFfirst\nsecond\n", Encoding::Utf8Lossy);
+        assert_eq!(res, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_line_offsets() {
+        // Смещения считаются по сырым байтам: BOM относится к первой строке, "\r\n" входит
+        let bytes = b"\xEF\xBB\xBFfirst\r\nsecond\nlast";
+        let mut lines = lines(Cursor::new(bytes.to_vec()), Encoding::Utf8Lossy, true).unwrap();
+        let mut offsets = Vec::new();
+        loop {
+            let offset = lines.offset();
+            let Some(line) = lines.next() else {
+                break;
+            };
+            offsets.push((offset, line.unwrap()));
+        }
+        assert_eq!(
+            offsets,
+            vec![
+                (0, "first".to_string()),
+                (10, "second".to_string()),
+                (17, "last".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_byte_encodings() {
+        assert_eq!(collect(b"caf\xE9\n", Encoding::Latin1), vec!["café"]);
+        assert_eq!(
+            collect(b"\xCF\xF0\xE8\xE2\xE5\xF2 \xB9 1\n", Encoding::Cp1251),
+            vec!["Привет № 1"]
+        );
+    }
+
+    #[test]
+    fn test_utf16_with_bom_detection() {
+        // "Ё\nb\r\n" в UTF-16LE с BOM; 0x0A внутри символа (U+010A) не должен резать строку
+        let mut le: Vec<u8> = vec![0xFF, 0xFE];
+        for u in "\u{010A}Ё\nb\r\n".encode_utf16() {
+            le.extend_from_slice(&u.to_le_bytes());
+        }
+        // Настройка Latin1 перекрывается BOM
+        assert_eq!(collect(&le, Encoding::Latin1), vec!["\u{010A}Ё", "b"]);
+
+        let mut be: Vec<u8> = vec![0xFE, 0xFF];
+        for u in "x\ny".encode_utf16() {
+            be.extend_from_slice(&u.to_be_bytes());
+        }
+        be.push(0x00); // обрезанный символ в конце
+        assert_eq!(collect(&be, Encoding::Utf8Lossy), vec!["x", "y\u{FFFD}"]);
+    }
+
+    #[test]
+    fn test_utf16_without_bom_uses_config() {
+        let mut le: Vec<u8> = Vec::new();
+        for u in "a\nb".encode_utf16() {
+            le.extend_from_slice(&u.to_le_bytes());
+        }
+        assert_eq!(collect(&le, Encoding::Utf16Le), vec!["a", "b"]);
+    }
+}
@@ -1,99 +1,516 @@
 use std::{
+    collections::HashMap,
     error::Error,
     fs::File,
-    io::{self, BufRead},
+    io::{self, BufRead, Read, Seek, SeekFrom},
+    sync::{Arc, Mutex, OnceLock},
 };
 
-use crate::common::enums::Filter;
-use crate::common::structs::Path;
+use tokio::{sync::Semaphore, task};
 
-pub async fn read_lines_from_start(
-    path: String,
+use crate::common::constants::{PAGE_BLOCK_LINES, PAGE_COUNTS_CACHE, SCAN_CHUNK_SIZE, STDIN_PATH};
+use crate::common::enums::{Encoding, Filter, LogFormat, Order};
+use crate::common::structs::{PageLine, Path, Place};
+use crate::index::{
+    lines::{self, FileKey, LineIndex},
+    text,
+};
+use crate::reader::{
+    container, encoding, journal, search,
+    stdin::{self, Snapshot},
+};
+
+pub async fn read_from_paths(
+    paths: Vec<Path>,
     limit: usize,
     offset: usize,
     filters: Option<Vec<Filter>>,
+    order: Order,
 ) -> Result<Vec<String>, Box<dyn Error>> {
-    let file = File::open(path)?; // Чтобы вернуть ошибку даже при limit = 0
-
-    // Ограничиваем начальную ёмкость вектора, чтобы избежать переполнения
-    let initial_capacity = std::cmp::min(limit, 1000);
-    let mut lines = Vec::with_capacity(initial_capacity);
+    let page = read_page(paths, limit, offset, filters, order).await?;
+    Ok(page.into_iter().map(|line| line.line).collect())
+}
 
-    if limit < 1 {
+// То же, что read_from_paths, но вместе с местом каждой строки в её файле
+pub async fn read_page(
+    paths: Vec<Path>,
+    limit: usize,
+    offset: usize,
+    filters: Option<Vec<Filter>>,
+    order: Order,
+) -> Result<Vec<PageLine>, Box<dyn Error>> {
+    let page_order = order.clone();
+    let (indexed, parts, filters, paths) = task::spawn_blocking(move || {
+        if let Some(lines) = read_indexed_page(&paths, limit, offset, &filters, &page_order)? {
+            return Ok::<_, io::Error>((Some(lines), Vec::new(), filters, paths));
+        }
+        let parts = plan_parts(&paths, &filters)?;
+        Ok((None, parts, filters, paths))
+    })
+    .await??;
+    if let Some(lines) = indexed {
         return Ok(lines);
     }
 
-    let reader = io::BufReader::new(file);
+    // Куски файлов сканируются параллельно, но не больше, чем ядер
+    let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let semaphore = Arc::new(Semaphore::new(parallelism));
+    let filters = Arc::new(filters);
+    let mut handles = Vec::with_capacity(parts.len());
+    for (source, part) in parts {
+        let handle = match part {
+            Part::Lines(lines) => {
+                let lines = lines.into_iter().map(|line| (None, line)).collect();
+                task::spawn_blocking(move || Ok(lines))
+            }
+            Part::Job(job) => {
+                let permit = semaphore.clone().acquire_owned().await?;
+                let filters = filters.clone();
+                task::spawn_blocking(move || {
+                    let res = scan_job(&job, &filters);
+                    drop(permit);
+                    res
+                })
+            }
+        };
+        handles.push((source, handle));
+    }
+
+    // Собираем результаты в порядке задач, чтобы вывод не зависел от планировщика
+    let mut all_lines = Vec::new();
+    for (source, handle) in handles {
+        for (place, line) in handle.await?? {
+            all_lines.push((line, source, place));
+        }
+    }
+
+    // Apply sorting based on order
+    // Сортировка устойчивая: одинаковые строки остаются в порядке сканирования
+    all_lines.sort_by(|a, b| a.0.cmp(&b.0));
+    if order == Order::OrderByDateReverse {
+        all_lines.reverse();
+    }
 
-    let mut counter: usize = 0;
+    // Apply the overall limit and offset to the combined results
+    let page = all_lines
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(line, source, place)| PageLine {
+            line,
+            place: place
+                .filter(|_| paths[source].path != STDIN_PATH)
+                .map(|offset| Place {
+                    path: paths[source].path.clone(),
+                    offset,
+                }),
+        })
+        .collect();
+    Ok(page)
+}
 
-    for line_result in reader.lines() {
-        let line = line_result?;
+// Что читать в источнике: строки stdin, которые ещё в памяти, или куски файла
+enum Part {
+    Lines(Vec<String>),
+    Job(ScanJob),
+}
 
-        if let Some(filter_vec) = &filters {
-            if !filter_vec.iter().all(|f| f.is_include(&line)) {
-                continue;
+// Части всех источников в порядке путей, каждая помечена номером своего источника
+fn plan_parts(paths: &[Path], filters: &Option<Vec<Filter>>) -> io::Result<Vec<(usize, Part)>> {
+    let mut parts = Vec::new();
+    for (source, path) in paths.iter().enumerate() {
+        let jobs = match path.path.as_str() {
+            // stdin: пока влезает в память — фильтруем строки здесь, иначе читаем файл спула
+            STDIN_PATH => match stdin::spool().snapshot()? {
+                Snapshot::Lines(lines) => {
+                    let lines = lines.into_iter().filter(|line| is_match(filters, line)).collect();
+                    parts.push((source, Part::Lines(lines)));
+                    continue;
+                }
+                Snapshot::File(spool_path) => {
+                    let spool_path = Path::new(spool_path, path.name.clone());
+                    plan_jobs(&spool_path, filters, SCAN_CHUNK_SIZE)?
+                }
+            },
+            _ => plan_jobs(path, filters, SCAN_CHUNK_SIZE)?,
+        };
+        parts.extend(jobs.into_iter().map(|job| (source, Part::Job(job))));
+    }
+    Ok(parts)
+}
+
+// Участок файла [start, end), начинающийся с начала строки; end == u64::MAX — до конца
+#[derive(Debug, PartialEq)]
+struct ScanJob {
+    path: String,
+    encoding: Encoding,
+    format: LogFormat,
+    start: u64,
+    end: u64,
+}
+
+// Разбивает файл на куски по границам строк. Если есть полнотекстовый индекс,
+// режутся только диапазоны блоков-кандидатов.
+fn plan_jobs(
+    source: &Path,
+    filters: &Option<Vec<Filter>>,
+    chunk_size: u64,
+) -> io::Result<Vec<ScanJob>> {
+    let path = source.path.as_str();
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let encoding = encoding::effective(path, source.encoding)?;
+    let format = journal::effective(path, source.format)?;
+    let whole = vec![ScanJob {
+        path: path.to_string(),
+        encoding,
+        format,
+        start: 0,
+        end: u64::MAX,
+    }];
+    // Каналы, устройства и т.п. читаются одним потоком до конца,
+    // UTF-16 тоже: байт '\n' в нём не обязательно граница строки.
+    // Контейнерные логи — тоже: часть строки может оказаться в соседнем куске, журнал — тоже
+    if !metadata.is_file() || !encoding.is_ascii_compatible() || format != LogFormat::Plain {
+        return Ok(whole);
+    }
+    let size = metadata.len();
+
+    let ranges = match filters {
+        Some(filter_vec) if !filter_vec.is_empty() => {
+            text::lookup(path).and_then(|index| index.ranges(filter_vec))
+        }
+        _ => None,
+    }
+    .unwrap_or_else(|| vec![(0, u64::MAX)]);
+
+    let mut jobs = Vec::new();
+    for (start, end) in ranges {
+        let limit = end.min(size);
+        let mut chunk_start = start;
+        while chunk_start + chunk_size < limit {
+            let boundary = line_boundary(&mut file, chunk_start + chunk_size)?;
+            if boundary >= limit {
+                break;
             }
+            jobs.push(ScanJob {
+                path: path.to_string(),
+                encoding,
+                format,
+                start: chunk_start,
+                end: boundary,
+            });
+            chunk_start = boundary;
         }
-        counter += 1;
-        if counter <= offset {
-            continue;
+        // Последний кусок читаем до исходной границы (файл мог дорасти)
+        jobs.push(ScanJob {
+            path: path.to_string(),
+            encoding,
+            format,
+            start: chunk_start,
+            end,
+        });
+    }
+    Ok(jobs)
+}
+
+// Первое начало строки не раньше at
+fn line_boundary(file: &mut File, at: u64) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(at - 1))?;
+    let mut reader = io::BufReader::new(&mut *file);
+    let mut skipped = Vec::new();
+    let read = reader.read_until(b'\n', &mut skipped)?;
+    Ok(at - 1 + read as u64)
+}
+
+fn scan_job(job: &ScanJob, filters: &Option<Vec<Filter>>) -> io::Result<Vec<(Option<u64>, String)>> {
+    let mut lines = Vec::new();
+    visit_job(job, filters, &mut |place, line| {
+        lines.push((place, line));
+        Ok(())
+    })?;
+    Ok(lines)
+}
+
+// Строки куска, прошедшие фильтры, по одной; у строк обычных файлов есть смещение начала.
+// Ошибка из visit прерывает чтение
+fn visit_job(
+    job: &ScanJob,
+    filters: &Option<Vec<Filter>>,
+    visit: &mut impl FnMut(Option<u64>, String) -> io::Result<()>,
+) -> io::Result<()> {
+    // Быстрый поиск смотрит на сырые байты, а в контейнерных логах текст экранирован и разрезан.
+    // Совпадения он отдаёт списком, но не больше, чем помещается в один кусок файла
+    if job.encoding == Encoding::Utf8Lossy
+        && job.format == LogFormat::Plain
+        && let (Some(needle), Some(filter_vec)) = (search::search_needle(filters), filters)
+    {
+        for (offset, line) in search::scan_matches(&job.path, job.start, job.end, needle, filter_vec)? {
+            visit(Some(offset), line)?;
         }
+        return Ok(());
+    }
 
-        lines.push(line);
+    if job.format == LogFormat::Journal {
+        for line in journal::lines(&job.path)? {
+            let line = line?;
+            if is_match(filters, &line) {
+                visit(None, line)?;
+            }
+        }
+        return Ok(());
+    }
 
-        if lines.len() >= limit {
-            break;
+    let mut file = File::open(&job.path)?;
+    file.seek(SeekFrom::Start(job.start))?;
+    let reader = io::BufReader::new(file.take(job.end - job.start));
+    let mut decoded = encoding::lines(reader, job.encoding, job.start == 0)?;
+    if job.format != LogFormat::Plain {
+        for line in container::lines(decoded, job.format) {
+            let line = line?;
+            if is_match(filters, &line) {
+                visit(None, line)?;
+            }
+        }
+        return Ok(());
+    }
+    loop {
+        let offset = job.start + decoded.offset();
+        let Some(line) = decoded.next() else {
+            return Ok(());
+        };
+        let line = line?;
+        if is_match(filters, &line) {
+            visit(Some(offset), line)?;
         }
     }
-    Ok(lines)
 }
 
-use crate::common::enums::Order;
+fn is_match(filters: &Option<Vec<Filter>>, line: &String) -> bool {
+    filters
+        .as_ref()
+        .is_none_or(|f| f.iter().all(|f| f.is_include(line)))
+}
 
-pub async fn read_from_paths(
-    paths: Vec<Path>,
+// Все строки источников, прошедшие фильтры, по одной и без сбора в память — для статистики.
+// Чтение блокирующее, вызывать из отдельного потока
+pub fn visit_lines(
+    paths: &[Path],
+    filters: &Option<Vec<Filter>>,
+    mut visit: impl FnMut(&str, String),
+) -> io::Result<()> {
+    visit_placed(paths, filters, |source, _, line| visit(&paths[source].name, line))
+}
+
+// Как visit_lines, но обход прерывается первой ошибкой из visit — так его можно отменить
+pub fn try_visit_lines(
+    paths: &[Path],
+    filters: &Option<Vec<Filter>>,
+    mut visit: impl FnMut(&str, String) -> io::Result<()>,
+) -> io::Result<()> {
+    try_visit_placed(paths, filters, |source, _, line| visit(&paths[source].name, line))
+}
+
+// Как visit_lines, но с номером источника и смещением строки в его файле (если оно есть)
+pub fn visit_placed(
+    paths: &[Path],
+    filters: &Option<Vec<Filter>>,
+    mut visit: impl FnMut(usize, Option<u64>, String),
+) -> io::Result<()> {
+    try_visit_placed(paths, filters, |source, place, line| {
+        visit(source, place, line);
+        Ok(())
+    })
+}
+
+fn try_visit_placed(
+    paths: &[Path],
+    filters: &Option<Vec<Filter>>,
+    mut visit: impl FnMut(usize, Option<u64>, String) -> io::Result<()>,
+) -> io::Result<()> {
+    for (source, part) in plan_parts(paths, filters)? {
+        match part {
+            Part::Lines(lines) => {
+                for line in lines {
+                    visit(source, None, line)?;
+                }
+            }
+            Part::Job(job) => {
+                let placed = paths[source].path != STDIN_PATH;
+                visit_job(&job, filters, &mut |place, line| {
+                    visit(source, place.filter(|_| placed), line)
+                })?
+            }
+        }
+    }
+    Ok(())
+}
+
+// Один уже отсортированный файл: сортировка ничего не меняет, поэтому страница читается
+// по индексу строк, без сканирования и сортировки всего файла. Несколько путей и
+// неотсортированные файлы по-прежнему сканируются целиком: их порядок даёт только сортировка
+fn read_indexed_page(
+    paths: &[Path],
     limit: usize,
     offset: usize,
-    filters: Option<Vec<Filter>>,
-    order: Order,
-) -> Result<Vec<String>, Box<dyn Error>> {
-    let mut all_lines = Vec::new();
+    filters: &Option<Vec<Filter>>,
+    order: &Order,
+) -> io::Result<Option<Vec<PageLine>>> {
+    if paths.len() != 1 {
+        return Ok(None);
+    }
+    let source = &paths[0].path;
+    if source == STDIN_PATH || journal::effective(source, paths[0].format)? != LogFormat::Plain {
+        return Ok(None);
+    }
+    // Признак сортировки считается по байтам — совпадает с порядком строк только для UTF-8
+    if encoding::effective(source, paths[0].encoding)? != Encoding::Utf8Lossy {
+        return Ok(None);
+    }
+    let Some(mut index) = lines::lookup(source) else {
+        return Ok(None);
+    };
+    if !index.is_sorted() || index.has_partial_tail() {
+        return Ok(None);
+    }
+    match filters.as_deref() {
+        Some(filters) if !filters.is_empty() => {
+            filtered_page(&mut index, source, limit, offset, filters, order).map(Some)
+        }
+        _ => seek_page(&mut index, source, limit, offset, order).map(Some),
+    }
+}
 
-    for path in paths {
-        let mut file_lines = read_lines_from_start(
-            path.path,
-            usize::MAX, // Read all lines from this file
-            0, // No offset at file level
-            filters.clone(),
-        ).await?;
-        
-        all_lines.append(&mut file_lines);
+// Без фильтров номер строки выдачи — это номер строки файла
+fn seek_page(
+    index: &mut LineIndex,
+    source: &str,
+    limit: usize,
+    offset: usize,
+    order: &Order,
+) -> io::Result<Vec<PageLine>> {
+    let (start, count) = match order {
+        Order::OrderByDate => (offset, limit),
+        Order::OrderByDateReverse => {
+            let total = index.line_count();
+            if offset >= total {
+                return Ok(Vec::new());
+            }
+            let end = total - offset;
+            let start = end.saturating_sub(limit);
+            (start, end - start)
+        }
+    };
+    let mut page = Vec::with_capacity(std::cmp::min(count, 1000));
+    for (n, line) in index
+        .read_lines(source, Encoding::Utf8Lossy, start, count)?
+        .into_iter()
+        .enumerate()
+    {
+        page.push(PageLine {
+            line,
+            place: Some(Place {
+                path: source.to_string(),
+                offset: index.offset_of(start + n)?,
+            }),
+        });
+    }
+    if *order == Order::OrderByDateReverse {
+        page.reverse();
     }
+    Ok(page)
+}
 
-    // Apply sorting based on order
-    match order {
-        Order::OrderByDate => {
-            // For date ascending, we can sort the strings if they start with date
-            // This is a simple approach - if logs start with date in sortable format like ISO 8601
-            all_lines.sort();
+// Совпадения фильтров по блокам строк индексированного файла, в порядке выдачи: блок 0 —
+// первые PAGE_BLOCK_LINES строк (последние для обратного порядка). before[k] — совпадений
+// до блока k, посчитаны блоки 0..before.len() - 1
+struct MatchCounts {
+    key: FileKey,
+    before: Vec<usize>,
+}
+
+// По файлу, порядку и фильтрам
+static MATCH_COUNTS: OnceLock<Mutex<HashMap<String, MatchCounts>>> = OnceLock::new();
+
+// С фильтрами страница начинается с ближайшего блока, до которого совпадений уже
+// посчитано не больше offset: первый переход на дальнюю страницу читает файл до неё,
+// а соседние страницы — один-два блока
+fn filtered_page(
+    index: &mut LineIndex,
+    source: &str,
+    limit: usize,
+    offset: usize,
+    filters: &[Filter],
+    order: &Order,
+) -> io::Result<Vec<PageLine>> {
+    let total = index.line_count();
+    let blocks = total.div_ceil(PAGE_BLOCK_LINES);
+    let cache_key = serde_json::to_string(&(source, order, filters)).map_err(io::Error::other)?;
+    let mut before = MATCH_COUNTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .get(&cache_key)
+        .filter(|counts| counts.key == *index.key())
+        .map_or_else(|| vec![0], |counts| counts.before.clone());
+
+    let mut block = before.partition_point(|&n| n <= offset) - 1;
+    let mut skip = offset - before[block];
+    let mut page = Vec::new();
+    while block < blocks && page.len() < limit {
+        let (start, end) = match order {
+            Order::OrderByDate => (
+                block * PAGE_BLOCK_LINES,
+                std::cmp::min((block + 1) * PAGE_BLOCK_LINES, total),
+            ),
+            Order::OrderByDateReverse => (
+                total.saturating_sub((block + 1) * PAGE_BLOCK_LINES),
+                total - block * PAGE_BLOCK_LINES,
+            ),
+        };
+        let mut matched = Vec::new();
+        for (n, line) in index
+            .read_lines(source, Encoding::Utf8Lossy, start, end - start)?
+            .into_iter()
+            .enumerate()
+        {
+            if filters.iter().all(|f| f.is_include(&line)) {
+                matched.push((start + n, line));
+            }
         }
-        Order::OrderByDateReverse => {
-            // For date descending, sort then reverse
-            all_lines.sort();
-            all_lines.reverse();
+        if *order == Order::OrderByDateReverse {
+            matched.reverse();
+        }
+        if block + 1 == before.len() {
+            before.push(before[block] + matched.len());
         }
+
+        let passed = std::cmp::min(skip, matched.len());
+        for (n, line) in matched.into_iter().skip(skip).take(limit - page.len()) {
+            page.push(PageLine {
+                line,
+                place: Some(Place {
+                    path: source.to_string(),
+                    offset: index.offset_of(n)?,
+                }),
+            });
+        }
+        skip -= passed;
+        block += 1;
     }
 
-    // Apply the overall limit and offset to the combined results
-    let start = offset;
-    let end = (offset + limit).min(all_lines.len());
-    
-    if start < all_lines.len() {
-        Ok(all_lines[start..end].to_vec())
-    } else {
-        Ok(Vec::new())
+    let mut counts = MATCH_COUNTS.get_or_init(Default::default).lock().unwrap();
+    if counts.len() >= PAGE_COUNTS_CACHE && !counts.contains_key(&cache_key) {
+        counts.clear();
     }
+    counts.insert(
+        cache_key,
+        MatchCounts {
+            key: index.key().clone(),
+            before,
+        },
+    );
+    Ok(page)
 }
 
 #[cfg(test)]
@@ -102,15 +519,19 @@ mod test {
 
     use std::io::{self, Write};
 
-    use crate::common::enums::Filter;
-    use crate::common::structs::SearchFilter;
-    use crate::reader::file::read_lines_from_start;
+    use crate::common::enums::{Encoding, Filter, LogFormat, Order};
+    use crate::common::structs::{Path, SearchFilter};
+    use crate::index::lines;
+    use crate::reader::file::{
+        filtered_page, plan_jobs, read_from_paths, scan_job,
+    };
     use tempdir::TempDir;
 
     #[tokio::test]
     async fn read_error() {
         let random_path = random_str::get_string(6, true, false, true, true);
-        let res = read_lines_from_start(random_path, 1, 1, None);
+        let source = Path::new(random_path.clone(), random_path);
+        let res = read_from_paths(vec![source], 1, 1, None, Order::OrderByDate);
         assert!(res.await.is_err())
     }
 
@@ -121,7 +542,9 @@ mod test {
             TempDir::new(&random_path).expect("Не получилось создать временную директорию");
         let file_path = tmp_dir.path().join("test.log");
         File::create(&file_path).expect("Не удалось создать временный файл");
-        let res = read_lines_from_start(file_path.to_str().unwrap().to_string(), 0, 0, None)
+        let file_path = file_path.to_str().unwrap().to_string();
+        let source = Path::new(file_path.clone(), file_path);
+        let res = read_from_paths(vec![source], 0, 0, None, Order::OrderByDate)
             .await
             .expect("Не удалось прочитать временный файл");
         assert_eq!(res.len(), 0)
@@ -138,7 +561,8 @@ mod test {
         writeln!(tmp_file, "test-2").expect("Не удалось записать строку в файл");
         let file_path = file_path.to_str().unwrap().to_string();
 
-        let res = read_lines_from_start(file_path, 3, 0, None)
+        let source = Path::new(file_path.clone(), file_path);
+        let res = read_from_paths(vec![source], 3, 0, None, Order::OrderByDate)
             .await
             .expect("Не удалось прочитать временный файл");
         assert_eq!(res.len(), 2)
@@ -160,7 +584,8 @@ mod test {
             substr: "test".to_string(),
         });
 
-        let res = read_lines_from_start(file_path, 3, 0, Some(vec![f]))
+        let source = Path::new(file_path.clone(), file_path);
+        let res = read_from_paths(vec![source], 3, 0, Some(vec![f]), Order::OrderByDate)
             .await
             .expect("Не удалось прочитать временный файл");
         assert_eq!(res.len(), 2)
@@ -182,9 +607,238 @@ mod test {
             substr: "test".to_string(),
         });
 
-        let res = read_lines_from_start(file_path, 3, 1, Some(vec![f]))
+        let source = Path::new(file_path.clone(), file_path);
+        let res = read_from_paths(vec![source], 3, 1, Some(vec![f]), Order::OrderByDate)
             .await
             .expect("Не удалось прочитать временный файл");
         assert_eq!(res.len(), 1)
     }
+
+    #[test]
+    fn chunks_follow_line_boundaries() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+        let file_path = tmp_dir.path().join("test.log");
+        let mut tmp_file = File::create(&file_path).expect("Не удалось создать временный файл");
+        let expected: Vec<String> = (0..50).map(|i| format!("line-{}-{}", i, "x".repeat(i % 7))).collect();
+        for l in &expected {
+            writeln!(tmp_file, "{}", l).expect("Не удалось записать строку в файл");
+        }
+        let file_path = file_path.to_str().unwrap().to_string();
+        let source = Path::new(file_path.clone(), file_path);
+
+        let jobs = plan_jobs(&source, &None, 32).expect("Не удалось разбить файл на куски");
+        assert!(jobs.len() > 1);
+        let mut lines = Vec::new();
+        for job in &jobs {
+            lines.append(&mut scan_job(job, &None).expect("Не удалось прочитать кусок"));
+        }
+        // Смещение каждой строки — сумма длин предыдущих вместе с '\n'
+        let mut offset = 0;
+        let placed: Vec<(Option<u64>, String)> = expected
+            .iter()
+            .map(|l| {
+                let place = Some(offset);
+                offset += l.len() as u64 + 1;
+                (place, l.clone())
+            })
+            .collect();
+        assert_eq!(lines, placed);
+    }
+
+    #[tokio::test]
+    async fn read_from_paths_merges_in_order() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+        let mut paths = Vec::new();
+        for name in ["a.log", "b.log"] {
+            let file_path = tmp_dir.path().join(name);
+            let mut tmp_file = File::create(&file_path).expect("Не удалось создать временный файл");
+            for i in 0..5 {
+                writeln!(tmp_file, "2024-01-0{}T00:00:00+00:00 {} test", i + 1, name)
+                    .expect("Не удалось записать строку в файл");
+            }
+            let file_path = file_path.to_str().unwrap().to_string();
+            paths.push(Path::new(file_path.clone(), file_path));
+        }
+        let f = Filter::Search(SearchFilter {
+            substr: "test".to_string(),
+        });
+
+        let res = read_from_paths(paths, 3, 1, Some(vec![f]), Order::OrderByDateReverse)
+            .await
+            .expect("Не удалось прочитать временные файлы");
+        assert_eq!(
+            res,
+            vec![
+                "2024-01-05T00:00:00+00:00 a.log test",
+                "2024-01-04T00:00:00+00:00 b.log test",
+                "2024-01-04T00:00:00+00:00 a.log test",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_from_paths_decodes_encodings() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+
+        // UTF-16LE без BOM — кодировка берётся из настройки пути
+        let utf16 = tmp_dir.path().join("utf16.log");
+        let mut bytes = Vec::new();
+        for u in "2024-01-01T00:00:00+00:00 ошибка\r\n".encode_utf16() {
+            bytes.extend_from_slice(&u.to_le_bytes());
+        }
+        std::fs::write(&utf16, bytes).expect("Не удалось создать временный файл");
+        let utf16 = utf16.to_str().unwrap().to_string();
+        let mut utf16_path = Path::new(utf16.clone(), utf16);
+        utf16_path.encoding = Encoding::Utf16Le;
+
+        // Невалидный UTF-8 не должен обрывать чтение
+        let broken = tmp_dir.path().join("broken.log");
+        std::fs::write(&broken, b"2024-01-02T00:00:00+00:00 bad \xFF\n2024-01-03T00:00:00+00:00 ok\n")
+            .expect("Не удалось создать временный файл");
+        let broken = broken.to_str().unwrap().to_string();
+
+        let res = read_from_paths(
+            vec![utf16_path, Path::new(broken.clone(), broken)],
+            10,
+            0,
+            None,
+            Order::OrderByDate,
+        )
+        .await
+        .expect("Не удалось прочитать временные файлы");
+        assert_eq!(
+            res,
+            vec![
+                "2024-01-01T00:00:00+00:00 ошибка",
+                "2024-01-02T00:00:00+00:00 bad \u{FFFD}",
+                "2024-01-03T00:00:00+00:00 ok",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_from_paths_unwraps_container_logs() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+
+        let docker = tmp_dir.path().join("docker.log");
+        std::fs::write(
+            &docker,
+            concat!(
+                r#"{"log":"GET / 200\n","stream":"stdout","time":"2026-10-17T12:00:03Z"}"#,
+                "\n",
+                r#"{"log":"panic: \"boom\"\n","stream":"stderr","time":"2026-10-17T12:00:01Z"}"#,
+                "\n",
+            ),
+        )
+        .expect("Не удалось создать временный файл");
+        let docker = docker.to_str().unwrap().to_string();
+        let mut docker_path = Path::new(docker.clone(), docker);
+        docker_path.format = LogFormat::DockerJson;
+
+        let cri = tmp_dir.path().join("cri.log");
+        std::fs::write(
+            &cri,
+            "2026-10-17T12:00:02Z stderr P panic: \n2026-10-17T12:00:02.5Z stderr F \"half\"\n",
+        )
+        .expect("Не удалось создать временный файл");
+        let cri = cri.to_str().unwrap().to_string();
+        let mut cri_path = Path::new(cri.clone(), cri);
+        cri_path.format = LogFormat::Cri;
+
+        // Поиск идёт по развёрнутому тексту: в json-file кавычки экранированы
+        let filters = vec![Filter::Search(SearchFilter {
+            substr: "panic: \"".to_string(),
+        })];
+        let res = read_from_paths(
+            vec![docker_path, cri_path],
+            10,
+            0,
+            Some(filters),
+            Order::OrderByDate,
+        )
+        .await
+        .expect("Не удалось прочитать временные файлы");
+        assert_eq!(
+            res,
+            vec![
+                "2026-10-17T12:00:01.000000000Z stream=stderr panic: \"boom\"",
+                "2026-10-17T12:00:02.000000000Z stream=stderr panic: \"half\"",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_from_paths_reads_stdin_spool() {
+        let spool = crate::reader::stdin::spool();
+        spool.push("2024-01-02T00:00:00+00:00 stdin test".to_string()).unwrap();
+        spool.push("2024-01-01T00:00:00+00:00 stdin skip".to_string()).unwrap();
+        let f = Filter::Search(SearchFilter {
+            substr: "test".to_string(),
+        });
+
+        let res = read_from_paths(
+            vec![Path::new("-".to_string(), "stdin".to_string())],
+            10,
+            0,
+            Some(vec![f]),
+            Order::OrderByDate,
+        )
+        .await
+        .expect("Не удалось прочитать stdin");
+        assert_eq!(res, vec!["2024-01-02T00:00:00+00:00 stdin test"]);
+    }
+
+    #[tokio::test]
+    async fn filtered_pages_from_line_index() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir =
+            TempDir::new(&random_path).expect("Не получилось создать временную директорию");
+        let file_path = tmp_dir.path().join("test.log");
+        let mut tmp_file = File::create(&file_path).expect("Не удалось создать временный файл");
+        for i in 0..10_000 {
+            let level = if i % 3 == 0 { "error" } else { "info" };
+            writeln!(tmp_file, "2024-01-01T00:00:00+00:00 {:05} {}", i, level)
+                .expect("Не удалось записать строку в файл");
+        }
+        let file_path = file_path.to_str().unwrap().to_string();
+        let source = Path::new(file_path.clone(), file_path.clone());
+        let content = std::fs::read(&file_path).expect("Не удалось прочитать временный файл");
+
+        let mut index = lines::build(&tmp_dir.path().join("index"), &file_path)
+            .expect("Не удалось собрать индекс строк");
+        assert!(index.is_sorted());
+        let filters = vec![Filter::Search(SearchFilter {
+            substr: "error".to_string(),
+        })];
+        // Страницы из индекса совпадают с полным сканированием, в том числе при переходе назад
+        for order in [Order::OrderByDate, Order::OrderByDateReverse] {
+            for (limit, offset) in [(30, 0), (30, 2000), (30, 1300), (50, 3320), (30, 5000)] {
+                let expected = read_from_paths(
+                    vec![source.clone()],
+                    limit,
+                    offset,
+                    Some(filters.clone()),
+                    order.clone(),
+                )
+                .await
+                .expect("Не удалось прочитать временный файл");
+                let page = filtered_page(&mut index, &file_path, limit, offset, &filters, &order)
+                    .expect("Не удалось прочитать страницу по индексу");
+                for line in &page {
+                    let place = line.place.as_ref().unwrap();
+                    assert!(content[place.offset as usize..].starts_with(line.line.as_bytes()));
+                }
+                let page: Vec<String> = page.into_iter().map(|line| line.line).collect();
+                assert_eq!(page, expected);
+            }
+        }
+    }
 }
@@ -0,0 +1,383 @@
+use std::{fs, io, net::SocketAddr, path::Path as StdPath, sync::Arc};
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, State},
+    http::{HeaderMap, StatusCode, header},
+    routing::post,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value, json};
+use tokio::{
+    net::TcpListener,
+    sync::mpsc::{self, UnboundedSender},
+};
+
+use crate::common::{
+    enums::Filter,
+    structs::{IngestConfig, Log},
+};
+use crate::reader::{rotating::RotatingFile, throughput::Throughput};
+
+// Приём логов по HTTP: POST /ingest/<source> с NDJSON или простым текстом, строка на строку лога.
+// Принятые строки ждут в ограниченной очереди: если она полна, клиент получает 429,
+// а не раздувает память просмотрщика.
+
+const TIME_FIELDS: [&str; 4] = ["time", "timestamp", "ts", "@timestamp"];
+const LEVEL_FIELDS: [&str; 3] = ["level", "lvl", "severity"];
+const MESSAGE_FIELDS: [&str; 2] = ["msg", "message"];
+
+pub struct Ingest {
+    listener: TcpListener,
+    spool: Option<RotatingFile>,
+    max_body_bytes: usize,
+    buffer_lines: usize,
+}
+
+#[derive(Clone)]
+struct IngestState {
+    queue: mpsc::Sender<(String, String)>,
+}
+
+impl Ingest {
+    pub async fn bind(config: &IngestConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(&config.addr).await?;
+        let spool = match &config.spool {
+            Some(path) => {
+                // Каталог спула по умолчанию (в кэше) при первом запуске ещё не создан
+                if let Some(dir) = StdPath::new(path).parent().filter(|d| !d.as_os_str().is_empty()) {
+                    fs::create_dir_all(dir)?;
+                }
+                Some(RotatingFile::open(
+                    path,
+                    config.spool_max_bytes,
+                    config.spool_files,
+                )?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            listener,
+            spool,
+            max_body_bytes: config.max_body_bytes,
+            buffer_lines: config.buffer_lines.max(1),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub async fn run(
+        mut self,
+        filters: Arc<Vec<Filter>>,
+        tx: UnboundedSender<Log>,
+        throughput: Arc<Throughput>,
+    ) {
+        let (queue, mut queue_rx) = mpsc::channel::<(String, String)>(self.buffer_lines);
+        let app = router(queue, self.max_body_bytes);
+        // Сервер живёт, пока жива эта задача
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(self.listener, app).await;
+        });
+
+        while let Some((source_name, line)) = queue_rx.recv().await {
+            // В спул пишется всё: фильтры в Page Mode могут быть другими
+            if let Some(spool) = self.spool.as_mut()
+                && spool.write_line(&line).is_err()
+            {
+                self.spool = None;
+            }
+            let passed = filters.iter().all(|f| f.is_include(&line));
+            throughput.record(&source_name, passed);
+            if !passed {
+                continue;
+            }
+            let log = Log {
+                date_time: Utc::now(),
+                data: line,
+                source_name,
+            };
+            if tx.send(log).is_err() {
+                break;
+            }
+        }
+        server.abort();
+    }
+}
+
+// Тело больше max_body_bytes axum отклоняет с 413 ещё до обработчика
+fn router(queue: mpsc::Sender<(String, String)>, max_body_bytes: usize) -> Router {
+    Router::new()
+        .route("/ingest/{source}", post(ingest))
+        .layer(DefaultBodyLimit::max(max_body_bytes))
+        .with_state(IngestState { queue })
+}
+
+async fn ingest(
+    State(state): State<IngestState>,
+    Path(source): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"));
+    let body = String::from_utf8_lossy(&body);
+    let received = Utc::now();
+    let lines: Vec<String> = body
+        .split('\n')
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| match is_json {
+            true => render_json(l, received),
+            false => l.to_string(),
+        })
+        .collect();
+
+    // Запрос либо принимается целиком, либо отклоняется: частичный приём клиент не повторит корректно
+    if lines.len() > state.queue.max_capacity() {
+        return error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "too many lines in one request",
+        );
+    }
+    let permits = match state.queue.try_reserve_many(lines.len()) {
+        Ok(permits) => permits,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            return error(StatusCode::TOO_MANY_REQUESTS, "ingest buffer is full");
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => {
+            return error(StatusCode::SERVICE_UNAVAILABLE, "ingest is shutting down");
+        }
+    };
+    let accepted = lines.len();
+    for (permit, line) in permits.zip(lines) {
+        permit.send((source.clone(), line));
+    }
+    (StatusCode::ACCEPTED, Json(json!({ "accepted": accepted })))
+}
+
+fn error(status: StatusCode, message: &str) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": message })))
+}
+
+// Объект NDJSON превращается в "<RFC3339-время> <уровень> <сообщение> k=v ...",
+// чтобы работали датовые фильтры. Всё, что не объект, остаётся как есть.
+pub fn render_json(raw: &str, received: DateTime<Utc>) -> String {
+    let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(raw) else {
+        return raw.to_string();
+    };
+    let time = take_first(&mut fields, &TIME_FIELDS)
+        .and_then(|v| parse_time(&v))
+        .unwrap_or(received);
+    let mut parts = vec![time.to_rfc3339_opts(SecondsFormat::Secs, false)];
+    if let Some(level) = take_first(&mut fields, &LEVEL_FIELDS) {
+        parts.push(plain(&level));
+    }
+    if let Some(message) = take_first(&mut fields, &MESSAGE_FIELDS) {
+        parts.push(plain(&message));
+    }
+    for (key, value) in fields {
+        parts.push(format!("{}={}", key, plain(&value)));
+    }
+    parts.join(" ")
+}
+
+fn take_first(fields: &mut Map<String, Value>, keys: &[&str]) -> Option<Value> {
+    keys.iter().find_map(|key| fields.remove(*key))
+}
+
+// Строка RFC3339 или unix-время в секундах либо миллисекундах
+fn parse_time(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc)),
+        Value::Number(n) => {
+            let n = n.as_f64()?;
+            match n > 1e12 {
+                true => DateTime::from_timestamp_millis(n as i64),
+                false => DateTime::from_timestamp(n as i64, 0),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tempdir::TempDir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::mpsc::unbounded_channel,
+    };
+
+    use super::*;
+    use crate::common::{constants::INGEST_SPOOL, enums::Order, structs::Memory};
+    use crate::reader::file::read_from_paths;
+
+    fn received() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    // Минимальный HTTP/1.1 клиент: отправляет POST и возвращает код ответа
+    async fn post(addr: SocketAddr, path: &str, content_type: &str, body: &str) -> u16 {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            content_type,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+            .split(' ')
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .expect("Ответ без кода")
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            render_json(
+                r#"{"ts":"2026-10-18T15:00:00+03:00","level":"warn","msg":"disk low","free":3}"#,
+                received()
+            ),
+            "2026-10-18T12:00:00+00:00 warn disk low free=3"
+        );
+        assert_eq!(
+            render_json(r#"{"message":"hi","time":1792238400000}"#, received()),
+            "2026-10-17T12:00:00+00:00 hi"
+        );
+        // Без времени — время приёма; не объект — строка как есть
+        assert_eq!(
+            render_json(r#"{"msg":"no time"}"#, received()),
+            "2026-10-18T12:00:00+00:00 no time"
+        );
+        assert_eq!(render_json("not json", received()), "not json");
+    }
+
+    #[tokio::test]
+    async fn test_ingest_plain_and_ndjson() {
+        let tmp_dir = TempDir::new("ingest").expect("Не получилось создать временную директорию");
+        // Каталога спула ещё нет — bind его создаёт
+        let spool = tmp_dir.path().join("spool").join("ingest.log");
+        let config = IngestConfig {
+            spool: Some(spool.to_str().unwrap().to_string()),
+            ..IngestConfig::new("127.0.0.1:0".to_string())
+        };
+        let ingest = Ingest::bind(&config).await.unwrap();
+        let addr = ingest.local_addr().unwrap();
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let filters = Arc::new(vec![Filter::Search(crate::common::structs::SearchFilter {
+            substr: "keep".to_string(),
+        })]);
+        let handle = tokio::spawn(ingest.run(filters, tx, Arc::new(Throughput::default())));
+
+        let status = post(
+            addr,
+            "/ingest/api",
+            "text/plain",
+            "keep one\r\ndrop\n\nkeep two",
+        )
+        .await;
+        assert_eq!(status, 202);
+        let status = post(
+            addr,
+            "/ingest/worker",
+            "application/x-ndjson",
+            "{\"time\":\"2026-10-18T12:00:00Z\",\"msg\":\"keep json\"}\n",
+        )
+        .await;
+        assert_eq!(status, 202);
+
+        let mut got = Vec::new();
+        for _ in 0..3 {
+            let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+                .await
+                .expect("Строка не пришла")
+                .unwrap();
+            got.push((log.source_name, log.data));
+        }
+        assert_eq!(
+            got,
+            vec![
+                ("api".to_string(), "keep one".to_string()),
+                ("api".to_string(), "keep two".to_string()),
+                (
+                    "worker".to_string(),
+                    "2026-10-18T12:00:00+00:00 keep json".to_string()
+                ),
+            ]
+        );
+        // Спул получает и отфильтрованные строки
+        assert_eq!(
+            fs::read_to_string(&spool).unwrap(),
+            "keep one\ndrop\nkeep two\n2026-10-18T12:00:00+00:00 keep json\n"
+        );
+        handle.abort();
+
+        // Page Mode листает спул как источник "ingest"
+        let memory = Memory {
+            ingest: Some(config),
+            ..Default::default()
+        };
+        let paths = memory.page_paths();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].name, "ingest");
+        let page = read_from_paths(paths, 10, 0, None, Order::OrderByDate)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 4);
+    }
+
+    #[test]
+    fn test_spool_default() {
+        // Спул включён по умолчанию, null в профиле его выключает
+        let config: IngestConfig = serde_json::from_str(r#"{"addr":"127.0.0.1:8514"}"#).unwrap();
+        assert!(config.spool.unwrap().ends_with(INGEST_SPOOL));
+        let config: IngestConfig =
+            serde_json::from_str(r#"{"addr":"127.0.0.1:8514","spool":null}"#).unwrap();
+        assert_eq!(config.spool, None);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_limits() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Очередь никто не разбирает: после двух строк она полна
+        let (queue, _queue_rx) = mpsc::channel::<(String, String)>(2);
+        let handle = tokio::spawn(async move {
+            let _ = axum::serve(listener, router(queue, 64)).await;
+        });
+
+        assert_eq!(
+            post(addr, "/ingest/a", "text/plain", &"x".repeat(100)).await,
+            413
+        );
+        assert_eq!(post(addr, "/ingest/a", "text/plain", "1\n2\n3").await, 413);
+        assert_eq!(post(addr, "/ingest/a", "text/plain", "1\n2").await, 202);
+        assert_eq!(post(addr, "/ingest/a", "text/plain", "3").await, 429);
+        handle.abort();
+    }
+}
@@ -0,0 +1,459 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+};
+
+use chrono::{DateTime, SecondsFormat};
+
+use crate::common::{enums::LogFormat, fields};
+
+// Журнал systemd без systemd: вывод `journalctl -o export` и сами файлы .journal.
+// Запись превращается в "<время> PRIORITY=.. _SYSTEMD_UNIT=.. SYSLOG_IDENTIFIER=.. <MESSAGE>":
+// время из __REALTIME_TIMESTAMP идёт первым, поэтому работают сортировка и датовые фильтры,
+// остальное — поля для фильтра по полю.
+
+pub const SIGNATURE: &[u8; 8] = b"LPKSHHRH";
+
+const SHOWN_FIELDS: [&str; 3] = ["PRIORITY", "_SYSTEMD_UNIT", "SYSLOG_IDENTIFIER"];
+
+const EXPORT_PREFIXES: [&str; 2] = ["__CURSOR=", "__REALTIME_TIMESTAMP="];
+
+type Fields = Vec<(String, String)>;
+
+// Журнал узнаётся без настройки формата: файл — по сигнатуре,
+// выгрузка — по служебному полю, с которого journalctl начинает каждую запись
+pub fn effective(path: &str, configured: LogFormat) -> io::Result<LogFormat> {
+    let mut head = [0u8; 24];
+    let mut file = File::open(path)?;
+    let mut read = 0;
+    while read < head.len() {
+        let n = file.read(&mut head[read..])?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    let head = &head[..read];
+    let is_journal = head.starts_with(SIGNATURE)
+        || EXPORT_PREFIXES
+            .iter()
+            .any(|prefix| head.starts_with(prefix.as_bytes()));
+    Ok(match is_journal {
+        true => LogFormat::Journal,
+        false => configured,
+    })
+}
+
+// Записи файла по порядку и по одной, без чтения файла целиком; записи без времени пропускаются
+pub fn lines(path: &str) -> io::Result<Box<dyn Iterator<Item = io::Result<String>> + Send>> {
+    let mut file = File::open(path)?;
+    let mut head = [0u8; 8];
+    let is_native = file.read_exact(&mut head).is_ok() && head == *SIGNATURE;
+    file.seek(SeekFrom::Start(0))?;
+    let entries: Box<dyn Iterator<Item = io::Result<Fields>> + Send> = match is_native {
+        true => Box::new(NativeEntries::open(file)?.map(Ok)),
+        false => Box::new(ExportEntries {
+            reader: BufReader::new(file),
+            done: false,
+        }),
+    };
+    Ok(Box::new(entries.filter_map(|entry| match entry {
+        Ok(entry) => render(&entry).map(Ok),
+        Err(e) => Some(Err(e)),
+    })))
+}
+
+fn render(entry: &Fields) -> Option<String> {
+    let get = |name: &str| {
+        entry
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let micros = get("__REALTIME_TIMESTAMP")?.parse().ok()?;
+    let mut line =
+        DateTime::from_timestamp_micros(micros)?.to_rfc3339_opts(SecondsFormat::Micros, true);
+    for name in SHOWN_FIELDS {
+        if let Some(value) = get(name) {
+            line.push(' ');
+            line.push_str(&fields::pair(name, value));
+        }
+    }
+    if let Some(message) = get("MESSAGE") {
+        line.push(' ');
+        // Многострочное сообщение остаётся одной строкой лога
+        line.push_str(&message.trim_end_matches('\n').replace('\n', " "));
+    }
+    Some(line)
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("journal: {}", what))
+}
+
+fn lossy(raw: &[u8]) -> String {
+    String::from_utf8_lossy(raw).into_owned()
+}
+
+// Export: строки "KEY=value", записи разделены пустой строкой. Значения с переводами
+// строк и двоичные идут как "KEY\n", длина le64, данные и '\n'.
+struct ExportEntries<R> {
+    reader: R,
+    // После ошибки разбора дальше не читаем
+    done: bool,
+}
+
+impl<R: BufRead> ExportEntries<R> {
+    fn entry(&mut self) -> io::Result<Option<Fields>> {
+        let mut entry = Fields::new();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok((!entry.is_empty()).then_some(entry));
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            if line.is_empty() {
+                if !entry.is_empty() {
+                    return Ok(Some(entry));
+                }
+                continue;
+            }
+            match line.iter().position(|b| *b == b'=') {
+                Some(eq) => entry.push((lossy(&line[..eq]), lossy(&line[eq + 1..]))),
+                None => {
+                    let mut len = [0u8; 8];
+                    self.reader
+                        .read_exact(&mut len)
+                        .map_err(|_| invalid("truncated field"))?;
+                    let len = u64::from_le_bytes(len);
+                    let mut value = Vec::new();
+                    (&mut self.reader).take(len).read_to_end(&mut value)?;
+                    if value.len() as u64 != len {
+                        return Err(invalid("truncated field"));
+                    }
+                    entry.push((lossy(&line), lossy(&value)));
+                    // Завершающий '\n' значения
+                    self.reader.read_exact(&mut [0u8; 1]).ok();
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ExportEntries<R> {
+    type Item = io::Result<Fields>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let entry = self.entry();
+        self.done = !matches!(entry, Ok(Some(_)));
+        entry.transpose()
+    }
+}
+
+// Раскладка файла — journal-def.h из systemd: все числа little-endian, объекты выровнены по 8
+const HEADER_INCOMPATIBLE_FLAGS: usize = 12;
+const HEADER_SIZE: usize = 88;
+const HEADER_N_ENTRIES: usize = 152;
+const HEADER_ENTRY_ARRAY_OFFSET: usize = 176;
+const INCOMPATIBLE_COMPACT: u32 = 1 << 4;
+
+const OBJECT_DATA: u8 = 1;
+const OBJECT_ENTRY: u8 = 3;
+const OBJECT_ENTRY_ARRAY: u8 = 6;
+// XZ, LZ4 и ZSTD
+const OBJECT_COMPRESSED: u8 = 1 | 2 | 4;
+
+fn read_u64(data: &[u8], at: usize) -> Option<u64> {
+    let bytes = data.get(at..at.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+// Объекты читаются с диска по смещению, в памяти только текущий
+struct Native {
+    file: File,
+    len: u64,
+    compact: bool,
+}
+
+impl Native {
+    fn read_at(&mut self, offset: u64, size: usize) -> Option<Vec<u8>> {
+        if offset.checked_add(size as u64)? > self.len {
+            return None;
+        }
+        let mut buf = vec![0u8; size];
+        self.file.seek(SeekFrom::Start(offset)).ok()?;
+        self.file.read_exact(&mut buf).ok()?;
+        Some(buf)
+    }
+
+    // Тело объекта ожидаемого типа вместе с заголовком и его флаги
+    fn object(&mut self, offset: u64, kind: u8) -> Option<(Vec<u8>, u8)> {
+        let header = self.read_at(offset, 16)?;
+        if header[0] != kind {
+            return None;
+        }
+        let size = usize::try_from(read_u64(&header, 8)?).ok()?;
+        let object = self.read_at(offset, size)?;
+        Some((object, header[1]))
+    }
+
+    // Ссылки в массивах: le32 в компактных файлах, le64 в обычных
+    fn offset_at(&self, object: &[u8], at: usize) -> Option<u64> {
+        match self.compact {
+            true => read_u32(object, at).map(u64::from),
+            false => read_u64(object, at),
+        }
+    }
+
+    // Поле "KEY=value"; сжатые значения пропускаем — распаковка потянула бы xz, lz4 и zstd,
+    // а systemd сжимает только большие поля
+    fn field(&mut self, offset: u64) -> Option<(String, String)> {
+        let (object, flags) = self.object(offset, OBJECT_DATA)?;
+        if flags & OBJECT_COMPRESSED != 0 {
+            return None;
+        }
+        let payload = object.get(if self.compact { 72 } else { 64 }..)?;
+        let eq = payload.iter().position(|b| *b == b'=')?;
+        Some((lossy(&payload[..eq]), lossy(&payload[eq + 1..])))
+    }
+
+    fn entry(&mut self, offset: u64) -> Option<Fields> {
+        let (object, _) = self.object(offset, OBJECT_ENTRY)?;
+        let realtime = read_u64(&object, 24)?;
+        let item_size = if self.compact { 4 } else { 16 };
+        let mut entry = vec![("__REALTIME_TIMESTAMP".to_string(), realtime.to_string())];
+        let mut at = 64;
+        while at + item_size <= object.len() {
+            if let Some(field) = self.offset_at(&object, at).and_then(|o| self.field(o)) {
+                entry.push(field);
+            }
+            at += item_size;
+        }
+        Some(entry)
+    }
+}
+
+// Записи идут по цепочке массивов записей от заголовка; битые записи пропускаются,
+// файл, скопированный с работающей машины, может быть дописан не до конца
+struct NativeEntries {
+    native: Native,
+    remaining: u64,
+    // Текущий массив записей, позиция в нём и следующий массив цепочки (0 — конец)
+    array: Vec<u8>,
+    at: usize,
+    next_array: u64,
+}
+
+impl NativeEntries {
+    fn open(file: File) -> io::Result<NativeEntries> {
+        let len = file.metadata()?.len();
+        let mut native = Native {
+            file,
+            len,
+            compact: false,
+        };
+        let header = native
+            .read_at(0, HEADER_ENTRY_ARRAY_OFFSET + 8)
+            .ok_or_else(|| invalid("truncated header"))?;
+        let field = |at| read_u64(&header, at).ok_or_else(|| invalid("truncated header"));
+        if field(HEADER_SIZE)? < (HEADER_ENTRY_ARRAY_OFFSET + 8) as u64 {
+            return Err(invalid("header too old"));
+        }
+        let flags =
+            read_u32(&header, HEADER_INCOMPATIBLE_FLAGS).ok_or_else(|| invalid("truncated header"))?;
+        native.compact = flags & INCOMPATIBLE_COMPACT != 0;
+        Ok(NativeEntries {
+            remaining: field(HEADER_N_ENTRIES)?,
+            next_array: field(HEADER_ENTRY_ARRAY_OFFSET)?,
+            native,
+            array: Vec::new(),
+            at: 0,
+        })
+    }
+
+    // Переход к следующему массиву цепочки; false — цепочка кончилась или испорчена
+    fn advance(&mut self) -> bool {
+        let offset = self.next_array;
+        if offset == 0 {
+            return false;
+        }
+        let Some((array, _)) = self.native.object(offset, OBJECT_ENTRY_ARRAY) else {
+            self.next_array = 0;
+            return false;
+        };
+        // Массивы только дописываются в конец: ссылка назад — испорченный файл
+        self.next_array = read_u64(&array, 16).filter(|next| *next > offset).unwrap_or(0);
+        self.array = array;
+        self.at = 24;
+        true
+    }
+}
+
+impl Iterator for NativeEntries {
+    type Item = Fields;
+
+    fn next(&mut self) -> Option<Fields> {
+        let item_size = if self.native.compact { 4 } else { 8 };
+        while self.remaining > 0 {
+            if self.at + item_size > self.array.len() {
+                if !self.advance() {
+                    return None;
+                }
+                continue;
+            }
+            match self.native.offset_at(&self.array, self.at) {
+                // Хвост массива выделен заранее и заполнен нулями
+                Some(0) | None => self.at = self.array.len(),
+                Some(offset) => {
+                    self.at += item_size;
+                    if let Some(entry) = self.native.entry(offset) {
+                        self.remaining -= 1;
+                        return Some(entry);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn write_file(dir: &TempDir, name: &str, data: &[u8]) -> String {
+        let path = dir.path().join(name);
+        File::create(&path)
+            .expect("Не удалось создать временный файл")
+            .write_all(data)
+            .unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_read_export() {
+        let mut data = b"__CURSOR=s=1\n__REALTIME_TIMESTAMP=1792238400000001\nPRIORITY=3\n\
+_SYSTEMD_UNIT=nginx.service\nMESSAGE=upstream timed out\n\n"
+            .to_vec();
+        // Многострочное сообщение записано двоичным полем
+        let message = b"panic: boom\ngoroutine 1";
+        data.extend(b"__REALTIME_TIMESTAMP=1792238399000000\nSYSLOG_IDENTIFIER=my app\nMESSAGE\n");
+        data.extend((message.len() as u64).to_le_bytes());
+        data.extend(message);
+        data.extend(b"\n\nMESSAGE=no time\n");
+
+        let tmp_dir = TempDir::new("journal").expect("Не получилось создать временную директорию");
+        let path = write_file(&tmp_dir, "dump.export", &data);
+        assert_eq!(
+            effective(&path, LogFormat::Plain).unwrap(),
+            LogFormat::Journal
+        );
+        assert_eq!(
+            lines(&path)
+                .unwrap()
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap(),
+            vec![
+                "2026-10-17T12:00:00.000001Z PRIORITY=3 _SYSTEMD_UNIT=nginx.service upstream timed out",
+                "2026-10-17T11:59:59.000000Z SYSLOG_IDENTIFIER=\"my app\" panic: boom goroutine 1",
+            ]
+        );
+        // Записи до обрезанного поля отдаются, затем ошибка и конец
+        let cut = write_file(
+            &tmp_dir,
+            "cut.export",
+            b"__REALTIME_TIMESTAMP=1792238400000000\nMESSAGE=ok\n\nMESSAGE\n\x05\0",
+        );
+        let mut cut = lines(&cut).unwrap();
+        assert_eq!(cut.next().unwrap().unwrap(), "2026-10-17T12:00:00.000000Z ok");
+        assert!(cut.next().unwrap().is_err());
+        assert!(cut.next().is_none());
+    }
+
+    // Объект с заголовком: тип, флаги, 6 байт резерва и размер
+    fn object(file: &mut Vec<u8>, kind: u8, flags: u8, body: &[u8]) -> u64 {
+        let offset = file.len() as u64;
+        file.extend([kind, flags, 0, 0, 0, 0, 0, 0]);
+        file.extend(((16 + body.len()) as u64).to_le_bytes());
+        file.extend(body);
+        while !file.len().is_multiple_of(8) {
+            file.push(0);
+        }
+        offset
+    }
+
+    fn data_object(file: &mut Vec<u8>, flags: u8, payload: &[u8]) -> u64 {
+        let mut body = vec![0u8; 48];
+        body.extend(payload);
+        object(file, OBJECT_DATA, flags, &body)
+    }
+
+    fn entry_object(file: &mut Vec<u8>, realtime: u64, items: &[u64]) -> u64 {
+        let mut body = vec![0u8; 48];
+        body[8..16].copy_from_slice(&realtime.to_le_bytes());
+        for item in items {
+            body.extend(item.to_le_bytes());
+            body.extend(0u64.to_le_bytes());
+        }
+        object(file, OBJECT_ENTRY, 0, &body)
+    }
+
+    #[test]
+    fn test_read_native() {
+        let mut file = vec![0u8; 256];
+        file[..8].copy_from_slice(SIGNATURE);
+        file[HEADER_SIZE..HEADER_SIZE + 8].copy_from_slice(&256u64.to_le_bytes());
+
+        let unit = data_object(&mut file, 0, b"_SYSTEMD_UNIT=sshd.service");
+        let priority = data_object(&mut file, 0, b"PRIORITY=6");
+        let first = data_object(&mut file, 0, b"MESSAGE=Accepted publickey");
+        let compressed = data_object(&mut file, 1, b"\xfd7zXZ");
+        let second = data_object(&mut file, 0, b"MESSAGE=Connection closed");
+        let entries = [
+            entry_object(&mut file, 1792238400000000, &[unit, priority, first]),
+            entry_object(&mut file, 1792238401500000, &[unit, compressed, second]),
+        ];
+        let mut array = 0u64.to_le_bytes().to_vec();
+        for entry in entries {
+            array.extend(entry.to_le_bytes());
+        }
+        // Незаполненный хвост массива
+        array.extend(0u64.to_le_bytes());
+        let array_offset = object(&mut file, OBJECT_ENTRY_ARRAY, 0, &array);
+        file[HEADER_N_ENTRIES..HEADER_N_ENTRIES + 8].copy_from_slice(&2u64.to_le_bytes());
+        file[HEADER_ENTRY_ARRAY_OFFSET..HEADER_ENTRY_ARRAY_OFFSET + 8]
+            .copy_from_slice(&array_offset.to_le_bytes());
+
+        let tmp_dir = TempDir::new("journal").expect("Не получилось создать временную директорию");
+        let path = write_file(&tmp_dir, "system.journal", &file);
+        assert_eq!(
+            effective(&path, LogFormat::Plain).unwrap(),
+            LogFormat::Journal
+        );
+        assert_eq!(
+            lines(&path)
+                .unwrap()
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap(),
+            vec![
+                "2026-10-17T12:00:00.000000Z PRIORITY=6 _SYSTEMD_UNIT=sshd.service Accepted publickey",
+                "2026-10-17T12:00:01.500000Z _SYSTEMD_UNIT=sshd.service Connection closed",
+            ]
+        );
+    }
+}
@@ -1,3 +1,13 @@
+pub mod command;
+pub mod container;
+pub mod encoding;
 pub mod file;
 pub mod find;
+pub mod ingest;
+pub mod journal;
+pub mod rotating;
+pub mod search;
+pub mod stdin;
+pub mod syslog;
 pub mod tail;
+pub mod throughput;
@@ -0,0 +1,87 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+// Файл спула с ротацией: spool, spool.1, spool.2 ...
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: &str, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: PathBuf::from(path),
+            max_bytes,
+            keep,
+            file,
+            size,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_spool_rotation() {
+        let tmp_dir = TempDir::new("rotating").expect("Не получилось создать временную директорию");
+        let path = tmp_dir.path().join("syslog.log");
+        let path_str = path.to_str().unwrap();
+        let mut spool = RotatingFile::open(path_str, 12, 2).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            spool.write_line(line).unwrap();
+        }
+        let read = |suffix: &str| fs::read_to_string(format!("{}{}", path_str, suffix)).unwrap();
+        assert_eq!(read(""), "fourth\n");
+        assert_eq!(read(".1"), "third\n");
+        assert_eq!(read(".2"), "second\n");
+        assert!(!PathBuf::from(format!("{}.3", path_str)).exists());
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use memchr::{memchr, memmem::Finder, memrchr};
+use memmap2::MmapOptions;
+
+use crate::common::enums::{Encoding, Filter};
+use crate::index::lines::FileKey;
+use crate::reader::encoding::detect_bom;
+
+// Быстрый путь для Filter::Search: ищем подстроку по байтам (memmem с SIMD),
+// а строки собираем только вокруг найденных совпадений. Остальные фильтры
+// применяются уже к собранным строкам, так что результат тот же, что у lines().
+
+// Живой лог могут обрезать прямо под отображением (logrotate copytruncate, `> file`),
+// и чтение за новым концом — это SIGBUS на весь процесс. Никакая проверка до или после
+// прохода от этого не спасает, поэтому через mmap читаются только ротированные архивы,
+// в которые уже никто не пишет, а живые файлы — окнами в буфер
+const WINDOW: usize = 8 * 1024 * 1024;
+
+// Подстрока первого непустого Search-фильтра — по ней и ищем
+pub fn search_needle(filters: &Option<Vec<Filter>>) -> Option<&str> {
+    filters.as_ref()?.iter().find_map(|f| match f {
+        Filter::Search(s) if !s.substr.is_empty() => Some(s.substr.as_str()),
+        _ => None,
+    })
+}
+
+pub fn scan_matches(
+    path: &str,
+    start: u64,
+    end: u64,
+    needle: &str,
+    filters: &[Filter],
+) -> io::Result<Vec<(u64, String)>> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let finder = Finder::new(needle.as_bytes());
+    let start = skip_bom(&file, start)?;
+
+    if !metadata.is_file() || !is_archive(path) {
+        return scan_buffered(file, start, end, &finder, filters);
+    }
+
+    let before = FileKey::from_metadata(&metadata);
+    let lines = scan_mapped(&file, start, end.min(before.size), &finder, filters)?;
+    // Архив подменили или дописали во время прохода — найденное устарело, перечитываем
+    if FileKey::from_metadata(&fs::metadata(path)?) != before {
+        return scan_buffered(File::open(path)?, start, end, &finder, filters);
+    }
+    Ok(lines)
+}
+
+// Ротированный архив: имя кончается на .N, .gz или -YYYYMMDD (logrotate с dateext);
+// app-20240101.log и прочие *.log ещё могут дописываться и читаются буферизованно
+fn is_archive(path: &str) -> bool {
+    let Some(name) = Path::new(path).file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| ext == "gz" || digits(ext))
+        || name
+            .rsplit_once('-')
+            .is_some_and(|(_, date)| date.len() == 8 && digits(date))
+}
+
+// UTF-8 BOM не входит в первую строку (так же делает encoding::lines)
+fn skip_bom(file: &File, start: u64) -> io::Result<u64> {
+    if start != 0 {
+        return Ok(start);
+    }
+    let mut head = [0u8; 3];
+    let mut read = 0;
+    while read < head.len() {
+        let n = (&*file).read(&mut head[read..])?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    Ok(match detect_bom(&head[..read]) {
+        Some((Encoding::Utf8Lossy, len)) => len as u64,
+        _ => 0,
+    })
+}
+
+fn scan_mapped(
+    file: &File,
+    start: u64,
+    end: u64,
+    finder: &Finder,
+    filters: &[Filter],
+) -> io::Result<Vec<(u64, String)>> {
+    let mut lines = Vec::new();
+    if start >= end {
+        return Ok(lines);
+    }
+    // Safety: отображение только читается, и сюда попадают только архивы (см. is_archive):
+    // их не обрезают, а дописанное после metadata за пределы отображения не попадает
+    let map = unsafe {
+        MmapOptions::new()
+            .offset(start)
+            .len((end - start) as usize)
+            .map(file)?
+    };
+    match_lines(&map, start, finder, filters, &mut lines);
+    Ok(lines)
+}
+
+fn scan_buffered(
+    mut file: File,
+    start: u64,
+    end: u64,
+    finder: &Finder,
+    filters: &[Filter],
+) -> io::Result<Vec<(u64, String)>> {
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = file.take(end - start);
+    let mut lines = Vec::new();
+    let mut buf: Vec<u8> = Vec::with_capacity(WINDOW);
+    // Смещение начала buf в файле
+    let mut base = start;
+
+    loop {
+        let filled = buf.len();
+        buf.resize(filled + WINDOW, 0);
+        let read = reader.read(&mut buf[filled..])?;
+        buf.truncate(filled + read);
+        if read == 0 {
+            // Остаток без '\n' в конце — последняя строка файла
+            match_lines(&buf, base, finder, filters, &mut lines);
+            return Ok(lines);
+        }
+        // Обрабатываем только полные строки, хвост переносим в следующее окно
+        if let Some(cut) = memrchr(b'\n', &buf) {
+            match_lines(&buf[..=cut], base, finder, filters, &mut lines);
+            buf.drain(..=cut);
+            base += cut as u64 + 1;
+        }
+    }
+}
+
+// haystack начинается с начала строки, base — его смещение в файле
+fn match_lines(
+    haystack: &[u8],
+    base: u64,
+    finder: &Finder,
+    filters: &[Filter],
+    lines: &mut Vec<(u64, String)>,
+) {
+    let mut pos = 0;
+    while pos < haystack.len() {
+        let Some(found) = finder.find(&haystack[pos..]) else {
+            break;
+        };
+        let hit = pos + found;
+        let line_start = memrchr(b'\n', &haystack[pos..hit]).map_or(pos, |i| pos + i + 1);
+        let line_end = memchr(b'\n', &haystack[hit..]).map_or(haystack.len(), |i| hit + i);
+
+        let mut raw = &haystack[line_start..line_end];
+        if line_end < haystack.len() {
+            raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+        }
+        let line = String::from_utf8_lossy(raw).into_owned();
+        if filters.iter().all(|f| f.is_include(&line)) {
+            lines.push((base + line_start as u64, line));
+        }
+        pos = line_end + 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs::File,
+        io::{BufRead, BufReader, BufWriter, Write},
+        time::Instant,
+    };
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::common::structs::{RegexFilter, SearchFilter};
+
+    fn search(substr: &str) -> Filter {
+        Filter::Search(SearchFilter {
+            substr: substr.to_string(),
+        })
+    }
+
+    fn slow_path(path: &str, filters: &[Filter]) -> Vec<String> {
+        BufReader::new(File::open(path).unwrap())
+            .lines()
+            .map(|l| l.unwrap())
+            .filter(|l| filters.iter().all(|f| f.is_include(l)))
+            .collect()
+    }
+
+    #[test]
+    fn test_fast_paths_match_lines() {
+        let tmp_dir = TempDir::new("search").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("test.log");
+        std::fs::write(
+            &log,
+            "\u{FEFF}error first\r\nerror one ERROR\nplain\nerror two\n\nerror three without newline",
+        )
+        .expect("Не удалось записать временный файл");
+        let path = log.to_str().unwrap();
+        let filters = vec![
+            search("error"),
+            Filter::Regex(RegexFilter {
+                pattern: "t(wo|hree)".to_string(),
+            }),
+        ];
+        let slow = slow_path(path, &filters);
+        let expected = vec![(38, slow[0].clone()), (49, slow[1].clone())];
+        assert_eq!(slow.len(), 2);
+
+        let finder = Finder::new(b"error");
+        let file = File::open(path).unwrap();
+        let len = file.metadata().unwrap().len();
+        assert_eq!(
+            scan_mapped(&file, 0, len, &finder, &filters).unwrap(),
+            expected
+        );
+        assert_eq!(
+            scan_buffered(File::open(path).unwrap(), 0, u64::MAX, &finder, &filters).unwrap(),
+            expected
+        );
+        assert_eq!(
+            scan_matches(path, 0, u64::MAX, "error", &filters).unwrap(),
+            expected
+        );
+        // Архив читается через mmap, а результат тот же
+        let archive = tmp_dir.path().join("test.log.1");
+        std::fs::copy(&log, &archive).expect("Не удалось скопировать временный файл");
+        assert_eq!(
+            scan_matches(archive.to_str().unwrap(), 0, u64::MAX, "error", &filters).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_is_archive() {
+        assert!(is_archive("/var/log/app.log.1"));
+        assert!(is_archive("/var/log/syslog.12"));
+        assert!(is_archive("/var/log/app.log-20240101"));
+        assert!(is_archive("/var/log/app.log.2.gz"));
+        assert!(is_archive("/var/log/app.log-20240101.gz"));
+        assert!(!is_archive("/var/log/app-20240101.log"));
+        assert!(!is_archive("/var/log/app.log-20240101.tmp"));
+        assert!(!is_archive("/var/log/app.log"));
+        assert!(!is_archive("/var/log/app-server.log"));
+        assert!(!is_archive("/var/log/app.log-2024"));
+        assert!(!is_archive("/var/log/ёж-журнал"));
+    }
+
+    #[test]
+    fn test_search_needle() {
+        assert_eq!(search_needle(&None), None);
+        assert_eq!(
+            search_needle(&Some(vec![search(""), search("abc")])),
+            Some("abc")
+        );
+    }
+
+    // Замер пропускной способности на большом файле:
+    // LOGSCRAPER_BENCH_MB=4096 cargo test --release bench_search_throughput -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_search_throughput() {
+        let size_mb: u64 = std::env::var("LOGSCRAPER_BENCH_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(512);
+        let tmp_dir =
+            TempDir::new("search_bench").expect("Не получилось создать временную директорию");
+        let log = tmp_dir.path().join("bench.log");
+        let mut out = BufWriter::new(File::create(&log).unwrap());
+        let mut written = 0u64;
+        let mut i = 0u64;
+        while written < size_mb * 1024 * 1024 {
+            let line = if i.is_multiple_of(10_000) {
+                format!(
+                    "2024-01-01T00:00:00+00:00 ERROR request {} failed: connection reset\n",
+                    i
+                )
+            } else {
+                format!(
+                    "2024-01-01T00:00:00+00:00 INFO request {} served in {} ms\n",
+                    i,
+                    i % 977
+                )
+            };
+            out.write_all(line.as_bytes()).unwrap();
+            written += line.len() as u64;
+            i += 1;
+        }
+        drop(out);
+        let path = log.to_str().unwrap();
+        let filters = vec![search("connection reset")];
+        let mb = written as f64 / (1024.0 * 1024.0);
+
+        let started = Instant::now();
+        let slow = slow_path(path, &filters);
+        let slow_secs = started.elapsed().as_secs_f64();
+
+        let finder = Finder::new(b"connection reset");
+        let file = File::open(path).unwrap();
+        let started = Instant::now();
+        let mapped = scan_mapped(&file, 0, written, &finder, &filters).unwrap();
+        let mapped_secs = started.elapsed().as_secs_f64();
+
+        let started = Instant::now();
+        let buffered =
+            scan_buffered(File::open(path).unwrap(), 0, u64::MAX, &finder, &filters).unwrap();
+        let buffered_secs = started.elapsed().as_secs_f64();
+
+        let text = |lines: Vec<(u64, String)>| lines.into_iter().map(|(_, l)| l).collect::<Vec<_>>();
+        assert_eq!(slow, text(mapped));
+        assert_eq!(slow, text(buffered));
+        println!(
+            "{:.0} MB: lines()+contains {:.0} MB/s, mmap+memmem {:.0} MB/s, buffered memmem {:.0} MB/s",
+            mb,
+            mb / slow_secs,
+            mb / mapped_secs,
+            mb / buffered_secs
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, IsTerminal, Write},
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use tokio::sync::broadcast;
+
+use crate::common::{
+    constants::{STDIN_BROADCAST_CAPACITY, STDIN_SPOOL_MAX_BYTES, STDIN_SPOOL_MEMORY},
+    enums::Encoding,
+    private_dir::PrivateDir,
+};
+use crate::reader::encoding;
+
+// Строки из stdin (`kubectl logs -f pod | logscraper`). Первые STDIN_SPOOL_MEMORY байт
+// держим в памяти, дальше всё уезжает во временный файл, и Page-режим читает его как обычный лог.
+// Файл растёт до STDIN_SPOOL_MAX_BYTES, потом новые строки в него не пишутся.
+// Tail-режим получает новые строки через broadcast.
+
+static SPOOL: OnceLock<Spool> = OnceLock::new();
+
+const SPOOL_FILE: &str = "stdin.log";
+
+pub struct Spool {
+    state: Mutex<SpoolState>,
+    tx: broadcast::Sender<String>,
+    memory_limit: usize,
+    file_limit: usize,
+    // Где создать личный каталог для файла спула
+    parent: PathBuf,
+}
+
+struct SpoolState {
+    lines: Vec<String>,
+    // Сколько байт в памяти, а после переноса — в файле
+    bytes: usize,
+    // Личный каталог спула и файл в нём
+    file: Option<(PrivateDir, BufWriter<File>)>,
+}
+
+// Что сейчас лежит в спуле
+pub enum Snapshot {
+    Lines(Vec<String>),
+    File(String),
+}
+
+impl Spool {
+    pub fn new(memory_limit: usize, file_limit: usize, parent: PathBuf) -> Self {
+        let (tx, _) = broadcast::channel(STDIN_BROADCAST_CAPACITY);
+        Self {
+            state: Mutex::new(SpoolState {
+                lines: Vec::new(),
+                bytes: 0,
+                file: None,
+            }),
+            tx,
+            memory_limit,
+            file_limit,
+            parent,
+        }
+    }
+
+    pub fn push(&self, line: String) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let bytes = state.bytes + line.len() + 1;
+        match state.file.as_mut() {
+            // Файл дорос до предела — строка достанется только Tail-режиму
+            Some(_) if bytes > self.file_limit => {}
+            Some((_, file)) => {
+                writeln!(file, "{}", line)?;
+                state.bytes = bytes;
+            }
+            None => {
+                state.bytes = bytes;
+                state.lines.push(line.clone());
+                if state.bytes > self.memory_limit {
+                    // Память кончилась — переносим накопленное в файл
+                    let dir = PrivateDir::create(&self.parent, "stdin")?;
+                    let mut file = BufWriter::new(dir.create_file(SPOOL_FILE)?);
+                    for l in state.lines.drain(..) {
+                        writeln!(file, "{}", l)?;
+                    }
+                    state.file = Some((dir, file));
+                }
+            }
+        }
+        // Никто не подписан — не ошибка
+        let _ = self.tx.send(line);
+        Ok(())
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.tx.subscribe()
+    }
+
+    pub fn snapshot(&self) -> io::Result<Snapshot> {
+        let mut state = self.state.lock().unwrap();
+        match state.file.as_mut() {
+            Some((dir, file)) => {
+                file.flush()?;
+                Ok(Snapshot::File(
+                    dir.path().join(SPOOL_FILE).to_string_lossy().into_owned(),
+                ))
+            }
+            None => Ok(Snapshot::Lines(state.lines.clone())),
+        }
+    }
+
+    fn cleanup(&self) {
+        // Каталог удаляется вместе с PrivateDir
+        self.state.lock().unwrap().file.take();
+    }
+}
+
+// Есть ли что читать из stdin: при запуске из терминала там клавиатура
+pub fn is_piped() -> bool {
+    !io::stdin().is_terminal()
+}
+
+pub fn spool() -> &'static Spool {
+    SPOOL.get_or_init(|| {
+        Spool::new(
+            STDIN_SPOOL_MEMORY,
+            STDIN_SPOOL_MAX_BYTES,
+            std::env::temp_dir(),
+        )
+    })
+}
+
+// Читаем stdin в отдельном потоке: чтение блокирующее и длится до закрытия трубы
+pub fn start() {
+    let spool = spool();
+    std::thread::spawn(move || {
+        let lines = match encoding::lines(io::stdin().lock(), Encoding::Utf8Lossy, true) {
+            Ok(lines) => lines,
+            Err(_) => return,
+        };
+        for line in lines {
+            let Ok(line) = line else {
+                break;
+            };
+            if spool.push(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Удаляет временный файл спула при выходе
+pub fn cleanup() {
+    if let Some(spool) = SPOOL.get() {
+        spool.cleanup();
+    }
+}
+
+// Спул лежит в static и сам не удаляется: guard убирает его и при выходе из main по ошибке
+// или панике
+pub struct CleanupGuard;
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        cleanup();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_spool_overflows_to_file() {
+        let tmp_dir = TempDir::new("spool").expect("Не получилось создать временную директорию");
+        let spool = Spool::new(16, 1024, tmp_dir.path().to_path_buf());
+        let mut rx = spool.subscribe();
+
+        spool.push("first".to_string()).unwrap();
+        match spool.snapshot().unwrap() {
+            Snapshot::Lines(lines) => assert_eq!(lines, vec!["first"]),
+            Snapshot::File(_) => panic!("Спул не должен был уйти в файл"),
+        }
+
+        spool.push("second line".to_string()).unwrap();
+        spool.push("third".to_string()).unwrap();
+        let file_path = match spool.snapshot().unwrap() {
+            Snapshot::File(path) => PathBuf::from(path),
+            Snapshot::Lines(_) => panic!("Спул должен был уйти в файл"),
+        };
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "first\nsecond line\nthird\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(file_path.parent().unwrap()), 0o700);
+            assert_eq!(mode(&file_path), 0o600);
+        }
+        assert_eq!(rx.try_recv().unwrap(), "first");
+        assert_eq!(rx.try_recv().unwrap(), "second line");
+        assert_eq!(rx.try_recv().unwrap(), "third");
+
+        spool.cleanup();
+        assert!(!file_path.parent().unwrap().exists());
+    }
+    #[test]
+    fn test_spool_file_limit() {
+        let tmp_dir = TempDir::new("spool").expect("Не получилось создать временную директорию");
+        // В памяти до 4 байт, в файле до 12
+        let spool = Spool::new(4, 12, tmp_dir.path().to_path_buf());
+        let mut rx = spool.subscribe();
+        for line in ["one", "two", "six", "ten"] {
+            spool.push(line.to_string()).unwrap();
+        }
+        let file_path = match spool.snapshot().unwrap() {
+            Snapshot::File(path) => path,
+            Snapshot::Lines(_) => panic!("Спул должен был уйти в файл"),
+        };
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\ntwo\nsix\n");
+        // Tail-режим получает и то, что в файл не влезло
+        for line in ["one", "two", "six", "ten"] {
+            assert_eq!(rx.try_recv().unwrap(), line);
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader},
+    net::{TcpListener, UdpSocket},
+    sync::mpsc::{self, Sender, UnboundedSender, error::TrySendError},
+    task::JoinSet,
+};
+
+use crate::common::{
+    constants::{SYSLOG_MAX_LENGTH_PREFIX, SYSLOG_MAX_MESSAGE},
+    enums::Filter,
+    structs::{Log, SyslogConfig},
+};
+use crate::reader::{rotating::RotatingFile, throughput::Throughput};
+
+// Приёмник syslog (RFC3164 и RFC5424) по UDP и TCP. Каждое сообщение приводится к строке
+// "<RFC3339-время> <хост> <важность> <текст>", чтобы работали датовые фильтры и сортировка,
+// а хост отправителя становится именем источника.
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct Message {
+    pub host: String,
+    pub line: String,
+}
+
+// peer — адрес отправителя; им подменяется хост, если в сообщении его нет
+pub fn parse(raw: &str, peer: &str, received: DateTime<Utc>) -> Message {
+    let raw = raw.trim_end_matches(['\r', '\n', '\0']);
+    let Some((severity, rest)) = split_pri(raw) else {
+        return finish(received, None, None, raw, peer);
+    };
+    match rest.strip_prefix("1 ") {
+        Some(rest) => parse_5424(rest, severity, peer, received),
+        None => parse_3164(rest, severity, peer, received),
+    }
+}
+
+fn split_pri(raw: &str) -> Option<(&'static str, &str)> {
+    let rest = raw.strip_prefix('<')?;
+    let end = rest.find('>')?;
+    let pri: u8 = rest[..end].parse().ok()?;
+    if end > 3 || pri > 191 {
+        return None;
+    }
+    Some((SEVERITIES[(pri & 7) as usize], &rest[end + 1..]))
+}
+
+// <PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID [SD] MSG
+fn parse_5424(rest: &str, severity: &str, peer: &str, received: DateTime<Utc>) -> Message {
+    let mut fields = rest.splitn(6, ' ');
+    let (Some(ts), Some(host), Some(app), Some(procid), Some(_msgid)) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) else {
+        return finish(received, None, Some(severity), rest, peer);
+    };
+    let time = DateTime::parse_from_rfc3339(ts)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(received);
+    let msg = skip_structured_data(fields.next().unwrap_or(""));
+    let msg = msg.strip_prefix('\u{FEFF}').unwrap_or(msg);
+
+    let body = match (app, procid) {
+        ("-", _) => msg.to_string(),
+        (app, "-") => format!("{}: {}", app, msg),
+        (app, procid) => format!("{}[{}]: {}", app, procid, msg),
+    };
+    finish(time, nil(host), Some(severity), &body, peer)
+}
+
+// "-" или [id k="v"][...] перед текстом; внутри значений ']' экранируется '\'
+fn skip_structured_data(rest: &str) -> &str {
+    if let Some(msg) = rest.strip_prefix('-') {
+        return msg.strip_prefix(' ').unwrap_or(msg);
+    }
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while bytes.get(i) == Some(&b'[') {
+        let mut in_quotes = false;
+        i += 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 1,
+                b'"' => in_quotes = !in_quotes,
+                b']' if !in_quotes => break,
+                _ => {}
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    let msg = rest.get(i..).unwrap_or("");
+    msg.strip_prefix(' ').unwrap_or(msg)
+}
+
+// <PRI>Mmm dd hh:mm:ss HOSTNAME TAG: MSG; время местное и без года
+fn parse_3164(rest: &str, severity: &str, peer: &str, received: DateTime<Utc>) -> Message {
+    if let Some(ts) = rest.get(..15)
+        && let Some(time) = bsd_time(ts, received)
+    {
+        let rest = rest[15..].trim_start();
+        let (host, body) = rest.split_once(' ').unwrap_or((rest, ""));
+        return finish(time, nil(host), Some(severity), body, peer);
+    }
+    // Некоторые отправители пишут RFC3339 вместо BSD-времени
+    if let Some((ts, rest)) = rest.split_once(' ')
+        && let Ok(time) = DateTime::parse_from_rfc3339(ts)
+    {
+        let (host, body) = rest.split_once(' ').unwrap_or((rest, ""));
+        return finish(
+            time.with_timezone(&Utc),
+            nil(host),
+            Some(severity),
+            body,
+            peer,
+        );
+    }
+    finish(received, None, Some(severity), rest, peer)
+}
+
+fn bsd_time(ts: &str, received: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let year = received.with_timezone(&Local).year();
+    let parse = |year: i32| {
+        NaiveDateTime::parse_from_str(&format!("{} {}", year, ts), "%Y %b %e %H:%M:%S")
+            .ok()
+            .and_then(|t| Local.from_local_datetime(&t).earliest())
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let time = parse(year)?;
+    // Сообщение из конца прошлого года, принятое в январе
+    if time > received + chrono::Duration::days(1) {
+        return parse(year - 1);
+    }
+    Some(time)
+}
+
+fn nil(field: &str) -> Option<&str> {
+    (!field.is_empty() && field != "-").then_some(field)
+}
+
+fn finish(
+    time: DateTime<Utc>,
+    host: Option<&str>,
+    severity: Option<&str>,
+    body: &str,
+    peer: &str,
+) -> Message {
+    let host = host.unwrap_or(peer).to_string();
+    let time = time.to_rfc3339_opts(SecondsFormat::Secs, false);
+    let line = match severity {
+        Some(severity) => format!("{} {} {} {}", time, host, severity, body),
+        None => format!("{} {} {}", time, host, body),
+    };
+    Message { host, line }
+}
+
+// Кадр TCP: "LEN SP MSG" (octet counting) или строка до '\n'/'\0' (non-transparent).
+// false — соединение закрыто
+async fn read_frame(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    buf: &mut Vec<u8>,
+) -> io::Result<bool> {
+    buf.clear();
+    let first = match reader.fill_buf().await?.first() {
+        Some(b) => *b,
+        None => return Ok(false),
+    };
+    if first.is_ascii_digit() {
+        // Длина ограничена, чтобы цифры без пробела не копились в памяти
+        (&mut *reader)
+            .take(SYSLOG_MAX_LENGTH_PREFIX as u64)
+            .read_until(b' ', buf)
+            .await?;
+        let len: usize = std::str::from_utf8(buf)
+            .ok()
+            .and_then(|s| s.strip_suffix(' '))
+            .and_then(|s| s.parse().ok())
+            .filter(|len| *len <= SYSLOG_MAX_MESSAGE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad syslog frame length"))?;
+        buf.resize(len, 0);
+        reader.read_exact(buf).await?;
+        return Ok(true);
+    }
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(!buf.is_empty());
+        }
+        match available.iter().position(|b| *b == b'\n' || *b == 0) {
+            Some(i) => {
+                buf.extend_from_slice(&available[..i]);
+                reader.consume(i + 1);
+                return Ok(true);
+            }
+            None => {
+                let n = available.len();
+                buf.extend_from_slice(available);
+                reader.consume(n);
+                if buf.len() > SYSLOG_MAX_MESSAGE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "syslog message too long",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// Сокеты открываются заранее, чтобы ошибка привязки дошла до вызывающего
+pub struct Receiver {
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpListener>,
+    spool: Option<RotatingFile>,
+    buffer_lines: usize,
+}
+
+impl Receiver {
+    pub async fn bind(config: &SyslogConfig) -> io::Result<Self> {
+        let udp = match &config.udp {
+            Some(addr) => Some(UdpSocket::bind(addr).await?),
+            None => None,
+        };
+        let tcp = match &config.tcp {
+            Some(addr) => Some(TcpListener::bind(addr).await?),
+            None => None,
+        };
+        let spool = match &config.spool {
+            Some(path) => Some(RotatingFile::open(
+                path,
+                config.spool_max_bytes,
+                config.spool_files,
+            )?),
+            None => None,
+        };
+        Ok(Self {
+            udp,
+            tcp,
+            spool,
+            buffer_lines: config.buffer_lines.max(1),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn local_addrs(&self) -> (Option<SocketAddr>, Option<SocketAddr>) {
+        (
+            self.udp.as_ref().and_then(|s| s.local_addr().ok()),
+            self.tcp.as_ref().and_then(|s| s.local_addr().ok()),
+        )
+    }
+
+    pub async fn run(
+        mut self,
+        filters: Arc<Vec<Filter>>,
+        tx: UnboundedSender<Log>,
+        throughput: Arc<Throughput>,
+    ) {
+        // Очередь ограничена: при наплыве новые сообщения отбрасываются и считаются по хосту
+        let (msg_tx, mut msg_rx) = mpsc::channel::<Message>(self.buffer_lines);
+        let mut tasks = JoinSet::new();
+        if let Some(udp) = self.udp.take() {
+            tasks.spawn(receive_udp(udp, msg_tx.clone(), throughput.clone()));
+        }
+        if let Some(tcp) = self.tcp.take() {
+            tasks.spawn(receive_tcp(tcp, msg_tx.clone(), throughput.clone()));
+        }
+        drop(msg_tx);
+
+        while let Some(message) = msg_rx.recv().await {
+            // В спул пишется всё: фильтры в Page Mode могут быть другими
+            if let Some(spool) = self.spool.as_mut()
+                && spool.write_line(&message.line).is_err()
+            {
+                self.spool = None;
+            }
+            let passed = filters.iter().all(|f| f.is_include(&message.line));
+            throughput.record(&message.host, passed);
+            if !passed {
+                continue;
+            }
+            let log = Log {
+                date_time: Utc::now(),
+                data: message.line,
+                source_name: message.host,
+            };
+            if tx.send(log).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+// false — обработчик закрылся и принимать больше незачем
+fn enqueue(msg_tx: &Sender<Message>, message: Message, throughput: &Throughput) -> bool {
+    match msg_tx.try_send(message) {
+        Ok(()) => true,
+        Err(TrySendError::Full(message)) => {
+            throughput.record_dropped(&message.host);
+            true
+        }
+        Err(TrySendError::Closed(_)) => false,
+    }
+}
+
+async fn receive_udp(socket: UdpSocket, msg_tx: Sender<Message>, throughput: Arc<Throughput>) {
+    let mut buf = vec![0u8; SYSLOG_MAX_MESSAGE];
+    while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+        let raw = String::from_utf8_lossy(&buf[..len]);
+        // В одной датаграмме бывает несколько строк
+        for raw in raw.split('\n').filter(|r| !r.trim().is_empty()) {
+            let message = parse(raw, &peer.ip().to_string(), Utc::now());
+            if !enqueue(&msg_tx, message, &throughput) {
+                return;
+            }
+        }
+    }
+}
+
+async fn receive_tcp(listener: TcpListener, msg_tx: Sender<Message>, throughput: Arc<Throughput>) {
+    // Соединения закрываются вместе с приёмником
+    let mut connections = JoinSet::new();
+    while let Ok((stream, peer)) = listener.accept().await {
+        let msg_tx = msg_tx.clone();
+        let throughput = throughput.clone();
+        connections.spawn(async move {
+            let peer = peer.ip().to_string();
+            let mut reader = BufReader::new(stream);
+            let mut buf = Vec::new();
+            while let Ok(true) = read_frame(&mut reader, &mut buf).await {
+                let raw = String::from_utf8_lossy(&buf);
+                if raw.trim().is_empty() {
+                    continue;
+                }
+                if !enqueue(&msg_tx, parse(&raw, &peer, Utc::now()), &throughput) {
+                    return;
+                }
+            }
+        });
+        // Завершённые соединения не копятся
+        while connections.try_join_next().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use std::fs;
+
+    use tempdir::TempDir;
+    use tokio::{io::AsyncWriteExt, net::TcpStream, sync::mpsc::unbounded_channel};
+
+    use super::*;
+
+    fn received() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_rfc5424() {
+        let m = parse(
+            "<165>1 2026-10-17T22:14:15.003+02:00 mymachine evntslog 42 ID47 \
+             [exampleSDID@32473 iut=\"3\" eventSource=\"App\\]\"] \u{FEFF}An application event\n",
+            "10.0.0.1",
+            received(),
+        );
+        assert_eq!(m.host, "mymachine");
+        assert_eq!(
+            m.line,
+            "2026-10-17T20:14:15+00:00 mymachine notice evntslog[42]: An application event"
+        );
+
+        let m = parse("<11>1 - - - - - - disk full", "10.0.0.1", received());
+        assert_eq!(m.host, "10.0.0.1");
+        assert_eq!(m.line, "2026-10-18T12:00:00+00:00 10.0.0.1 err disk full");
+    }
+
+    #[test]
+    fn test_parse_rfc3164() {
+        let m = parse(
+            "<34>Oct 11 22:14:15 web01 su: 'su root' failed",
+            "10.0.0.2",
+            received(),
+        );
+        assert_eq!(m.host, "web01");
+        let expected = Local
+            .with_ymd_and_hms(2026, 10, 11, 22, 14, 15)
+            .unwrap()
+            .with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Secs, false);
+        assert_eq!(
+            m.line,
+            format!("{} web01 crit su: 'su root' failed", expected)
+        );
+
+        // Без PRI и времени — время приёма и адрес отправителя
+        let m = parse("plain text", "10.0.0.3", received());
+        assert_eq!(m.line, "2026-10-18T12:00:00+00:00 10.0.0.3 plain text");
+    }
+
+    #[tokio::test]
+    async fn test_receive_udp_and_tcp() {
+        let tmp_dir = TempDir::new("syslog").expect("Не получилось создать временную директорию");
+        let spool = tmp_dir.path().join("spool.log");
+        let config = SyslogConfig {
+            udp: Some("127.0.0.1:0".to_string()),
+            tcp: Some("127.0.0.1:0".to_string()),
+            spool: Some(spool.to_str().unwrap().to_string()),
+            spool_max_bytes: 1024 * 1024,
+            spool_files: 1,
+            buffer_lines: 16,
+        };
+        let receiver = Receiver::bind(&config).await.unwrap();
+        let (udp_addr, tcp_addr) = receiver.local_addrs();
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let filters = Arc::new(vec![Filter::Search(crate::common::structs::SearchFilter {
+            substr: "keep".to_string(),
+        })]);
+        let handle = tokio::spawn(receiver.run(filters, tx, Arc::new(Throughput::default())));
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        client
+            .send_to(b"<14>1 - udphost app - - - keep udp", udp_addr.unwrap())
+            .await
+            .unwrap();
+        let mut stream = TcpStream::connect(tcp_addr.unwrap()).await.unwrap();
+        // Octet counting, затем две строки с переводом строки и одна отброшенная фильтром
+        let framed = "<14>1 - tcphost app - - - keep one";
+        stream
+            .write_all(format!("{} {}", framed.len(), framed).as_bytes())
+            .await
+            .unwrap();
+        stream
+            .write_all(
+                b"<14>Oct 11 22:14:15 bsdhost app: keep two\n<14>1 - tcphost app - - - drop\n",
+            )
+            .await
+            .unwrap();
+        drop(stream);
+
+        let mut got = Vec::new();
+        for _ in 0..3 {
+            let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+                .await
+                .expect("Сообщение не пришло")
+                .unwrap();
+            got.push((
+                log.source_name,
+                log.data.split_once(' ').unwrap().1.to_string(),
+            ));
+        }
+        got.sort();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "bsdhost".to_string(),
+                    "bsdhost info app: keep two".to_string()
+                ),
+                (
+                    "tcphost".to_string(),
+                    "tcphost info app: keep one".to_string()
+                ),
+                (
+                    "udphost".to_string(),
+                    "udphost info app: keep udp".to_string()
+                ),
+            ]
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(fs::read_to_string(&spool).unwrap().lines().count(), 4);
+        handle.abort();
+    }
+
+    #[test]
+    fn test_full_queue_drops_and_counts() {
+        let throughput = Throughput::default();
+        let (msg_tx, msg_rx) = mpsc::channel::<Message>(1);
+        let message = || parse("<14>1 - flood app - - - x", "10.0.0.1", Utc::now());
+        for _ in 0..3 {
+            assert!(enqueue(&msg_tx, message(), &throughput));
+        }
+        let snapshots = throughput.snapshot(Utc::now());
+        assert_eq!(
+            (snapshots[0].name.as_str(), snapshots[0].dropped),
+            ("flood", 2)
+        );
+        assert_eq!(snapshots[0].total, 0);
+
+        drop(msg_rx);
+        assert!(!enqueue(&msg_tx, message(), &throughput));
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_length_prefix() {
+        let mut buf = Vec::new();
+        let mut reader = &b"5 hello"[..];
+        assert!(read_frame(&mut reader, &mut buf).await.unwrap());
+        assert_eq!(buf, b"hello");
+
+        // Цифры без пробела дальше предела не читаются
+        let digits = vec![b'1'; 1024 * 1024];
+        let mut reader = &digits[..];
+        let error = read_frame(&mut reader, &mut buf).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(buf.len() <= SYSLOG_MAX_LENGTH_PREFIX);
+    }
+}
@@ -1,18 +1,81 @@
-use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use notify::{Event, EventKind, Watcher, recommended_watcher};
 use tokio::{
     fs::File,
-    io::{AsyncBufReadExt, AsyncSeekExt, BufReader},
-    sync::mpsc::{self, UnboundedSender},
+    io::{AsyncSeekExt, BufReader},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::{self, UnboundedSender},
+    },
+    task::JoinSet,
+};
+
+use chrono::Utc;
+
+use crate::alert::{self, AlertStatus};
+use crate::common::{
+    constants::STDIN_PATH,
+    enums::{Encoding, LogFormat},
+    structs::{Log, Memory, Stream},
 };
+use crate::reader::{
+    command::{self, CommandStatus},
+    container::Reassembler,
+    encoding, ingest, journal, stdin, syslog,
+    throughput::Throughput,
+};
+use crate::remote::client;
+
+// Приёмники (syslog, HTTP), которые не смогли занять адрес, и ошибка для каждого;
+// остальные источники хвоста при этом продолжают работать
+#[derive(Default)]
+pub struct ListenerStatus {
+    failed: Mutex<Vec<(String, String)>>,
+    // Без TUI ошибку больше некуда показать, кроме stderr
+    stderr: bool,
+}
+
+impl ListenerStatus {
+    pub fn stderr() -> Self {
+        Self {
+            stderr: true,
+            ..Default::default()
+        }
+    }
 
-use crate::common::structs::{Memory, Stream};
+    pub fn fail(&self, listener: &str, error: String) {
+        if self.stderr {
+            eprintln!("logscraper: {}: {}", listener, error);
+        }
+        self.failed.lock().unwrap().push((listener.to_string(), error));
+    }
+
+    pub fn failed(&self) -> Vec<(String, String)> {
+        self.failed.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.failed.lock().unwrap().clear();
+    }
+}
 
 #[derive(Debug)]
 struct TrackedFile {
     reader: BufReader<File>,
     position: u64,
+    encoding: Encoding,
+    source_name: String,
+    // Начало строки, которую ещё не дописали
+    pending: Vec<u8>,
+    // Части строк контейнерного лога
+    reassembler: Reassembler,
 }
 
 #[allow(dead_code)]
@@ -50,6 +113,10 @@ pub async fn tail(stream: Stream) -> Result<(), Box<dyn Error + Send + Sync>> {
             TrackedFile {
                 reader,
                 position: size,
+                encoding: encoding::effective(&path.to_string_lossy(), Encoding::default())?,
+                source_name: path.to_string_lossy().into_owned(),
+                pending: Vec::new(),
+                reassembler: Reassembler::new(LogFormat::Plain),
             },
         );
     }
@@ -59,7 +126,7 @@ pub async fn tail(stream: Stream) -> Result<(), Box<dyn Error + Send + Sync>> {
             Some(changed_path) = notify_rx.recv() => {
 
                 if let Some(tracked) = tracked_files.get_mut(&changed_path) {
-                    read_new_lines(&mut tracked.reader, &mut tracked.position, &stream).await?;
+                    read_new_lines(tracked, &stream).await?;
                 }
             }
         }
@@ -68,11 +135,61 @@ pub async fn tail(stream: Stream) -> Result<(), Box<dyn Error + Send + Sync>> {
 
 pub async fn tail_stream(
     memory: Memory,
-    tx: UnboundedSender<String>,
+    tx: UnboundedSender<Log>,
+    command_status: Arc<CommandStatus>,
+    throughput: Arc<Throughput>,
+    alert_status: Arc<AlertStatus>,
+    listener_status: Arc<ListenerStatus>,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let paths: Vec<PathBuf> = memory.paths.iter().map(|p| PathBuf::from(&p.path)).collect();
     let filters = memory.filters;
 
+    // Команды, приёмники syslog и HTTP, хвосты агентов живут, пока жив хвост: JoinSet отменяет задачи при выходе,
+    // группы процессов команд убиваются вместе с ними
+    let mut live_sources = JoinSet::new();
+    // Строки всех источников проходят через правила оповещений и только потом уходят получателю
+    let (line_tx, line_rx) = mpsc::unbounded_channel::<Log>();
+    live_sources.spawn(alert::watch(memory.alerts, alert_status, line_rx, tx));
+    let tx = line_tx;
+    let command_filters = Arc::new(filters.clone());
+    for source in memory.commands {
+        live_sources.spawn(command::run_command(
+            source,
+            command_filters.clone(),
+            tx.clone(),
+            command_status.clone(),
+            throughput.clone(),
+        ));
+    }
+    // Занятый адрес останавливает только свой приёмник
+    if let Some(config) = memory.syslog {
+        let (filters, tx, throughput) = (command_filters.clone(), tx.clone(), throughput.clone());
+        let listener_status = listener_status.clone();
+        live_sources.spawn(async move {
+            match syslog::Receiver::bind(&config).await {
+                Ok(receiver) => receiver.run(filters, tx, throughput).await,
+                Err(e) => listener_status.fail("syslog", e.to_string()),
+            }
+        });
+    }
+    if let Some(config) = memory.ingest {
+        let (filters, tx, throughput) = (command_filters.clone(), tx.clone(), throughput.clone());
+        let listener_status = listener_status.clone();
+        live_sources.spawn(async move {
+            match ingest::Ingest::bind(&config).await {
+                Ok(ingest) => ingest.run(filters, tx, throughput).await,
+                Err(e) => listener_status.fail("ingest", e.to_string()),
+            }
+        });
+    }
+    for agent in memory.agents {
+        live_sources.spawn(client::tail(
+            agent,
+            command_filters.clone(),
+            tx.clone(),
+            throughput.clone(),
+        ));
+    }
+
     let (notify_tx, mut notify_rx) = mpsc::unbounded_channel::<PathBuf>();
 
     let mut watcher = recommended_watcher(move |res: Result<Event, _>| {
@@ -86,20 +203,36 @@ pub async fn tail_stream(
     })?;
 
     let mut tracked_files: HashMap<PathBuf, TrackedFile> = HashMap::new();
-    for path in &paths {
-        let metadata = fs::metadata(path).unwrap();
-        watcher.watch(path, notify::RecursiveMode::NonRecursive)?;
+    // stdin не файл: новые строки приходят из спула, а не от notify
+    let mut stdin_rx: Option<(broadcast::Receiver<String>, String)> = None;
+    for source in &memory.paths {
+        if source.path == STDIN_PATH {
+            stdin_rx = Some((stdin::spool().subscribe(), source.name.clone()));
+            continue;
+        }
+        // Журнал — выгрузка с чужой машины, новых записей в нём не будет
+        if journal::effective(&source.path, source.format)? == LogFormat::Journal {
+            continue;
+        }
+        // notify присылает абсолютные пути, поэтому ключ — канонический путь
+        let path = fs::canonicalize(&source.path)?;
+        let metadata = fs::metadata(&path)?;
+        watcher.watch(&path, notify::RecursiveMode::NonRecursive)?;
 
-        let file = File::open(path).await?;
+        let file = File::open(&path).await?;
         let size = metadata.len();
 
         let mut reader = BufReader::new(file);
         reader.seek(std::io::SeekFrom::Start(size)).await?;
         tracked_files.insert(
-            path.clone().to_path_buf(),
+            path,
             TrackedFile {
                 reader,
                 position: size,
+                encoding: encoding::effective(&source.path, source.encoding)?,
+                source_name: source.name.clone(),
+                pending: Vec::new(),
+                reassembler: Reassembler::new(source.format),
             },
         );
     }
@@ -108,74 +241,108 @@ pub async fn tail_stream(
         tokio::select! {
             Some(changed_path) = notify_rx.recv() => {
                 if let Some(tracked) = tracked_files.get_mut(&changed_path) {
-                    read_new_lines_with_filters(&mut tracked.reader, &mut tracked.position, &tx, &filters).await?;
+                    read_new_lines_with_filters(tracked, &tx, &filters, &throughput).await?;
+                }
+            }
+            line = recv_stdin(&mut stdin_rx) => {
+                let Some((line, source_name)) = line else {
+                    // stdin закрыт — дальше следим только за файлами
+                    stdin_rx = None;
+                    continue;
+                };
+                let passed = filters.iter().all(|f| f.is_include(&line));
+                throughput.record(&source_name, passed);
+                if !passed {
+                    continue;
+                }
+                let log = Log {
+                    date_time: Utc::now(),
+                    data: line,
+                    source_name,
+                };
+                if tx.send(log).is_err() {
+                    return Ok(());
                 }
             }
         }
     }
 }
 
-#[allow(dead_code)]
-async fn read_new_lines(
-    reader: &mut BufReader<File>,
-    position: &mut u64,
-    stream: &Stream,
-) -> Result<(), std::io::Error> {
-    let mut buf = String::new();
+// Следующая строка stdin; без подписки ждёт вечно, чтобы ветка select не срабатывала
+async fn recv_stdin(
+    stdin_rx: &mut Option<(broadcast::Receiver<String>, String)>,
+) -> Option<(String, String)> {
+    let Some((rx, name)) = stdin_rx else {
+        return std::future::pending().await;
+    };
     loop {
-        let bytes_read = reader.read_line(&mut buf).await?;
-
-        if bytes_read == 0 {
-            break;
+        match rx.recv().await {
+            Ok(line) => return Some((line, name.clone())),
+            // Отстали — пропущенные строки всё равно есть в Page-режиме
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
         }
+    }
+}
 
-        if !buf.ends_with('\n') {
-            break;
+// Следующая полная строка файла. Недописанный хвост остаётся в pending
+// и дочитывается при следующем изменении файла, а не теряется.
+// Части контейнерных строк копятся в reassembler, пока строка не закончится.
+async fn next_line(tracked: &mut TrackedFile) -> Result<Option<String>, std::io::Error> {
+    loop {
+        let bytes_read = encoding::read_raw_line_async(
+            &mut tracked.reader,
+            tracked.encoding,
+            &mut tracked.pending,
+        )
+        .await?;
+        tracked.position += bytes_read as u64;
+        if !encoding::is_terminated(tracked.encoding, &tracked.pending) {
+            return Ok(None);
         }
+        let line = encoding::decode(
+            tracked.encoding,
+            encoding::strip_terminator(tracked.encoding, &tracked.pending),
+        );
+        tracked.pending.clear();
+        if let Some(line) = tracked.reassembler.push(line) {
+            return Ok(Some(line));
+        }
+    }
+}
 
-        let line = buf.trim_end_matches('\n').to_string();
-        *position += bytes_read as u64;
-
+#[allow(dead_code)]
+async fn read_new_lines(tracked: &mut TrackedFile, stream: &Stream) -> Result<(), std::io::Error> {
+    while let Some(line) = next_line(tracked).await? {
         if let Err(err) = stream.send(line).await {
             eprintln!("Error sending to stream: {:?}", err);
         }
-        buf.clear();
     }
     Ok(())
 }
 
 async fn read_new_lines_with_filters(
-    reader: &mut BufReader<File>,
-    position: &mut u64,
-    tx: &UnboundedSender<String>,
+    tracked: &mut TrackedFile,
+    tx: &UnboundedSender<Log>,
     filters: &[crate::common::enums::Filter],
+    throughput: &Throughput,
 ) -> Result<(), std::io::Error> {
-    let mut buf = String::new();
-    loop {
-        let bytes_read = reader.read_line(&mut buf).await?;
-
-        if bytes_read == 0 {
-            break;
+    while let Some(line) = next_line(tracked).await? {
+        // Apply filters - only send line if it passes all filters
+        let passed = filters.iter().all(|f| f.is_include(&line));
+        throughput.record(&tracked.source_name, passed);
+        if !passed {
+            continue;
         }
-
-        if !buf.ends_with('\n') {
+        let log = Log {
+            date_time: Utc::now(),
+            data: line,
+            source_name: tracked.source_name.clone(),
+        };
+        if tx.send(log).is_err() {
+            // Channel closed, stop reading
             break;
         }
-
-        let line = buf.trim_end_matches('\n').to_string();
-        
-        // Apply filters - only send line if it passes all filters
-        let should_send = filters.iter().all(|f| f.is_include(&line));
-        
-        if should_send {
-            if let Err(_) = tx.send(line.clone()) {
-                // Channel closed, stop reading
-                break;
-            }
-        }
-        
-        *position += bytes_read as u64;
-        buf.clear();
     }
     Ok(())
 }
@@ -256,4 +423,94 @@ mod tests {
         assert_eq!(msg, "new test log");
         handle.abort();
     }
+
+    #[tokio::test]
+    async fn test_live_tail_keeps_partial_line() {
+        let random_path = random_str::get_string(6, true, false, true, true);
+        let tmp_dir = TempDir::new(&random_path).expect("не удалось создать временную директорию");
+        let file_path = tmp_dir.path().join("test.log");
+        std::fs::write(&file_path, "").unwrap();
+
+        let (tx, mut rx) = unbounded_channel::<String>();
+        let stream = Stream::new(
+            Batch::new(
+                1,
+                crate::common::enums::Order::OrderByDate,
+                Some(vec![Source::new(
+                    file_path.to_str().unwrap().to_string(),
+                    "test".to_string(),
+                )]),
+                None,
+                None,
+            ),
+            tx,
+        );
+        let handle = tokio::spawn(async move {
+            let _ = tail(stream).await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Строка дописывается в два приёма и содержит невалидный UTF-8
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        f.write_all(b"half ").unwrap();
+        f.flush().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        f.write_all(b"and \xFF half\n").unwrap();
+        drop(f);
+
+        let msg = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(msg, "half and \u{FFFD} half");
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_busy_listener_keeps_other_sources() {
+        // Порты уже заняты: syslog и HTTP не запустятся, а команда должна работать
+        let busy = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let busy_tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let memory = Memory {
+            syslog: Some(crate::common::structs::SyslogConfig {
+                udp: Some(busy.local_addr().unwrap().to_string()),
+                ..Default::default()
+            }),
+            commands: vec![crate::common::structs::CommandSource {
+                name: "cmd".to_string(),
+                command: "echo alive; sleep 1000".to_string(),
+            }],
+            ingest: Some(crate::common::structs::IngestConfig::new(
+                busy_tcp.local_addr().unwrap().to_string(),
+            )),
+            ..Default::default()
+        };
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let listener_status = Arc::new(ListenerStatus::default());
+        let handle = tokio::spawn(tail_stream(
+            memory,
+            tx,
+            Arc::new(CommandStatus::default()),
+            Arc::new(Throughput::default()),
+            Arc::new(AlertStatus::default()),
+            listener_status.clone(),
+        ));
+
+        let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("Команда не прислала строку")
+            .unwrap();
+        assert_eq!(log.data, "alive");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut failed: Vec<String> = listener_status.failed().into_iter().map(|(l, _)| l).collect();
+        failed.sort();
+        assert_eq!(failed, vec!["ingest", "syslog"]);
+        assert!(!handle.is_finished());
+        handle.abort();
+    }
 }
@@ -0,0 +1,170 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+
+use crate::common::constants::RATE_WINDOW;
+
+// Сколько строк приходит в хвост по каждому источнику: считает сам читатель хвоста,
+// до фильтров и после, а TUI только показывает
+#[derive(Default)]
+pub struct Throughput {
+    sources: Mutex<BTreeMap<String, SourceRate>>,
+}
+
+struct SourceRate {
+    total: u64,
+    passed: u64,
+    // Не дошли до обработки: очередь приёма была полна
+    dropped: u64,
+    last: Option<DateTime<Utc>>,
+    // Кольцо по секундам: секунда Unix и число строк в ней
+    seconds: [(i64, u64); RATE_WINDOW],
+}
+
+// Срез для отрисовки; rate — строк в секунду за последние RATE_WINDOW секунд, от старых к новым
+#[derive(Debug, PartialEq)]
+pub struct RateSnapshot {
+    pub name: String,
+    pub total: u64,
+    pub passed: u64,
+    pub dropped: u64,
+    pub last: Option<DateTime<Utc>>,
+    pub rate: Vec<u64>,
+}
+
+impl SourceRate {
+    fn new() -> SourceRate {
+        SourceRate {
+            total: 0,
+            passed: 0,
+            dropped: 0,
+            last: None,
+            seconds: [(0, 0); RATE_WINDOW],
+        }
+    }
+
+    fn add(&mut self, passed: bool, now: DateTime<Utc>) {
+        self.total += 1;
+        if passed {
+            self.passed += 1;
+        }
+        self.last = Some(now);
+        let second = now.timestamp();
+        let slot = &mut self.seconds[second.rem_euclid(RATE_WINDOW as i64) as usize];
+        if slot.0 != second {
+            *slot = (second, 0);
+        }
+        slot.1 += 1;
+    }
+
+    fn rate(&self, now: i64) -> Vec<u64> {
+        (now - RATE_WINDOW as i64 + 1..=now)
+            .map(|second| {
+                let slot = self.seconds[second.rem_euclid(RATE_WINDOW as i64) as usize];
+                if slot.0 == second { slot.1 } else { 0 }
+            })
+            .collect()
+    }
+}
+
+impl Throughput {
+    // passed — строка прошла фильтры и ушла на экран
+    pub fn record(&self, source: &str, passed: bool) {
+        self.record_at(source, passed, Utc::now());
+    }
+
+    fn record_at(&self, source: &str, passed: bool, now: DateTime<Utc>) {
+        let mut sources = self.sources.lock().unwrap();
+        match sources.get_mut(source) {
+            Some(rate) => rate.add(passed, now),
+            None => {
+                let mut rate = SourceRate::new();
+                rate.add(passed, now);
+                sources.insert(source.to_string(), rate);
+            }
+        }
+    }
+
+    // Строка источника отброшена ещё до фильтров
+    pub fn record_dropped(&self, source: &str) {
+        let mut sources = self.sources.lock().unwrap();
+        sources
+            .entry(source.to_string())
+            .or_insert_with(SourceRate::new)
+            .dropped += 1;
+    }
+
+    // Источники по имени; пустой, пока в хвост не пришло ни строки
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Vec<RateSnapshot> {
+        let sources = self.sources.lock().unwrap();
+        sources
+            .iter()
+            .map(|(name, rate)| RateSnapshot {
+                name: name.clone(),
+                total: rate.total,
+                passed: rate.passed,
+                dropped: rate.dropped,
+                last: rate.last,
+                rate: rate.rate(now.timestamp()),
+            })
+            .collect()
+    }
+
+    // Сумма по всем источникам
+    pub fn overall(snapshots: &[RateSnapshot]) -> RateSnapshot {
+        let mut overall = RateSnapshot {
+            name: "all".to_string(),
+            total: 0,
+            passed: 0,
+            dropped: 0,
+            last: None,
+            rate: vec![0; RATE_WINDOW],
+        };
+        for snapshot in snapshots {
+            overall.total += snapshot.total;
+            overall.passed += snapshot.passed;
+            overall.dropped += snapshot.dropped;
+            overall.last = overall.last.max(snapshot.last);
+            for (sum, count) in overall.rate.iter_mut().zip(&snapshot.rate) {
+                *sum += count;
+            }
+        }
+        overall
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[test]
+    fn test_throughput() {
+        let throughput = Throughput::default();
+        let start = DateTime::from_timestamp(1_792_238_400, 0).unwrap();
+        for i in 0..3 {
+            throughput.record_at("api", i != 1, start);
+        }
+        throughput.record_at("web", true, start + TimeDelta::seconds(2));
+        // Через окно слот той же секунды переиспользуется
+        let later = start + TimeDelta::seconds(RATE_WINDOW as i64);
+        throughput.record_at("api", true, later);
+
+        let snapshots = throughput.snapshot(later);
+        assert_eq!(snapshots.len(), 2);
+        let api = &snapshots[0];
+        assert_eq!((api.name.as_str(), api.total, api.passed), ("api", 4, 3));
+        assert_eq!(api.last, Some(later));
+        assert_eq!(api.rate.len(), RATE_WINDOW);
+        assert_eq!(api.rate.iter().sum::<u64>(), 1);
+        assert_eq!(api.rate.last(), Some(&1));
+
+        // Строка web пришла за RATE_WINDOW - 2 секунды до later — второй столбец
+        let overall = Throughput::overall(&snapshots);
+        assert_eq!((overall.total, overall.passed), (5, 4));
+        assert_eq!(overall.last, Some(later));
+        assert_eq!(overall.rate[1], 1);
+        assert_eq!(overall.rate.iter().sum::<u64>(), 2);
+    }
+}
@@ -0,0 +1,414 @@
+use std::{io, sync::Arc};
+
+use regex::Regex;
+use tokio::{
+    net::{
+        TcpListener, TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::mpsc::unbounded_channel,
+    task::JoinSet,
+};
+
+use crate::common::{
+    constants::{REMOTE_HANDSHAKE_TIMEOUT, REMOTE_MAX_FRAME, REMOTE_PAGE_LINES},
+    enums::Filter,
+    structs::{Log, Memory, Path},
+};
+use crate::alert::AlertStatus;
+use crate::reader::{
+    command::CommandStatus,
+    file::read_from_paths,
+    tail::{ListenerStatus, tail_stream},
+    throughput::Throughput,
+};
+use crate::remote::protocol::{
+    PROTOCOL_VERSION, Request, Response, read_frame, read_hello, write_frame,
+};
+
+// Агент отдаёт клиентам только свои источники: путь к файлу клиент не передаёт, только имя
+pub struct Agent {
+    paths: Vec<Path>,
+    token: String,
+}
+
+impl Agent {
+    pub fn new(paths: Vec<Path>, token: String) -> Self {
+        Self { paths, token }
+    }
+
+    pub async fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let agent = Arc::new(self);
+        // Соединения закрываются вместе с агентом
+        let mut connections = JoinSet::new();
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let agent = agent.clone();
+            connections.spawn(async move {
+                let _ = agent.handle(stream).await;
+            });
+            // Завершённые соединения не копятся
+            while connections.try_join_next().is_some() {}
+        }
+    }
+
+    async fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let (mut reader, mut writer) = stream.into_split();
+        let hello = tokio::time::timeout(REMOTE_HANDSHAKE_TIMEOUT, read_hello(&mut reader))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no handshake"))??;
+        let denied = match hello {
+            Some(Request::Hello { version, .. }) if version != PROTOCOL_VERSION => {
+                Some(format!("unsupported protocol version {}", version))
+            }
+            Some(Request::Hello { token, .. }) if same_token(&token, &self.token) => None,
+            _ => Some("invalid token".to_string()),
+        };
+        if let Some(reason) = denied {
+            return write_frame(&mut writer, &Response::Denied { reason }).await;
+        }
+        let paths = self.paths.iter().map(|p| p.name.clone()).collect();
+        write_frame(&mut writer, &Response::Welcome { paths }).await?;
+
+        while let Some(request) = read_frame(&mut reader).await? {
+            match request {
+                Request::Page {
+                    paths,
+                    filters,
+                    order,
+                    offset,
+                    limit,
+                } => {
+                    // Глубокие страницы клиент дочитывает следующими запросами
+                    let limit = limit.min(REMOTE_PAGE_LINES);
+                    let page = match check_filters(&filters).and_then(|_| self.select(&paths)) {
+                        Ok(paths) => read_from_paths(paths, limit, offset, Some(filters), order)
+                            .await
+                            .map_err(|e| e.to_string()),
+                        Err(message) => Err(message),
+                    };
+                    match page {
+                        Ok(lines) => write_page(&mut writer, lines).await?,
+                        Err(message) => {
+                            write_frame(&mut writer, &Response::Error { message }).await?
+                        }
+                    }
+                }
+                Request::Tail { paths, filters } => {
+                    return match check_filters(&filters).and_then(|_| self.select(&paths)) {
+                        Ok(paths) => tail(paths, filters, reader, writer).await,
+                        Err(message) => {
+                            write_frame(&mut writer, &Response::Error { message }).await
+                        }
+                    };
+                }
+                Request::Hello { .. } => {
+                    let message = "already greeted".to_string();
+                    write_frame(&mut writer, &Response::Error { message }).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Источники по именам; пустой список — все
+    fn select(&self, names: &[String]) -> Result<Vec<Path>, String> {
+        if names.is_empty() {
+            return Ok(self.paths.clone());
+        }
+        names
+            .iter()
+            .map(|name| {
+                self.paths
+                    .iter()
+                    .find(|p| &p.name == name)
+                    .cloned()
+                    .ok_or_else(|| format!("unknown source {}", name))
+            })
+            .collect()
+    }
+}
+
+// Страница уходит кадрами Page, которые делятся пополам, пока не влезут в предел кадра.
+// Кадр — это JSON-массив строк и несколько байт тега вокруг него
+async fn write_page(writer: &mut OwnedWriteHalf, lines: Vec<String>) -> io::Result<()> {
+    let mut chunks = vec![lines];
+    while let Some(mut lines) = chunks.pop() {
+        if serde_json::to_vec(&lines)?.len() + 64 <= REMOTE_MAX_FRAME {
+            write_frame(writer, &Response::Page { lines }).await?;
+            continue;
+        }
+        if lines.len() == 1 {
+            let message = "line is longer than the frame limit".to_string();
+            return write_frame(writer, &Response::Error { message }).await;
+        }
+        let second = lines.split_off(lines.len() / 2);
+        chunks.push(second);
+        chunks.push(lines);
+    }
+    write_frame(writer, &Response::PageEnd).await
+}
+
+// Хвост занимает соединение, пока клиент его не закроет
+async fn tail(
+    paths: Vec<Path>,
+    filters: Vec<Filter>,
+    mut reader: OwnedReadHalf,
+    mut writer: OwnedWriteHalf,
+) -> io::Result<()> {
+    let memory = Memory {
+        paths,
+        filters,
+        ..Default::default()
+    };
+    let (tx, mut rx) = unbounded_channel::<Log>();
+    let mut tail = tokio::spawn(tail_stream(
+        memory,
+        tx,
+        Arc::new(CommandStatus::default()),
+        Arc::new(Throughput::default()),
+        Arc::new(AlertStatus::default()),
+        Arc::new(ListenerStatus::default()),
+    ));
+    let result = loop {
+        tokio::select! {
+            Some(log) = rx.recv() => {
+                let line = Response::Line {
+                    source: log.source_name,
+                    line: log.data,
+                };
+                if let Err(e) = write_frame(&mut writer, &line).await {
+                    break Err(e);
+                }
+            }
+            res = &mut tail => {
+                let message = match res {
+                    Ok(Ok(())) => "tail stopped".to_string(),
+                    Ok(Err(e)) => e.to_string(),
+                    Err(e) => e.to_string(),
+                };
+                break write_frame(&mut writer, &Response::Error { message }).await;
+            }
+            // Во время хвоста клиент ничего не шлёт: чтение заканчивается, когда он отключился
+            _ = read_frame::<Request>(&mut reader) => break Ok(()),
+        }
+    };
+    tail.abort();
+    result
+}
+
+// Фильтры приходят от клиента: неверная регулярка — ответ с ошибкой, а не паника при чтении
+fn check_filters(filters: &[Filter]) -> Result<(), String> {
+    for filter in filters {
+        if let Filter::Regex(f) = filter
+            && let Err(e) = Regex::new(&f.pattern)
+        {
+            return Err(format!("invalid regex: {}", e));
+        }
+    }
+    Ok(())
+}
+
+// Сравнение без раннего выхода: по времени ответа токен не подобрать
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::Write, net::SocketAddr, time::Duration};
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::common::{
+        enums::Order,
+        structs::{RegexFilter, SearchFilter},
+    };
+
+    async fn start(paths: Vec<Path>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Agent::new(paths, "secret".to_string()).serve(listener));
+        addr
+    }
+
+    async fn hello(addr: SocketAddr, token: &str) -> (TcpStream, Response) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let hello = Request::Hello {
+            version: PROTOCOL_VERSION,
+            token: token.to_string(),
+        };
+        write_frame(&mut stream, &hello).await.unwrap();
+        let response = read_frame(&mut stream).await.unwrap().unwrap();
+        (stream, response)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_checks_token() {
+        let addr = start(Vec::new()).await;
+        let (mut stream, response) = hello(addr, "wrong").await;
+        assert_eq!(
+            response,
+            Response::Denied {
+                reason: "invalid token".to_string()
+            }
+        );
+        // После отказа агент закрывает соединение
+        assert!(read_frame::<Response>(&mut stream).await.unwrap().is_none());
+
+        let (_, response) = hello(addr, "secret").await;
+        assert_eq!(response, Response::Welcome { paths: Vec::new() });
+    }
+
+    #[tokio::test]
+    async fn test_page_limit_capped() {
+        let tmp_dir = TempDir::new("agent").expect("Не получилось создать временную директорию");
+        let file_path = tmp_dir.path().join("app.log");
+        let content: String = (0..REMOTE_PAGE_LINES + 5)
+            .map(|i| format!("{:05}\n", i))
+            .collect();
+        fs::write(&file_path, content).unwrap();
+        let path = Path::new(file_path.to_str().unwrap().to_string(), "app".to_string());
+        let addr = start(vec![path]).await;
+
+        // Сколько бы строк ни просил клиент, агент отдаёт не больше REMOTE_PAGE_LINES
+        let (mut stream, _) = hello(addr, "secret").await;
+        let page = Request::Page {
+            paths: Vec::new(),
+            filters: Vec::new(),
+            order: Order::OrderByDate,
+            offset: 3,
+            limit: usize::MAX,
+        };
+        write_frame(&mut stream, &page).await.unwrap();
+        let mut lines = Vec::new();
+        loop {
+            match read_frame::<Response>(&mut stream).await.unwrap().unwrap() {
+                Response::Page { lines: chunk } => lines.extend(chunk),
+                Response::PageEnd => break,
+                response => panic!("{:?}", response),
+            }
+        }
+        assert_eq!(lines.len(), REMOTE_PAGE_LINES);
+        assert_eq!(lines[0], "00003");
+    }
+
+    #[tokio::test]
+    async fn test_page_and_tail() {
+        let tmp_dir = TempDir::new("agent").expect("Не получилось создать временную директорию");
+        let file_path = tmp_dir.path().join("app.log");
+        fs::write(
+            &file_path,
+            "2026-01-02 error b\n2026-01-01 error a\n2026-01-03 info c\n",
+        )
+        .unwrap();
+        let path = Path::new(file_path.to_str().unwrap().to_string(), "app".to_string());
+        let addr = start(vec![path]).await;
+
+        let (mut stream, response) = hello(addr, "secret").await;
+        assert_eq!(
+            response,
+            Response::Welcome {
+                paths: vec!["app".to_string()]
+            }
+        );
+        let errors = vec![Filter::Search(SearchFilter {
+            substr: "error".to_string(),
+        })];
+        let page = Request::Page {
+            paths: Vec::new(),
+            filters: errors.clone(),
+            order: Order::OrderByDate,
+            offset: 0,
+            limit: 10,
+        };
+        write_frame(&mut stream, &page).await.unwrap();
+        assert_eq!(
+            read_frame::<Response>(&mut stream).await.unwrap().unwrap(),
+            Response::Page {
+                lines: vec![
+                    "2026-01-01 error a".to_string(),
+                    "2026-01-02 error b".to_string()
+                ]
+            }
+        );
+        assert_eq!(
+            read_frame::<Response>(&mut stream).await.unwrap().unwrap(),
+            Response::PageEnd
+        );
+        // Файлы вне списка агента клиенту недоступны
+        let page = Request::Page {
+            paths: vec!["/etc/passwd".to_string()],
+            filters: Vec::new(),
+            order: Order::OrderByDate,
+            offset: 0,
+            limit: 10,
+        };
+        write_frame(&mut stream, &page).await.unwrap();
+        assert_eq!(
+            read_frame::<Response>(&mut stream).await.unwrap().unwrap(),
+            Response::Error {
+                message: "unknown source /etc/passwd".to_string()
+            }
+        );
+
+        // Неверная регулярка от клиента — ошибка в ответ, соединение живо
+        let broken = vec![Filter::Regex(RegexFilter {
+            pattern: "(unclosed".to_string(),
+        })];
+        let page = Request::Page {
+            paths: Vec::new(),
+            filters: broken.clone(),
+            order: Order::OrderByDate,
+            offset: 0,
+            limit: 10,
+        };
+        write_frame(&mut stream, &page).await.unwrap();
+        let response = read_frame::<Response>(&mut stream).await.unwrap().unwrap();
+        assert!(
+            matches!(&response, Response::Error { message } if message.starts_with("invalid regex")),
+            "{:?}",
+            response
+        );
+        let (mut broken_tail, _) = hello(addr, "secret").await;
+        let tail = Request::Tail {
+            paths: Vec::new(),
+            filters: broken,
+        };
+        write_frame(&mut broken_tail, &tail).await.unwrap();
+        let response = read_frame::<Response>(&mut broken_tail).await.unwrap().unwrap();
+        assert!(matches!(response, Response::Error { .. }));
+
+        let tail = Request::Tail {
+            paths: vec!["app".to_string()],
+            filters: errors,
+        };
+        write_frame(&mut stream, &tail).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut f = fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        writeln!(f, "2026-01-04 info d").unwrap();
+        writeln!(f, "2026-01-04 error e").unwrap();
+        drop(f);
+        let line =
+            tokio::time::timeout(Duration::from_secs(2), read_frame::<Response>(&mut stream))
+                .await
+                .expect("Строка хвоста не пришла")
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            line,
+            Response::Line {
+                source: "app".to_string(),
+                line: "2026-01-04 error e".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use std::{collections::VecDeque, error::Error, future::Future, io, sync::Arc};
+
+use chrono::Utc;
+use futures_util::future::try_join_all;
+use tokio::{net::TcpStream, sync::mpsc::UnboundedSender};
+
+use crate::common::{
+    constants::{REMOTE_PAGE_LINES, REMOTE_RETRY, REMOTE_TIMEOUT},
+    enums::{Filter, Order},
+    structs::{AgentSource, Log, PageLine, Path},
+};
+use crate::reader::{file::read_page, throughput::Throughput};
+use crate::remote::protocol::{PROTOCOL_VERSION, Request, Response, read_frame, write_frame};
+
+// Соединение с агентом после рукопожатия
+pub struct Connection {
+    stream: TcpStream,
+    name: String,
+    pub paths: Vec<String>,
+}
+
+impl Connection {
+    // Недоступный агент не должен держать вызывающего дольше REMOTE_TIMEOUT
+    pub async fn connect(agent: &AgentSource) -> io::Result<Self> {
+        timed(&agent.name, async {
+            let mut stream = TcpStream::connect(&agent.addr).await?;
+            let hello = Request::Hello {
+                version: PROTOCOL_VERSION,
+                token: agent.token.clone(),
+            };
+            write_frame(&mut stream, &hello).await?;
+            match read_frame(&mut stream).await? {
+                Some(Response::Welcome { paths }) => Ok(Self {
+                    stream,
+                    name: agent.name.clone(),
+                    paths,
+                }),
+                Some(Response::Denied { reason }) => Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("agent {}: {}", agent.name, reason),
+                )),
+                _ => Err(unexpected(&agent.name)),
+            }
+        })
+        .await
+    }
+
+    // Страница из источников paths; пустой список — из всех источников агента.
+    // Агент отдаёт её несколькими кадрами и не больше REMOTE_PAGE_LINES строк
+    pub async fn page(
+        &mut self,
+        paths: Vec<String>,
+        filters: Vec<Filter>,
+        order: Order,
+        offset: usize,
+        limit: usize,
+    ) -> io::Result<Vec<String>> {
+        let request = Request::Page {
+            paths,
+            filters,
+            order,
+            offset,
+            limit,
+        };
+        let name = self.name.clone();
+        timed(&name, async {
+            write_frame(&mut self.stream, &request).await?;
+            let mut page = Vec::new();
+            loop {
+                match read_frame(&mut self.stream).await? {
+                    Some(Response::Page { lines }) if page.len() + lines.len() <= limit => {
+                        page.extend(lines)
+                    }
+                    Some(Response::PageEnd) => return Ok(page),
+                    Some(Response::Error { message }) => return Err(self.error(message)),
+                    _ => return Err(unexpected(&self.name)),
+                }
+            }
+        })
+        .await
+    }
+
+    // Строки хвоста уходят в tx под именем "агент/источник"
+    async fn tail(
+        mut self,
+        filters: Vec<Filter>,
+        tx: &UnboundedSender<Log>,
+        throughput: &Throughput,
+    ) -> io::Result<()> {
+        let request = Request::Tail {
+            paths: Vec::new(),
+            filters,
+        };
+        write_frame(&mut self.stream, &request).await?;
+        loop {
+            match read_frame(&mut self.stream).await? {
+                Some(Response::Line { source, line }) => {
+                    let source_name = format!("{}/{}", self.name, source);
+                    // Фильтрует агент: отброшенные строки сюда не доходят
+                    throughput.record(&source_name, true);
+                    let log = Log {
+                        date_time: Utc::now(),
+                        data: line,
+                        source_name,
+                    };
+                    if tx.send(log).is_err() {
+                        return Ok(());
+                    }
+                }
+                Some(Response::Error { message }) => return Err(self.error(message)),
+                Some(_) => return Err(unexpected(&self.name)),
+                None => return Err(io::ErrorKind::UnexpectedEof.into()),
+            }
+        }
+    }
+
+    fn error(&self, message: String) -> io::Error {
+        io::Error::other(format!("agent {}: {}", self.name, message))
+    }
+}
+
+// Зависший агент — ошибка TimedOut вместо вечного ожидания
+async fn timed<T>(name: &str, future: impl Future<Output = io::Result<T>>) -> io::Result<T> {
+    tokio::time::timeout(REMOTE_TIMEOUT, future)
+        .await
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("agent {}: timed out", name),
+            ))
+        })
+}
+
+fn unexpected(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("agent {}: unexpected response", name),
+    )
+}
+
+// Хвост агента в Tail Mode; после обрыва подключается снова, пока жив получатель
+pub async fn tail(
+    agent: AgentSource,
+    filters: Arc<Vec<Filter>>,
+    tx: UnboundedSender<Log>,
+    throughput: Arc<Throughput>,
+) {
+    loop {
+        if let Ok(connection) = Connection::connect(&agent).await {
+            let _ = connection.tail(filters.to_vec(), &tx, &throughput).await;
+        }
+        if tx.is_closed() {
+            return;
+        }
+        tokio::time::sleep(REMOTE_RETRY).await;
+    }
+}
+
+// Строки одного агента в порядке выдачи. Читаются кусками по мере слияния, поэтому
+// с агента приходит не больше строк, чем нужно до конца страницы
+struct Cursor {
+    connection: Connection,
+    filters: Vec<Filter>,
+    order: Order,
+    offset: usize,
+    lines: VecDeque<String>,
+    done: bool,
+}
+
+impl Cursor {
+    // want — сколько строк ещё может понадобиться странице
+    async fn fetch(&mut self, want: usize) -> io::Result<()> {
+        let limit = want.min(REMOTE_PAGE_LINES);
+        let lines = self
+            .connection
+            .page(
+                Vec::new(),
+                self.filters.clone(),
+                self.order.clone(),
+                self.offset,
+                limit,
+            )
+            .await?;
+        self.done = lines.len() < limit;
+        self.offset += lines.len();
+        self.lines.extend(lines);
+        Ok(())
+    }
+}
+
+// Страница из своих файлов и со всех агентов. Свои файлы отдают первые offset + limit
+// строк, строки агентов подмешиваются слиянием, а порядок тот же, что в read_from_paths.
+// У строк агентов нет места в файле
+pub async fn read_from_sources(
+    paths: Vec<Path>,
+    agents: &[AgentSource],
+    limit: usize,
+    offset: usize,
+    filters: Option<Vec<Filter>>,
+    order: Order,
+) -> Result<Vec<PageLine>, Box<dyn Error>> {
+    if agents.is_empty() {
+        return read_page(paths, limit, offset, filters, order).await;
+    }
+    let top = offset.saturating_add(limit);
+    let mut local = read_page(paths, top, 0, filters.clone(), order.clone())
+        .await?
+        .into_iter()
+        .peekable();
+    let filters = filters.unwrap_or_default();
+    let mut cursors = try_join_all(agents.iter().map(|agent| {
+        let filters = filters.clone();
+        let order = order.clone();
+        async move {
+            Ok::<_, io::Error>(Cursor {
+                connection: Connection::connect(agent).await?,
+                filters,
+                order,
+                offset: 0,
+                lines: VecDeque::new(),
+                done: false,
+            })
+        }
+    }))
+    .await?;
+
+    let reverse = order == Order::OrderByDateReverse;
+    let mut page = Vec::new();
+    for taken in 0..top {
+        try_join_all(
+            cursors
+                .iter_mut()
+                .filter(|cursor| cursor.lines.is_empty() && !cursor.done)
+                .map(|cursor| cursor.fetch(top - taken)),
+        )
+        .await?;
+        // Следующая строка — наименьшая из первых строк источников (наибольшая при обратном
+        // порядке); при равенстве свои файлы идут первыми
+        let mut next = local.peek().map(|line| (&line.line, None));
+        for (i, cursor) in cursors.iter().enumerate() {
+            if let Some(line) = cursor.lines.front()
+                && next.is_none_or(|(best, _)| match reverse {
+                    true => line > best,
+                    false => line < best,
+                })
+            {
+                next = Some((line, Some(i)));
+            }
+        }
+        let line = match next.map(|(_, source)| source) {
+            None => break,
+            Some(None) => local.next().unwrap(),
+            Some(Some(i)) => PageLine {
+                line: cursors[i].lines.pop_front().unwrap(),
+                place: None,
+            },
+        };
+        if taken >= offset {
+            page.push(line);
+        }
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use tempdir::TempDir;
+    use tokio::{net::TcpListener, sync::mpsc::unbounded_channel};
+
+    use super::*;
+    use crate::common::structs::Place;
+    use crate::remote::agent::Agent;
+
+    fn log_file(tmp_dir: &TempDir, name: &str, content: &str) -> Path {
+        let file_path = tmp_dir.path().join(format!("{}.log", name));
+        fs::write(&file_path, content).unwrap();
+        Path::new(file_path.to_str().unwrap().to_string(), name.to_string())
+    }
+
+    async fn start(paths: Vec<Path>) -> AgentSource {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Agent::new(paths, "secret".to_string()).serve(listener));
+        AgentSource {
+            name: "remote".to_string(),
+            addr: addr.to_string(),
+            token: "secret".to_string(),
+            session: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_from_sources_merges_agents() {
+        let tmp_dir = TempDir::new("client").expect("Не получилось создать временную директорию");
+        let local = log_file(&tmp_dir, "local", "2026-01-01 a\n2026-01-03 c\n");
+        let remote = log_file(&tmp_dir, "remote", "2026-01-02 b\n2026-01-04 d\n");
+        let agent = start(vec![remote]).await;
+
+        let lines = read_from_sources(
+            vec![local.clone()],
+            std::slice::from_ref(&agent),
+            2,
+            1,
+            None,
+            Order::OrderByDate,
+        )
+        .await
+        .unwrap();
+        // Место в файле есть только у своих строк
+        assert_eq!(
+            lines,
+            vec![
+                PageLine {
+                    line: "2026-01-02 b".to_string(),
+                    place: None,
+                },
+                PageLine {
+                    line: "2026-01-03 c".to_string(),
+                    place: Some(Place {
+                        path: local.path.clone(),
+                        offset: 13,
+                    }),
+                },
+            ]
+        );
+
+        let lines = read_from_sources(
+            vec![local],
+            std::slice::from_ref(&agent),
+            3,
+            0,
+            None,
+            Order::OrderByDateReverse,
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|line| line.line)
+        .collect::<Vec<_>>();
+        assert_eq!(lines, vec!["2026-01-04 d", "2026-01-03 c", "2026-01-02 b"]);
+
+        // Неверный токен — ошибка, а не пустая страница
+        let denied = AgentSource {
+            token: "wrong".to_string(),
+            ..agent
+        };
+        let err = read_from_sources(Vec::new(), &[denied], 10, 0, None, Order::OrderByDate)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "agent remote: invalid token");
+    }
+
+    #[tokio::test]
+    async fn test_read_from_sources_deep_page() {
+        // Агент отдаёт больше REMOTE_PAGE_LINES строк: страница дочитывается несколькими запросами
+        let tmp_dir = TempDir::new("client").expect("Не получилось создать временную директорию");
+        let total = REMOTE_PAGE_LINES * 3;
+        let numbered = |parity: usize| -> String {
+            (0..total)
+                .filter(|i| i % 2 == parity)
+                .map(|i| format!("{:06}\n", i))
+                .collect()
+        };
+        let local = log_file(&tmp_dir, "local", &numbered(0));
+        let remote = log_file(&tmp_dir, "remote", &numbered(1));
+        let agent = start(vec![remote]).await;
+
+        for (order, expected) in [
+            (Order::OrderByDate, total - 30..total - 10),
+            (Order::OrderByDateReverse, 10..30),
+        ] {
+            let mut expected: Vec<String> = expected.map(|i| format!("{:06}", i)).collect();
+            if order == Order::OrderByDateReverse {
+                expected.reverse();
+            }
+            let lines = read_from_sources(
+                vec![local.clone()],
+                std::slice::from_ref(&agent),
+                20,
+                total - 30,
+                None,
+                order,
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|line| line.line)
+            .collect::<Vec<_>>();
+            assert_eq!(lines, expected);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_agent_times_out() {
+        // Соединение принимается ядром, но агент не отвечает на приветствие
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let agent = AgentSource {
+            name: "stalled".to_string(),
+            addr: listener.local_addr().unwrap().to_string(),
+            token: "secret".to_string(),
+            session: false,
+        };
+        let err = read_from_sources(Vec::new(), &[agent], 10, 0, None, Order::OrderByDate)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "agent stalled: timed out");
+    }
+
+    #[tokio::test]
+    async fn test_tail_prefixes_agent_name() {
+        let tmp_dir = TempDir::new("client").expect("Не получилось создать временную директорию");
+        let remote = log_file(&tmp_dir, "app", "");
+        let file_path = remote.path.clone();
+        let agent = start(vec![remote]).await;
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let handle = tokio::spawn(tail(
+            agent,
+            Arc::new(Vec::new()),
+            tx,
+            Arc::new(Throughput::default()),
+        ));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        fs::write(&file_path, "hello\n").unwrap();
+        let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("Строка хвоста не пришла")
+            .unwrap();
+        assert_eq!(log.source_name, "remote/app");
+        assert_eq!(log.data, "hello");
+        handle.abort();
+    }
+}
@@ -0,0 +1,5 @@
+// Удалённый просмотр: `logscraper agent` работает рядом с файлами,
+// а TUI на ноутбуке подключается к одному или нескольким агентам
+pub mod agent;
+pub mod client;
+pub mod protocol;
@@ -0,0 +1,168 @@
+use std::io;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::common::{
+    constants::{REMOTE_HELLO_MAX_FRAME, REMOTE_MAX_FRAME},
+    enums::{Filter, Order},
+};
+
+// Кадр: длина тела (u32, big-endian) и JSON. Первым клиент присылает Hello с токеном,
+// агент отвечает Welcome или Denied. Дальше на соединении выполняются запросы Page
+// по одному, а Tail занимает соединение до его закрытия. Страница приходит несколькими
+// кадрами Page, каждый не больше REMOTE_MAX_FRAME, и завершается PageEnd.
+// Шифрования нет: токен и строки логов идут по сети открытым текстом.
+
+pub const PROTOCOL_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Request {
+    Hello {
+        version: u32,
+        token: String,
+    },
+    // Параметры read_from_paths; paths — имена источников агента, пустой список — все.
+    // Агент отдаёт не больше REMOTE_PAGE_LINES строк
+    Page {
+        paths: Vec<String>,
+        filters: Vec<Filter>,
+        order: Order,
+        offset: usize,
+        limit: usize,
+    },
+    Tail {
+        paths: Vec<String>,
+        filters: Vec<Filter>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum Response {
+    // Имена источников, которые отдаёт агент
+    Welcome { paths: Vec<String> },
+    Denied { reason: String },
+    Page { lines: Vec<String> },
+    PageEnd,
+    Line { source: String, line: String },
+    Error { message: String },
+}
+
+pub async fn write_frame<T: Serialize>(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    if body.len() > REMOTE_MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame is too large",
+        ));
+    }
+    writer.write_all(&(body.len() as u32).to_be_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
+
+// None — соединение закрыто между кадрами
+pub async fn read_frame<T: DeserializeOwned>(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> io::Result<Option<T>> {
+    read_limited_frame(reader, REMOTE_MAX_FRAME).await
+}
+
+// Hello читается с маленьким пределом: без токена клиент не заставит занять 16 МиБ
+pub async fn read_hello(reader: &mut (impl AsyncRead + Unpin)) -> io::Result<Option<Request>> {
+    read_limited_frame(reader, REMOTE_HELLO_MAX_FRAME).await
+}
+
+async fn read_limited_frame<T: DeserializeOwned>(
+    reader: &mut (impl AsyncRead + Unpin),
+    max_len: usize,
+) -> io::Result<Option<T>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    // Длину проверяем до выделения памяти: чужой клиент не должен заставить нас занять 4 ГиБ
+    if len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame is too large",
+        ));
+    }
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::structs::SearchFilter;
+
+    #[tokio::test]
+    async fn test_frame_roundtrip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let request = Request::Page {
+            paths: vec!["app".to_string()],
+            filters: vec![Filter::Search(SearchFilter {
+                substr: "error".to_string(),
+            })],
+            order: Order::OrderByDateReverse,
+            offset: 10,
+            limit: 5,
+        };
+        write_frame(&mut client, &request).await.unwrap();
+        drop(client);
+        let got: Request = read_frame(&mut server).await.unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_string(&got).unwrap(),
+            serde_json::to_string(&request).unwrap()
+        );
+        // Закрытие между кадрами — не ошибка
+        assert!(read_frame::<Request>(&mut server).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_rejected() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client
+            .write_all(&(REMOTE_MAX_FRAME as u32 + 1).to_be_bytes())
+            .await
+            .unwrap();
+        match read_frame::<Request>(&mut server).await {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            Ok(_) => panic!("Кадр больше предела должен отклоняться"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hello_frame_limit() {
+        let hello = Request::Hello {
+            version: PROTOCOL_VERSION,
+            token: "secret".to_string(),
+        };
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        write_frame(&mut client, &hello).await.unwrap();
+        assert!(matches!(
+            read_hello(&mut server).await.unwrap(),
+            Some(Request::Hello { .. })
+        ));
+
+        // Обычный по размеру кадр до проверки токена уже слишком велик
+        client
+            .write_all(&(REMOTE_HELLO_MAX_FRAME as u32 + 1).to_be_bytes())
+            .await
+            .unwrap();
+        match read_hello(&mut server).await {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            Ok(_) => panic!("Кадр до проверки токена должен быть маленьким"),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::common::{constants::STATS_PROGRESS_STEP, enums::Filter, fields, structs::Path};
+use crate::reader::file::visit_lines;
+
+// Сводка по строкам источников, прошедшим фильтры: строки не хранятся, только счётчики
+#[derive(Default, Debug, PartialEq)]
+pub struct Stats {
+    pub total: u64,
+    pub by_source: BTreeMap<String, u64>,
+    // Строки без узнаваемого уровня — под NO_LEVEL
+    pub by_level: BTreeMap<String, u64>,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    // Строк в каждой минуте, ключ — минуты Unix; строки без времени сюда не попадают
+    pub per_minute: BTreeMap<i64, u64>,
+}
+
+pub const NO_LEVEL: &str = "-";
+
+// Ширина столбца гистограммы
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Bucket {
+    #[default]
+    Minute,
+    Hour,
+}
+
+impl Bucket {
+    pub fn next(&self) -> Bucket {
+        match self {
+            Bucket::Minute => Bucket::Hour,
+            Bucket::Hour => Bucket::Minute,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bucket::Minute => "minute",
+            Bucket::Hour => "hour",
+        }
+    }
+
+    fn minutes(&self) -> i64 {
+        match self {
+            Bucket::Minute => 1,
+            Bucket::Hour => 60,
+        }
+    }
+}
+
+impl Stats {
+    pub fn add(&mut self, source: &str, line: &str) {
+        self.total += 1;
+        // Строк много, источников мало: ключ выделяем только для нового источника
+        match self.by_source.get_mut(source) {
+            Some(count) => *count += 1,
+            None => {
+                self.by_source.insert(source.to_string(), 1);
+            }
+        }
+        let level = fields::level(line).unwrap_or(NO_LEVEL);
+        match self.by_level.get_mut(level) {
+            Some(count) => *count += 1,
+            None => {
+                self.by_level.insert(level.to_string(), 1);
+            }
+        }
+        if let Some(time) = fields::timestamp(line) {
+            self.first = Some(self.first.map_or(time, |first| first.min(time)));
+            self.last = Some(self.last.map_or(time, |last| last.max(time)));
+            *self
+                .per_minute
+                .entry(time.timestamp().div_euclid(60))
+                .or_default() += 1;
+        }
+    }
+
+    // Последние max столбцов гистограммы до последней строки, пустые промежутки — нули
+    pub fn histogram(&self, bucket: Bucket, max: usize) -> Vec<(DateTime<Utc>, u64)> {
+        let (Some(first), Some(last)) = (
+            self.per_minute.first_key_value(),
+            self.per_minute.last_key_value(),
+        ) else {
+            return Vec::new();
+        };
+        let width = bucket.minutes();
+        let last_bucket = last.0.div_euclid(width);
+        let first_bucket = first.0.div_euclid(width).max(last_bucket - max as i64 + 1);
+        let mut counts = vec![0u64; (last_bucket - first_bucket + 1) as usize];
+        for (minute, count) in self.per_minute.range(first_bucket * width..) {
+            counts[(minute.div_euclid(width) - first_bucket) as usize] += count;
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, count)| {
+                let start = DateTime::from_timestamp((first_bucket + i as i64) * width * 60, 0)?;
+                Some((start, count))
+            })
+            .collect()
+    }
+}
+
+// Состояние фонового подсчёта для экрана статистики
+#[derive(Default)]
+pub struct StatsProgress {
+    pub running: AtomicBool,
+    pub lines: AtomicU64,
+    pub result: Mutex<Option<Stats>>,
+    pub error: Mutex<Option<String>>,
+}
+
+// Блокирующий проход по всем источникам; вызывать из отдельного потока
+pub fn collect(paths: &[Path], filters: &[Filter], progress: &StatsProgress) -> io::Result<Stats> {
+    progress.lines.store(0, Ordering::Relaxed);
+    let mut stats = Stats::default();
+    let filters = Some(filters.to_vec());
+    visit_lines(paths, &filters, |source, line| {
+        stats.add(source, &line);
+        if stats.total.is_multiple_of(STATS_PROGRESS_STEP) {
+            progress.lines.store(stats.total, Ordering::Relaxed);
+        }
+    })?;
+    progress.lines.store(stats.total, Ordering::Relaxed);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::common::structs::SearchFilter;
+
+    #[test]
+    fn test_collect() {
+        let tmp_dir = TempDir::new("stats").expect("Не получилось создать временную директорию");
+        let mut paths = Vec::new();
+        for (name, content) in [
+            (
+                "api",
+                "2026-10-17T12:00:10Z ERROR db down\n2026-10-17T12:00:50Z INFO retry\n\
+                 2026-10-17T12:03:00+03:00 level=warn slow\nno time here\n",
+            ),
+            (
+                "web",
+                "2026-10-17T12:01:30Z PRIORITY=3 upstream\n2026-10-17T09:02:59Z [debug] skipped\n",
+            ),
+        ] {
+            let file_path = tmp_dir.path().join(format!("{}.log", name));
+            fs::write(&file_path, content).unwrap();
+            paths.push(Path::new(
+                file_path.to_str().unwrap().to_string(),
+                name.to_string(),
+            ));
+        }
+        let filters = vec![Filter::Search(SearchFilter {
+            substr: " ".to_string(),
+        })];
+        let progress = StatsProgress::default();
+        let stats = collect(&paths, &filters, &progress).unwrap();
+
+        assert_eq!(stats.total, 6);
+        assert_eq!(progress.lines.load(Ordering::Relaxed), 6);
+        let count = |map: &BTreeMap<String, u64>, key: &str| map.get(key).copied();
+        assert_eq!(count(&stats.by_source, "api"), Some(4));
+        assert_eq!(count(&stats.by_source, "web"), Some(2));
+        assert_eq!(count(&stats.by_level, "error"), Some(2));
+        assert_eq!(count(&stats.by_level, "info"), Some(1));
+        assert_eq!(count(&stats.by_level, "warn"), Some(1));
+        assert_eq!(count(&stats.by_level, "debug"), Some(1));
+        assert_eq!(count(&stats.by_level, NO_LEVEL), Some(1));
+        assert_eq!(
+            stats.first.unwrap().to_rfc3339(),
+            "2026-10-17T09:02:59+00:00"
+        );
+        assert_eq!(
+            stats.last.unwrap().to_rfc3339(),
+            "2026-10-17T12:01:30+00:00"
+        );
+
+        let minutes: Vec<u64> = stats
+            .histogram(Bucket::Minute, 3)
+            .into_iter()
+            .map(|(_, count)| count)
+            .collect();
+        // Последние три минуты: 11:59 пустая, 12:00 — две строки, 12:01 — одна
+        assert_eq!(minutes, vec![0, 2, 1]);
+        let hours = stats.histogram(Bucket::Hour, 24);
+        assert_eq!(hours.len(), 4);
+        assert_eq!(hours[0].0.to_rfc3339(), "2026-10-17T09:00:00+00:00");
+        assert_eq!(
+            hours.iter().map(|(_, count)| count).collect::<Vec<_>>(),
+            vec![&2, &0, &0, &3]
+        );
+    }
+}
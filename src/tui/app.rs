@@ -3,10 +3,31 @@ use ratatui::{
     style::{Color, Style},
     text::Span,
 };
-use tokio::sync::mpsc::UnboundedReceiver;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use crate::alert::AlertStatus;
+use crate::bookmark;
+use crate::cluster::{ClusterProgress, Drain};
+use crate::common::dedup::{self, Run};
+use crate::common::enums::{Dedup, ExportFormat, Filter, Mode, Order};
+use crate::common::fields;
+use crate::common::structs::{
+    AlertRule, Bookmark, CommandSource, Log, Memory, PageLine, Place, View,
+};
+use crate::common::{constants::STDIN_PATH, profile};
+use crate::export::{ExportProgress, ExportRequest, Query, Selection};
+use crate::index::text::IndexProgress;
+use crate::reader::{command::CommandStatus, container, tail::ListenerStatus, throughput::Throughput};
+use crate::redact::Redactor;
+use crate::stats::{Bucket, StatsProgress};
 
-use crate::common::enums::{Mode, Order};
-use crate::common::structs::Memory;
+// Итог фоновой загрузки страницы: номер запроса и строки или ошибка
+pub type PageResult = (u64, Result<Vec<PageLine>, String>);
 
 pub struct App {
     pub cur_screen: Screen,
@@ -17,7 +38,8 @@ pub struct App {
     pub cur_page: usize,
     pub exit_approved: bool,
     pub logs: Vec<String>,
-    pub rx: UnboundedReceiver<String>,
+    pub places: Vec<Option<Place>>, // Места строк страницы Page Mode в файлах, параллельно logs
+    pub rx: UnboundedReceiver<Log>,
     pub memory: Memory,
     // Поля для управления состоянием модальных окон
     pub selected_index: Option<usize>, // Индекс выбранного элемента в списках модальных окон
@@ -30,12 +52,60 @@ pub struct App {
     pub editing_date_field: DateField, // Поле, которое в данный момент редактируется
     // Поля для управления загрузкой логов
     pub needs_refresh: bool,           // Нужно ли обновить логи
+    pub page_request: u64,             // Номер последней запрошенной страницы; ответы на прежние отбрасываются
+    pub page_result: Arc<Mutex<Option<PageResult>>>, // Страница из фоновой загрузки
+    pub page_loading: bool,            // Страница ещё читается
+    pub page_error: Option<String>,    // Ошибка последней загрузки страницы
     // Поля для отслеживания изменений
     pub last_order: Order,             // Последняя настройка сортировки 
     pub last_mode: Mode,               // Последняя настройка режима
     pub last_size: usize,              // Последний размер страницы
     pub last_paths_count: usize,       // Последнее количество путей
     pub last_filters_count: usize,     // Последнее количество фильтров
+    // Поля для полнотекстового индекса
+    pub index_requested: bool,         // Пользователь запросил сборку индекса
+    pub index_progress: Arc<IndexProgress>, // Прогресс фоновой сборки
+    pub command_status: Arc<CommandStatus>, // Состояние команд-источников в Tail Mode
+    pub throughput: Arc<Throughput>,   // Скорость строк хвоста по источникам
+    pub tail_error: Arc<Mutex<Option<String>>>, // Почему хвост остановился: нет файла и т.п.
+    pub listener_status: Arc<ListenerStatus>, // Приёмники syslog и HTTP, не занявшие адрес
+    // Поля для выгрузки в файл
+    pub export_buffer: String,         // Имя файла выгрузки
+    pub export_format: ExportFormat,   // Формат выгрузки
+    pub export_all: bool,              // Все отфильтрованные строки, а не только страница
+    pub export_requested: Option<ExportRequest>, // Выгрузка, которую нужно запустить
+    pub export_progress: Arc<ExportProgress>, // Прогресс фоновой выгрузки
+    // Поля для экрана статистики
+    pub stats_requested: bool,         // Нужно пересчитать статистику
+    pub stats: Arc<StatsProgress>,     // Фоновый подсчёт и его результат
+    pub stats_bucket: Bucket,          // Ширина столбца гистограммы
+    // Поля для экрана шаблонов сообщений
+    pub clusters_requested: bool,      // Нужно заново разобрать строки Page Mode
+    pub clusters: Arc<ClusterProgress>, // Фоновый разбор и его результат
+    pub tail_clusters: Drain,          // Шаблоны строк хвоста, пополняются по мере прихода
+    pub cluster_selected: usize,       // Выбранный шаблон в списке
+    // Поля для свёртки повторов
+    pub dedup: Dedup,                  // Режим свёртки
+    pub dedup_runs: Vec<Run>,          // Свёрнутые строки app.logs
+    pub dedup_expanded: HashSet<usize>, // Развёрнутые свёртки, по индексу первой строки
+    pub dedup_selected: usize,         // Выбранная свёртка среди видимых
+    // Поля для оповещений
+    pub alert_status: Arc<AlertStatus>, // Срабатывания правил в Tail Mode
+    pub bells_rung: u64,               // Сколько звонков уже отправлено в терминал
+    // Поля для маскирования
+    pub redactor: Arc<Redactor>,       // Встроенные детекторы и правила из памяти
+    pub redact: bool,                  // Маскировать строки на экране и в выгрузке
+    // Поля для закладок
+    pub line_selected: usize,          // Выбранная строка среди видимых, когда свёртка выключена
+    pub bookmark_key: Option<char>,    // Нажата b или ', ждём букву закладки
+    pub jump_requested: Option<Bookmark>, // Закладка, к строке которой нужно перейти
+    pub jump_result: Arc<Mutex<Option<bookmark::Jump>>>, // Номер строки закладки из фонового поиска
+    pub jump_target: Option<Bookmark>, // Закладка, строку которой выбрать после загрузки страницы
+    pub bookmark_status: Option<String>, // Итог последнего действия с закладками
+    // Поля для профилей
+    pub profiles: Vec<String>,         // Профили в каталоге настроек, для переключателя
+    pub profile_switched: bool,        // Профиль сменился, хвост нужно перезапустить
+    pub profile_status: Option<String>, // Ошибка последнего действия с профилями
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,22 +113,29 @@ pub enum FilterType {
     Date,
     Regex,
     Search,
+    Field,
 }
 
 impl App {
-    pub fn new(rx: UnboundedReceiver<String>, memory: Memory) -> App {
+    pub fn new(rx: UnboundedReceiver<Log>, memory: Memory) -> App {
         let paths_count = memory.paths.len();
         let filters_count = memory.filters.len();
-        
+        // Порядок, размер страницы и режим — из профиля
+        let view = memory.view.clone();
+        let page_size = view.page_size.clamp(5, 1000);
+        let redactor = Arc::new(Redactor::new(&memory.redaction.rules));
+        let redact = memory.redaction.enabled;
+
         App {
             cur_screen: Screen::Main,
             cur_modal: None,
-            cur_order: Order::OrderByDate,
-            cur_mode: Mode::Page,
-            cur_size: 30,
+            cur_order: view.order.clone(),
+            cur_mode: view.mode.clone(),
+            cur_size: page_size,
             exit_approved: false,
             cur_page: 1,
             logs: Vec::new(),
+            places: Vec::new(),
             rx,
             memory,
             selected_index: None,
@@ -70,11 +147,50 @@ impl App {
             editing_date_field: DateField::None,
             filter_type: FilterType::Search,
             needs_refresh: true, // Обновляем логи при первом отображении
-            last_order: Order::OrderByDate,
-            last_mode: Mode::Page,
-            last_size: 30,
+            page_request: 0,
+            page_result: Arc::new(Mutex::new(None)),
+            page_loading: false,
+            page_error: None,
+            last_order: view.order,
+            last_mode: view.mode,
+            last_size: page_size,
             last_paths_count: paths_count,
             last_filters_count: filters_count,
+            index_requested: false,
+            index_progress: Arc::new(IndexProgress::default()),
+            command_status: Arc::new(CommandStatus::default()),
+            throughput: Arc::new(Throughput::default()),
+            tail_error: Arc::new(Mutex::new(None)),
+            listener_status: Arc::new(ListenerStatus::default()),
+            export_buffer: String::new(),
+            export_format: ExportFormat::default(),
+            export_all: false,
+            export_requested: None,
+            export_progress: Arc::new(ExportProgress::default()),
+            stats_requested: false,
+            stats: Arc::new(StatsProgress::default()),
+            stats_bucket: Bucket::default(),
+            clusters_requested: false,
+            clusters: Arc::new(ClusterProgress::default()),
+            tail_clusters: Drain::default(),
+            cluster_selected: 0,
+            dedup: Dedup::default(),
+            dedup_runs: Vec::new(),
+            dedup_expanded: HashSet::new(),
+            dedup_selected: 0,
+            alert_status: Arc::new(AlertStatus::default()),
+            bells_rung: 0,
+            redactor,
+            redact,
+            line_selected: 0,
+            bookmark_key: None,
+            jump_requested: None,
+            jump_result: Arc::new(Mutex::new(None)),
+            jump_target: None,
+            bookmark_status: None,
+            profiles: Vec::new(),
+            profile_switched: false,
+            profile_status: None,
         }
     }
     pub fn handle_additional(&mut self, key: KeyCode) {
@@ -87,12 +203,48 @@ impl App {
                         match _modal {
                             Modal::Path => {
                                 if index < self.memory.paths.len() {
-
-                                    let updated_path = crate::common::structs::Path::new(
-                                        self.edit_buffer.clone(),
-                                        self.edit_buffer.clone(),
-                                    );
-                                    let _ = self.memory.update_path(index, updated_path);
+                                    // Каталог логов подов (/var/log/pods) разворачивается в источники по контейнерам
+                                    let pods = container::discover_pods(&self.edit_buffer).unwrap_or_default();
+                                    if !pods.is_empty() {
+                                        self.memory.paths.splice(index..=index, pods);
+                                    } else {
+                                        // Кодировка и формат остаются прежними
+                                        let mut updated_path = self.memory.paths[index].clone();
+                                        updated_path.path = self.edit_buffer.clone();
+                                        updated_path.name = self.edit_buffer.clone();
+                                        let _ = self.memory.update_path(index, updated_path);
+                                    }
+                                }
+                            }
+                            Modal::Command => {
+                                if index < self.memory.commands.len() {
+                                    let updated_command = CommandSource::parse(&self.edit_buffer);
+                                    let _ = self.memory.update_command(index, updated_command);
+                                }
+                            }
+                            Modal::Alert => {
+                                let _ = self.memory.update_alert(index, AlertRule::parse(&self.edit_buffer));
+                            }
+                            Modal::Bookmark => {
+                                if let Some(bookmark) = self.memory.bookmarks.get_mut(index) {
+                                    bookmark.note = self.edit_buffer.clone();
+                                }
+                            }
+                            Modal::Profile => {
+                                // Имя нового профиля: переключаемся сразу, файл появится при сохранении
+                                let name = self.edit_buffer.trim().to_string();
+                                match profile::is_valid(&name) {
+                                    true => {
+                                        self.switch_profile(&name);
+                                        self.cur_screen = Screen::Main;
+                                        self.cur_modal = None;
+                                        self.selected_index = None;
+                                    }
+                                    false => {
+                                        self.profile_status = Some(format!("Invalid profile name '{}': use letters, digits, - and _", name));
+                                        self.profiles.retain(|p| !p.is_empty());
+                                        self.selected_index = None;
+                                    }
                                 }
                             }
                             Modal::Filter => {
@@ -109,6 +261,9 @@ impl App {
                                                 pattern: self.edit_buffer.clone(),
                                             },
                                         ),
+                                        FilterType::Field => crate::common::enums::Filter::Field(
+                                            crate::common::structs::FieldFilter::parse(&self.edit_buffer),
+                                        ),
                                         FilterType::Date => {
                                             // Для датового фильтра парсим даты из буферов
                                             let date_start = if !self.date_start_buffer.is_empty() {
@@ -151,7 +306,7 @@ impl App {
                 KeyCode::Char(c) => {
                     // В зависимости от типа фильтра и поля редактирования, добавляем символ в соответствующий буфер
                     match self.filter_type {
-                        FilterType::Date => {
+                        FilterType::Date if self.cur_modal == Some(Modal::Filter) => {
                             match self.editing_date_field {
                                 DateField::Start => self.date_start_buffer.push(c),
                                 DateField::Finish => self.date_finish_buffer.push(c),
@@ -164,7 +319,7 @@ impl App {
                 KeyCode::Backspace => {
                     // В зависимости от типа фильтра и поля редактирования, удаляем символ из соответствующего буфера
                     match self.filter_type {
-                        FilterType::Date => {
+                        FilterType::Date if self.cur_modal == Some(Modal::Filter) => {
                             match self.editing_date_field {
                                 DateField::Start => { self.date_start_buffer.pop(); },
                                 DateField::Finish => { self.date_finish_buffer.pop(); },
@@ -180,7 +335,8 @@ impl App {
                     self.edit_buffer.clear();
                 }
                 KeyCode::Tab => {
-                    if let (Some(_modal), Some(index)) = (&self.cur_modal, self.selected_index) {
+                    // Tab переключает только тип фильтра
+                    if let (Some(Modal::Filter), Some(index)) = (&self.cur_modal, self.selected_index) {
                         if index < self.memory.filters.len() {
                             match self.filter_type {
                                 FilterType::Search => {
@@ -193,6 +349,13 @@ impl App {
                                     self.filter_type = FilterType::Regex;
                                 },
                                 FilterType::Regex => {
+                                    let updated_filter = crate::common::enums::Filter::Field(
+                                        crate::common::structs::FieldFilter::parse(&self.edit_buffer),
+                                    );
+                                    let _ = self.memory.update_filter(index, updated_filter);
+                                    self.filter_type = FilterType::Field;
+                                },
+                                FilterType::Field => {
                                     let updated_filter = crate::common::enums::Filter::Date(
                                         crate::common::structs::DateFilter {
                                             date_format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
@@ -289,7 +452,23 @@ impl App {
                         // Select the newly added item
                         self.selected_index = Some(self.memory.filters.len().saturating_sub(1));
                     }
-                    None => {}
+                    Some(Modal::Command) => {
+                        self.memory.add_command(CommandSource::parse(""));
+                        self.selected_index = Some(self.memory.commands.len().saturating_sub(1));
+                    }
+                    Some(Modal::Alert) => {
+                        self.memory.add_alert(AlertRule::parse(""));
+                        self.selected_index = Some(self.memory.alerts.len().saturating_sub(1));
+                    }
+                    Some(Modal::Profile) => {
+                        // Новый профиль: сразу вводим имя
+                        self.profiles.push(String::new());
+                        self.selected_index = Some(self.profiles.len() - 1);
+                        self.edit_buffer.clear();
+                        self.editing_mode = true;
+                    }
+                    // Закладки ставятся с главного экрана
+                    Some(Modal::Bookmark) | None => {}
                 }
             }
             KeyCode::Char('d') => {
@@ -324,9 +503,74 @@ impl App {
                             }
                         }
                     }
+                    Some(Modal::Command) => {
+                        if let Some(index) = self.selected_index
+                            && index < self.memory.commands.len()
+                        {
+                            let _ = self.memory.remove_command(index);
+                            self.selected_index = match self.memory.commands.len() {
+                                0 => None,
+                                len => Some(index.min(len - 1)),
+                            };
+                        }
+                    }
+                    Some(Modal::Alert) => {
+                        if let Some(index) = self.selected_index
+                            && self.memory.remove_alert(index).is_ok()
+                        {
+                            self.selected_index = match self.memory.alerts.len() {
+                                0 => None,
+                                len => Some(index.min(len - 1)),
+                            };
+                        }
+                    }
+                    Some(Modal::Profile) => {
+                        // Активный профиль не удаляется: его память сохранится снова при выходе
+                        if let Some(index) = self.selected_index
+                            && let Some(name) = self.profiles.get(index).cloned()
+                        {
+                            if name == profile::active() {
+                                self.profile_status = Some("Switch to another profile before deleting this one".to_string());
+                            } else if let Err(e) = profile::delete(&name) {
+                                self.profile_status = Some(format!("Profile {}: {}", name, e));
+                            } else {
+                                self.profiles.remove(index);
+                                self.selected_index = match self.profiles.len() {
+                                    0 => None,
+                                    len => Some(index.min(len - 1)),
+                                };
+                            }
+                        }
+                    }
+                    Some(Modal::Bookmark) => {
+                        if let Some(index) = self.selected_index
+                            && self.memory.remove_bookmark(index).is_ok()
+                        {
+                            self.selected_index = match self.memory.bookmarks.len() {
+                                0 => None,
+                                len => Some(index.min(len - 1)),
+                            };
+                        }
+                    }
                     None => {}
                 }
             }
+            KeyCode::Char('e') => {
+                // Переключаем кодировку выбранного пути
+                if let (Some(Modal::Path), Some(index)) = (&self.cur_modal, self.selected_index)
+                    && let Some(path) = self.memory.paths.get_mut(index)
+                {
+                    path.encoding = path.encoding.next();
+                }
+            }
+            KeyCode::Char('t') => {
+                // Переключаем формат выбранного пути (обычный, Docker, CRI)
+                if let (Some(Modal::Path), Some(index)) = (&self.cur_modal, self.selected_index)
+                    && let Some(path) = self.memory.paths.get_mut(index)
+                {
+                    path.format = path.format.next();
+                }
+            }
             KeyCode::Up => {
                 // Move selection up in the list
                 match self.cur_modal {
@@ -346,6 +590,31 @@ impl App {
                             };
                         }
                     }
+                    Some(Modal::Command) if !self.memory.commands.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(0) | None => Some(self.memory.commands.len() - 1),
+                            Some(i) => Some(i - 1),
+                        };
+                    }
+                    Some(Modal::Alert) if !self.memory.alerts.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(0) | None => Some(self.memory.alerts.len() - 1),
+                            Some(i) => Some(i - 1),
+                        };
+                    }
+                    Some(Modal::Bookmark) if !self.memory.bookmarks.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(0) | None => Some(self.memory.bookmarks.len() - 1),
+                            Some(i) => Some(i - 1),
+                        };
+                    }
+                    Some(Modal::Profile) if !self.profiles.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(0) | None => Some(self.profiles.len() - 1),
+                            Some(i) => Some(i - 1),
+                        };
+                    }
+                    Some(Modal::Command) | Some(Modal::Alert) | Some(Modal::Bookmark) | Some(Modal::Profile) => {}
                     None => {}
                 }
             }
@@ -370,6 +639,35 @@ impl App {
                             };
                         }
                     }
+                    Some(Modal::Command) if !self.memory.commands.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(i) if i >= self.memory.commands.len() - 1 => Some(0),
+                            Some(i) => Some(i + 1),
+                            None => Some(0),
+                        };
+                    }
+                    Some(Modal::Alert) if !self.memory.alerts.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(i) if i >= self.memory.alerts.len() - 1 => Some(0),
+                            Some(i) => Some(i + 1),
+                            None => Some(0),
+                        };
+                    }
+                    Some(Modal::Bookmark) if !self.memory.bookmarks.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(i) if i >= self.memory.bookmarks.len() - 1 => Some(0),
+                            Some(i) => Some(i + 1),
+                            None => Some(0),
+                        };
+                    }
+                    Some(Modal::Profile) if !self.profiles.is_empty() => {
+                        self.selected_index = match self.selected_index {
+                            Some(i) if i >= self.profiles.len() - 1 => Some(0),
+                            Some(i) => Some(i + 1),
+                            None => Some(0),
+                        };
+                    }
+                    Some(Modal::Command) | Some(Modal::Alert) | Some(Modal::Bookmark) | Some(Modal::Profile) => {}
                     None => {}
                 }
             }
@@ -384,6 +682,34 @@ impl App {
                                 self.editing_mode = true;
                             }
                         }
+                        Modal::Command => {
+                            if index < self.memory.commands.len() {
+                                self.edit_buffer = self.memory.commands[index].to_input();
+                                self.editing_mode = true;
+                            }
+                        }
+                        Modal::Alert => {
+                            if let Some(alert) = self.memory.alerts.get(index) {
+                                self.edit_buffer = alert.to_input();
+                                self.editing_mode = true;
+                            }
+                        }
+                        Modal::Bookmark => {
+                            if let Some(bookmark) = self.memory.bookmarks.get(index) {
+                                self.edit_buffer = bookmark.note.clone();
+                                self.editing_mode = true;
+                            }
+                        }
+                        Modal::Profile => {
+                            if let Some(name) = self.profiles.get(index).cloned()
+                                && profile::is_valid(&name)
+                            {
+                                self.switch_profile(&name);
+                                self.cur_screen = Screen::Main;
+                                self.cur_modal = None;
+                                self.selected_index = None;
+                            }
+                        }
                         Modal::Filter => {
                             if index < self.memory.filters.len() {
                                 // Set edit buffer based on filter type
@@ -398,6 +724,14 @@ impl App {
                                         self.filter_type = FilterType::Regex;
                                         self.editing_mode = true;
                                     },
+                                    crate::common::enums::Filter::Field(f) => {
+                                        self.edit_buffer = match f.value.is_empty() {
+                                            true => f.name.clone(),
+                                            false => format!("{}={}", f.name, f.value),
+                                        };
+                                        self.filter_type = FilterType::Field;
+                                        self.editing_mode = true;
+                                    },
                                     crate::common::enums::Filter::Date(f) => {
                                         // Установим значения для датового фильтра
                                         self.filter_type = FilterType::Date;
@@ -452,12 +786,32 @@ impl App {
                     }
                 }
             }
+            KeyCode::Char('g') if self.cur_modal == Some(Modal::Bookmark) => {
+                // Перейти к строке выбранной закладки
+                if let Some(bookmark) = self.selected_index.and_then(|i| self.memory.bookmarks.get(i)) {
+                    self.jump_requested = Some(bookmark.clone());
+                    let _ = self.memory.save();
+                    self.cur_screen = Screen::Main;
+                    self.cur_modal = None;
+                    self.selected_index = None;
+                }
+            }
+            KeyCode::Char('x') if self.cur_modal == Some(Modal::Bookmark) => {
+                self.export_timeline();
+            }
+            KeyCode::Char('4')
+                if self.cur_modal == Some(Modal::Filter) && self.filter_type != FilterType::Date =>
+            {
+                // Switch to Field filter type
+                self.filter_type = FilterType::Field;
+            }
             _ => {}
         }
     }
     pub fn handle_exit(&mut self, key: KeyCode) {
         match key {
             KeyCode::Char('y') => {
+                self.save_view();
                 self.exit_approved = true;
             }
             _ => {
@@ -466,6 +820,18 @@ impl App {
         }
     }
     pub fn handle_main(&mut self, key: KeyCode) {
+        // Вторая клавиша после b или ' — буква закладки
+        if let Some(prefix) = self.bookmark_key.take() {
+            if let KeyCode::Char(mark) = key
+                && mark.is_ascii_lowercase()
+            {
+                match prefix {
+                    'b' => self.add_bookmark(mark),
+                    _ => self.request_jump(mark),
+                }
+            }
+            return;
+        }
         match key {
             KeyCode::Char('q') => {
                 self.cur_screen = Screen::Exit;
@@ -478,6 +844,14 @@ impl App {
                 self.cur_screen = Screen::Additional;
                 self.cur_modal = Some(Modal::Filter);
             }
+            KeyCode::Char('c') => {
+                self.cur_screen = Screen::Additional;
+                self.cur_modal = Some(Modal::Command);
+            }
+            KeyCode::Char('a') => {
+                self.cur_screen = Screen::Additional;
+                self.cur_modal = Some(Modal::Alert);
+            }
             KeyCode::Char('o') => {
                 self.cur_order = match self.cur_order {
                     Order::OrderByDate => Order::OrderByDateReverse,
@@ -488,6 +862,86 @@ impl App {
                     self.needs_refresh = true;
                 }
             }
+            KeyCode::Char('i') => {
+                // Собрать полнотекстовый индекс по текущим путям
+                if !self.index_progress.running.load(std::sync::atomic::Ordering::Relaxed) {
+                    self.index_requested = true;
+                }
+            }
+            KeyCode::Char('s') => {
+                // Статистика по текущим путям и фильтрам считается заново при каждом открытии
+                self.cur_screen = Screen::Stats;
+                self.request_stats();
+            }
+            KeyCode::Char('d') => {
+                self.dedup = self.dedup.next();
+                self.dedup_runs.clear();
+                self.dedup_expanded.clear();
+                self.collapse_logs();
+            }
+            KeyCode::Up if self.dedup != Dedup::Off => {
+                self.dedup_selected = self.dedup_selected.saturating_sub(1);
+            }
+            KeyCode::Down if self.dedup != Dedup::Off => {
+                let last = self.visible_runs().len().saturating_sub(1);
+                self.dedup_selected = (self.dedup_selected + 1).min(last);
+            }
+            KeyCode::Up => {
+                self.line_selected = self.line_selected.saturating_sub(1);
+            }
+            KeyCode::Down => {
+                let last = self.visible_lines().len().saturating_sub(1);
+                self.line_selected = (self.line_selected + 1).min(last);
+            }
+            KeyCode::Char(prefix @ ('b' | '\'')) => {
+                // Закладка: b + буква ставит (m уже переключает режим), ' + буква переходит
+                self.bookmark_key = Some(prefix);
+            }
+            KeyCode::Char('w') => {
+                self.profiles = profile::list();
+                self.selected_index = self.profiles.iter().position(|p| *p == profile::active());
+                self.cur_screen = Screen::Additional;
+                self.cur_modal = Some(Modal::Profile);
+            }
+            KeyCode::Char('B') => {
+                self.cur_screen = Screen::Additional;
+                self.cur_modal = Some(Modal::Bookmark);
+            }
+            KeyCode::Char('e') if self.dedup != Dedup::Off => {
+                // Развернуть или свернуть выбранные повторы
+                if let Some(start) = self.visible_runs().get(self.dedup_selected).map(|r| r.start)
+                    && !self.dedup_expanded.remove(&start)
+                {
+                    self.dedup_expanded.insert(start);
+                }
+            }
+            KeyCode::Char('t') => {
+                // Шаблоны сообщений: в Page Mode разбираются все отфильтрованные строки, в хвосте — пришедшие
+                self.cur_screen = Screen::Clusters;
+                self.cluster_selected = 0;
+                if self.cur_mode == Mode::Page {
+                    self.request_clusters();
+                }
+            }
+            KeyCode::Char('r') => {
+                // Временно, до выхода: в памяти остаётся настройка по умолчанию
+                self.redact = !self.redact;
+            }
+            KeyCode::Char('x') => {
+                // Выгрузка в файл; во время выгрузки та же клавиша её отменяет
+                if self.export_progress.running.load(std::sync::atomic::Ordering::Relaxed) {
+                    self.export_progress
+                        .cancelled
+                        .store(true, std::sync::atomic::Ordering::Relaxed);
+                } else {
+                    self.export_buffer = format!(
+                        "logscraper-{}.{}",
+                        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+                        self.export_format.extension()
+                    );
+                    self.cur_screen = Screen::Export;
+                }
+            }
             KeyCode::Char('m') => {
                 self.cur_mode = match self.cur_mode {
                     Mode::Page => Mode::Tail,
@@ -498,6 +952,10 @@ impl App {
                 if self.cur_mode == Mode::Page {
                     self.needs_refresh = true;
                 }
+                // Хвост начинается заново — и его шаблоны тоже
+                if self.cur_mode == Mode::Tail {
+                    self.tail_clusters = Drain::default();
+                }
             }
             KeyCode::Enter => {
                 // Reload logs based on current mode:
@@ -564,16 +1022,350 @@ impl App {
             }
         }
     }
+    pub fn handle_export(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Enter if !self.export_buffer.is_empty() => {
+                self.export_requested = Some(self.export_request());
+                self.cur_screen = Screen::Main;
+            }
+            KeyCode::Esc => {
+                self.cur_screen = Screen::Main;
+            }
+            KeyCode::Tab => {
+                // Расширение файла следует за форматом, если его не меняли руками
+                let old_extension = format!(".{}", self.export_format.extension());
+                self.export_format = self.export_format.next();
+                if let Some(stem) = self.export_buffer.strip_suffix(&old_extension) {
+                    self.export_buffer = format!("{}.{}", stem, self.export_format.extension());
+                }
+            }
+            KeyCode::Up | KeyCode::Down => {
+                self.export_all = !self.export_all;
+            }
+            KeyCode::Backspace => {
+                self.export_buffer.pop();
+            }
+            KeyCode::Char(c) => {
+                self.export_buffer.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    // Строка такой, какой её показывать: с маской, если маскирование включено
+    pub fn shown(&self, line: &str) -> String {
+        match self.redact {
+            true => self.redactor.redact(line),
+            false => line.to_string(),
+        }
+    }
+
+    // Страница Page Mode перечитывается по тому же запросу, что и экран, но с источниками строк.
+    // В Tail Mode страница — то, что сейчас в буфере хвоста.
+    fn export_request(&self) -> ExportRequest {
+        let selection = match (&self.cur_mode, self.export_all) {
+            (Mode::Page, _) | (_, true) => {
+                let (offset, limit) = match self.export_all {
+                    true => (0, usize::MAX),
+                    false => (self.cur_page.saturating_sub(1) * self.cur_size, self.cur_size),
+                };
+                Selection::Query(Query {
+                    paths: self.memory.paths.clone(),
+                    agents: self.memory.agents.clone(),
+                    filters: self.memory.filters.clone(),
+                    order: self.cur_order.clone(),
+                    offset,
+                    limit,
+                })
+            }
+            _ => Selection::Lines(self.logs.clone()),
+        };
+        ExportRequest {
+            selection,
+            format: self.export_format,
+            output: self.export_buffer.clone(),
+            redactor: self.redact.then(|| self.redactor.clone()),
+        }
+    }
+
+    pub fn handle_stats(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('b') => {
+                self.stats_bucket = self.stats_bucket.next();
+            }
+            KeyCode::Char('r') => {
+                self.request_stats();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.cur_screen = Screen::Main;
+            }
+            _ => {}
+        }
+    }
+
+    fn request_stats(&mut self) {
+        if !self.stats.running.load(std::sync::atomic::Ordering::Relaxed) {
+            self.stats_requested = true;
+        }
+    }
+
+    pub fn handle_clusters(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => {
+                self.cluster_selected = self.cluster_selected.saturating_sub(1);
+            }
+            KeyCode::Down => {
+                let count = self.with_clusters(|drain| drain.map_or(0, |d| d.sorted().len()));
+                self.cluster_selected = (self.cluster_selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Enter => {
+                // Выбранный шаблон становится фильтром
+                let selected = self.cluster_selected;
+                let filter = self.with_clusters(|drain| {
+                    drain.and_then(|d| d.sorted().get(selected).map(|c| c.filter()))
+                });
+                if let Some(filter) = filter {
+                    self.add_filter(filter);
+                    self.cur_screen = Screen::Main;
+                }
+            }
+            KeyCode::Char('r') if self.cur_mode == Mode::Page => {
+                self.request_clusters();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.cur_screen = Screen::Main;
+            }
+            _ => {}
+        }
+    }
+
+    fn request_clusters(&mut self) {
+        if !self.clusters.running.load(std::sync::atomic::Ordering::Relaxed) {
+            self.clusters_requested = true;
+        }
+    }
+
+    // Шаблоны для экрана: в Page Mode — фонового разбора, в хвосте — пришедших строк
+    pub fn with_clusters<R>(&self, f: impl FnOnce(Option<&Drain>) -> R) -> R {
+        match self.cur_mode {
+            Mode::Page => f(self.clusters.result.lock().unwrap().as_ref()),
+            Mode::Tail | Mode::Stopped => f(Some(&self.tail_clusters)),
+        }
+    }
+
+    fn add_filter(&mut self, filter: Filter) {
+        self.memory.add_filter(filter);
+        let _ = self.memory.save();
+        self.cur_page = 1;
+        self.needs_refresh = true;
+    }
+
     pub fn handle(&mut self, key: KeyCode) {
         match self.cur_screen {
             Screen::Main => self.handle_main(key),
             Screen::Additional => self.handle_additional(key),
             Screen::Exit => self.handle_exit(key),
+            Screen::Export => self.handle_export(key),
+            Screen::Stats => self.handle_stats(key),
+            Screen::Clusters => self.handle_clusters(key),
         }
     }
     pub fn update_logs(&mut self) {
         while let Ok(log) = self.rx.try_recv() {
-            self.logs.push(log);
+            self.tail_clusters.add(&log.data, Some(log.date_time));
+            self.logs.push(log.data);
+            // У строк хвоста мест нет
+            self.places.clear();
+        }
+        self.collapse_logs();
+    }
+
+    // Новая страница Page Mode: свёртки считаются заново
+    pub fn set_logs(&mut self, logs: Vec<String>) {
+        self.logs = logs;
+        self.places.clear();
+        self.dedup_runs.clear();
+        self.dedup_expanded.clear();
+        self.collapse_logs();
+        // Свёртка, где оказалась выбранная строка, например после перехода к закладке
+        self.dedup_selected = self
+            .dedup_runs
+            .iter()
+            .position(|r| r.start <= self.line_selected && self.line_selected < r.end())
+            .unwrap_or(0);
+    }
+
+    // Страница вместе с местами строк. После перехода к закладке выбирается её строка,
+    // если файл между поиском и чтением страницы не изменился
+    pub fn set_page(&mut self, page: Vec<PageLine>) {
+        let (logs, places): (Vec<String>, Vec<Option<Place>>) =
+            page.into_iter().map(|line| (line.line, line.place)).unzip();
+        if let Some(bookmark) = self.jump_target.take() {
+            let found = places.iter().position(|place| {
+                place.as_ref().is_some_and(|place| {
+                    Some(&place.path) == bookmark.path.as_ref()
+                        && Some(place.offset) == bookmark.offset
+                })
+            });
+            match found {
+                Some(line) => self.line_selected = line,
+                None => {
+                    self.bookmark_status =
+                        Some(format!("Bookmark {} is not on page {}", bookmark.mark, self.cur_page))
+                }
+            }
+        }
+        self.set_logs(logs);
+        self.places = places;
+    }
+
+    // Сворачивает строки, пришедшие после последней свёртки
+    fn collapse_logs(&mut self) {
+        if self.dedup == Dedup::Off {
+            return;
+        }
+        let from = self.dedup_runs.last().map_or(0, Run::end);
+        dedup::collapse(&mut self.dedup_runs, &self.logs, from, self.dedup);
+    }
+
+    // Строки на экране: в хвосте — последние cur_size
+    pub fn visible_lines(&self) -> &[String] {
+        match self.cur_mode {
+            Mode::Tail => &self.logs[self.logs.len().saturating_sub(self.cur_size)..],
+            Mode::Page | Mode::Stopped => &self.logs,
+        }
+    }
+
+    // Номер выбранной строки в app.logs
+    fn selected_log(&self) -> Option<usize> {
+        let index = match self.dedup {
+            Dedup::Off => self.logs.len() - self.visible_lines().len() + self.line_selected,
+            _ => self.visible_runs().get(self.dedup_selected)?.start,
+        };
+        (index < self.logs.len()).then_some(index)
+    }
+
+    // Закладка на выбранную строку; место в источнике берётся у прочитанной страницы
+    fn add_bookmark(&mut self, mark: char) {
+        let Some(index) = self.selected_log() else {
+            self.bookmark_status = Some("No line to bookmark".to_string());
+            return;
+        };
+        let line = self.logs[index].clone();
+        let place = self.places.get(index).cloned().flatten();
+        let source = place.as_ref().and_then(|place| {
+            self.memory.paths.iter().find(|path| path.path == place.path)
+        });
+        // У строк хвоста без своего времени берём время прихода
+        let time = fields::timestamp(&line)
+            .or_else(|| (self.cur_mode != Mode::Page).then(chrono::Utc::now));
+        self.memory.set_bookmark(Bookmark {
+            mark,
+            source: source.map_or("-".to_string(), |path| path.name.clone()),
+            path: place.as_ref().map(|place| place.path.clone()),
+            offset: place.map(|place| place.offset),
+            time,
+            note: String::new(),
+            line,
+        });
+        let _ = self.memory.save();
+        self.bookmark_status = Some(format!("Bookmark {} set", mark));
+    }
+
+    fn request_jump(&mut self, mark: char) {
+        // Номер строки считается только по своим файлам, а страница смешивает их со строками
+        // агентов: переход открыл бы не ту страницу
+        if !self.memory.agents.is_empty() {
+            self.bookmark_status = Some(format!("Bookmark {}: jump is not supported with agents", mark));
+            return;
+        }
+        match self.memory.bookmarks.iter().find(|b| b.mark == mark) {
+            Some(bookmark) => self.jump_requested = Some(bookmark.clone()),
+            None => self.bookmark_status = Some(format!("No bookmark {}", mark)),
+        }
+    }
+
+    // Страница и строка закладки по её номеру в выдаче Page Mode с текущими фильтрами и порядком
+    pub fn jump_to(&mut self, bookmark: Bookmark, position: Result<usize, String>) {
+        match position {
+            Ok(position) => {
+                self.cur_mode = Mode::Page;
+                self.cur_page = position / self.cur_size + 1;
+                self.line_selected = position % self.cur_size;
+                self.needs_refresh = true;
+                self.bookmark_status =
+                    Some(format!("Bookmark {}: page {}", bookmark.mark, self.cur_page));
+                self.jump_target = Some(bookmark);
+            }
+            Err(e) => {
+                self.bookmark_status = Some(format!("Bookmark {}: {}", bookmark.mark, e));
+            }
+        }
+    }
+
+    // Порядок, размер страницы и режим уходят в профиль вместе с остальной памятью
+    fn save_view(&mut self) {
+        self.memory.view = View {
+            order: self.cur_order.clone(),
+            page_size: self.cur_size,
+            mode: self.cur_mode.clone(),
+        };
+        let _ = self.memory.save();
+    }
+
+    // Текущий профиль сохраняется, другой загружается; stdin и --agent этого запуска остаются
+    fn switch_profile(&mut self, name: &str) {
+        if name == profile::active() {
+            return;
+        }
+        self.save_view();
+        profile::select(name);
+        let mut memory = match Memory::load() {
+            Ok(memory) => memory,
+            Err(e) => {
+                self.profile_status = Some(format!("Profile {}: {}", name, e));
+                Memory::default()
+            }
+        };
+        memory
+            .paths
+            .extend(self.memory.paths.iter().filter(|p| p.path == STDIN_PATH).cloned());
+        memory
+            .agents
+            .extend(self.memory.agents.iter().filter(|a| a.session).cloned());
+
+        let mut app = App::new(std::mem::replace(&mut self.rx, unbounded_channel().1), memory);
+        // Общее для всего запуска, не для профиля
+        app.command_status = self.command_status.clone();
+        app.throughput = self.throughput.clone();
+        app.alert_status = self.alert_status.clone();
+        app.bells_rung = self.bells_rung;
+        app.index_progress = self.index_progress.clone();
+        app.export_progress = self.export_progress.clone();
+        app.profile_status = self.profile_status.take();
+        app.profile_switched = true;
+        *self = app;
+    }
+
+    // Хронология закладок в файл рядом; строки маскируются, как на экране
+    fn export_timeline(&mut self) {
+        let output = format!(
+            "logscraper-timeline-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let redactor = self.redact.then_some(self.redactor.as_ref());
+        let timeline = bookmark::timeline(&self.memory.bookmarks, redactor);
+        self.bookmark_status = Some(match std::fs::write(&output, timeline) {
+            Ok(()) => format!("Timeline saved to {}", output),
+            Err(e) => format!("Timeline {}: {}", output, e),
+        });
+    }
+
+    // Свёртки на экране: в хвосте — последние cur_size, как и строки без свёртки
+    pub fn visible_runs(&self) -> &[Run] {
+        match self.cur_mode {
+            Mode::Tail => &self.dedup_runs[self.dedup_runs.len().saturating_sub(self.cur_size)..],
+            Mode::Page | Mode::Stopped => &self.dedup_runs,
         }
     }
 
@@ -588,13 +1380,16 @@ pub enum Screen {
     Main,
     Additional,
     Exit,
+    Export,
+    Stats,
+    Clusters,
 }
 
 impl Screen {
     pub fn keys_hint(&self) -> Span<'_> {
         match self {
             Screen::Main => Span::styled(
-                "(q) - quit / (f/p) - add [filter/path] / (o/m) - change [order/mode]",
+                "(q) - quit / (f/p/c/a) - add [filter/path/command/alert] / (o/m) - change [order/mode] / (i) - index / (s) - stats / (t) - templates / (d) - dedup / (b/') + letter - set/jump bookmark / (B) - bookmarks / (w) - profiles / (r) - redact / (x) - export",
                 Style::default().fg(Color::Red),
             ),
             Screen::Additional => {
@@ -604,6 +1399,18 @@ impl Screen {
                 "(y) - quit / (Any) - back to Main ",
                 Style::default().fg(Color::Red),
             ),
+            Screen::Export => Span::styled(
+                "(Enter) - export / (Tab) - format / (Up/Down) - page or all / (Esc) - back to Main",
+                Style::default().fg(Color::Red),
+            ),
+            Screen::Stats => Span::styled(
+                "(q) - back to Main / (b) - minutes or hours / (r) - recount",
+                Style::default().fg(Color::Red),
+            ),
+            Screen::Clusters => Span::styled(
+                "(q) - back to Main / (Up/Down) - select / (Enter) - filter by template / (r) - recount",
+                Style::default().fg(Color::Red),
+            ),
         }
     }
 }
@@ -619,6 +1426,10 @@ pub enum DateField {
 pub enum Modal {
     Filter,
     Path,
+    Command,
+    Alert,
+    Bookmark,
+    Profile,
 }
 
 impl Modal {
@@ -626,6 +1437,10 @@ impl Modal {
         match self {
             Modal::Filter => "Create a new filter".to_string(),
             Modal::Path => "Create a new path".to_string(),
+            Modal::Command => "Create a new command (name: command)".to_string(),
+            Modal::Alert => "Create a new alert rule".to_string(),
+            Modal::Bookmark => "Bookmarks".to_string(),
+            Modal::Profile => "Profiles".to_string(),
         }
     }
 }
@@ -1,4 +1,6 @@
-use std::io;
+use std::{future::Future, io};
+
+use futures_util::FutureExt;
 
 use ratatui::{
     Terminal,
@@ -6,15 +8,18 @@ use ratatui::{
     prelude::Backend,
 };
 
-use crate::reader::file::read_from_paths;
+use crate::remote::client::read_from_sources;
 
 pub mod app;
 pub mod ui;
 
 use tokio::task;
 
-pub async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut app::App, tx: tokio::sync::mpsc::UnboundedSender<String>) -> io::Result<bool> {
+// shutdown — SIGTERM/SIGINT: TUI выходит тем же путём, что и по клавише
+pub async fn run_app<B: Backend + io::Write>(terminal: &mut Terminal<B>, app: &mut app::App, tx: tokio::sync::mpsc::UnboundedSender<crate::common::structs::Log>, shutdown: impl Future<Output = ()>) -> io::Result<bool> {
     let mut tail_handle: Option<task::JoinHandle<()>> = None;
+    let mut page_handle: Option<task::JoinHandle<()>> = None;
+    tokio::pin!(shutdown);
     
     loop {
         terminal.draw(|frame| ui::ui(frame, app))?;
@@ -24,37 +29,173 @@ pub async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut app::App,
                     continue;
                 }
                 app.handle(key.code);
+
+                // Сборка полнотекстового индекса идёт в отдельном потоке
+                if app.index_requested {
+                    app.index_requested = false;
+                    let sources = app.memory.paths.clone();
+                    let progress = app.index_progress.clone();
+                    progress.running.store(true, std::sync::atomic::Ordering::Relaxed);
+                    std::thread::spawn(move || crate::index::text::build_all(sources, progress));
+                }
                 
-                // При переключении в Tail Mode, запускаем процесс хвоста
-                if app.cur_mode == crate::common::enums::Mode::Tail && tail_handle.is_none() {
-                    let memory = app.memory.clone();
-                    let tx_clone = tx.clone();
-                    tail_handle = Some(task::spawn(async move {
-                        if let Err(e) = crate::reader::tail::tail_stream(memory, tx_clone).await {
-                            eprintln!("Error in tail stream: {}", e);
+                // Статистика считается в отдельном потоке, как и индекс
+                if app.stats_requested {
+                    app.stats_requested = false;
+                    let sources = app.memory.paths.clone();
+                    let filters = app.memory.filters.clone();
+                    let stats = app.stats.clone();
+                    stats.running.store(true, std::sync::atomic::Ordering::Relaxed);
+                    std::thread::spawn(move || {
+                        match crate::stats::collect(&sources, &filters, &stats) {
+                            Ok(result) => {
+                                *stats.result.lock().unwrap() = Some(result);
+                                *stats.error.lock().unwrap() = None;
+                            }
+                            Err(e) => *stats.error.lock().unwrap() = Some(e.to_string()),
                         }
-                    }));
+                        stats.running.store(false, std::sync::atomic::Ordering::Relaxed);
+                    });
                 }
-                
-                // При выходе из Tail Mode, останавливаем процесс хвоста
-                if app.cur_mode != crate::common::enums::Mode::Tail && tail_handle.is_some() {
-                    if let Some(handle) = tail_handle.take() {
-                        handle.abort();
-                    }
+
+                // Шаблоны сообщений Page Mode разбираются в отдельном потоке, как и статистика
+                if app.clusters_requested {
+                    app.clusters_requested = false;
+                    let sources = app.memory.paths.clone();
+                    let filters = app.memory.filters.clone();
+                    let clusters = app.clusters.clone();
+                    clusters.running.store(true, std::sync::atomic::Ordering::Relaxed);
+                    std::thread::spawn(move || {
+                        match crate::cluster::collect(&sources, &filters, &clusters) {
+                            Ok(result) => {
+                                *clusters.result.lock().unwrap() = Some(result);
+                                *clusters.error.lock().unwrap() = None;
+                            }
+                            Err(e) => *clusters.error.lock().unwrap() = Some(e.to_string()),
+                        }
+                        clusters.running.store(false, std::sync::atomic::Ordering::Relaxed);
+                    });
                 }
-                
+
+                // Выгрузка идёт фоном, итог показывается в статусе
+                if let Some(request) = app.export_requested.take() {
+                    let progress = app.export_progress.clone();
+                    progress.start();
+                    let output = request.output.clone();
+                    task::spawn(async move {
+                        let result = crate::export::export(request, progress.clone())
+                            .await
+                            .map_err(|e| e.to_string());
+                        match result {
+                            Ok(Some(count)) => {
+                                *progress.saved.lock().unwrap() =
+                                    Some(format!("Exported {} lines to {}", count, output));
+                            }
+                            Ok(None) => {}
+                            Err(e) => *progress.error.lock().unwrap() = Some(e),
+                        }
+                        progress.running.store(false, std::sync::atomic::Ordering::Relaxed);
+                    });
+                }
+
+                // Переход к закладке: номер её строки в выдаче Page Mode считается фоном,
+                // одним проходом по источникам с текущими фильтрами и порядком
+                if let Some(bookmark) = app.jump_requested.take() {
+                    app.bookmark_status = Some(format!("Bookmark {}: locating...", bookmark.mark));
+                    let sources = app.memory.paths.clone();
+                    let filters = Some(app.memory.filters.clone());
+                    let order = app.cur_order.clone();
+                    let jump_result = app.jump_result.clone();
+                    task::spawn_blocking(move || {
+                        let position = crate::bookmark::position(&sources, &filters, &order, &bookmark);
+                        *jump_result.lock().unwrap() = Some((bookmark, position));
+                    });
+                }
+
                 if app.exit_approved {
                     // Останавливаем хвост, если он запущен, перед выходом
-                    if let Some(handle) = tail_handle.take() {
-                        handle.abort();
-                    }
+                    stop_tasks(tail_handle.take(), page_handle.take()).await;
                     return Ok(true);
                 }
             }
         }
+
+        // `kill <pid>`: без этого группы процессов команд-источников переживут TUI
+        if (&mut shutdown).now_or_never().is_some() {
+            stop_tasks(tail_handle.take(), page_handle.take()).await;
+            return Ok(true);
+        }
         
+        // Другой профиль — другие источники: хвост перезапускается с новой памятью
+        if app.profile_switched {
+            app.profile_switched = false;
+            if let Some(handle) = tail_handle.take() {
+                handle.abort();
+            }
+        }
+
+        // При переключении в Tail Mode, запускаем процесс хвоста; профиль может и начинаться с него
+        if app.cur_mode == crate::common::enums::Mode::Tail && tail_handle.is_none() {
+            let memory = app.memory.clone();
+            let tx_clone = tx.clone();
+            let command_status = app.command_status.clone();
+            let throughput = app.throughput.clone();
+            let alert_status = app.alert_status.clone();
+            // stderr занят интерфейсом: ошибку показываем в строке состояния, как ошибку страницы
+            let tail_error = app.tail_error.clone();
+            *tail_error.lock().unwrap() = None;
+            let listener_status = app.listener_status.clone();
+            listener_status.clear();
+            tail_handle = Some(task::spawn(async move {
+                if let Err(e) = crate::reader::tail::tail_stream(memory, tx_clone, command_status, throughput, alert_status, listener_status).await {
+                    *tail_error.lock().unwrap() = Some(e.to_string());
+                }
+            }));
+        }
+
+        // При выходе из Tail Mode, останавливаем процесс хвоста
+        if app.cur_mode != crate::common::enums::Mode::Tail && tail_handle.is_some() {
+            if let Some(handle) = tail_handle.take() {
+                handle.abort();
+            }
+        }
+
         // Обновляем логи из канала
         app.update_logs();
+
+        // Фоновая загрузка страницы закончилась; ответ на прежний запрос или после выхода
+        // из Page Mode не показываем
+        let page = app.page_result.lock().unwrap().take();
+        if let Some((request, page)) = page
+            && request == app.page_request
+        {
+            app.page_loading = false;
+            if app.cur_mode == crate::common::enums::Mode::Page {
+                match page {
+                    Ok(page) => {
+                        app.page_error = None;
+                        app.set_page(page);
+                    }
+                    Err(e) => app.page_error = Some(e),
+                }
+            }
+        }
+
+        // Фоновый поиск строки закладки закончился: переходим на её страницу
+        let jump = app.jump_result.lock().unwrap().take();
+        if let Some((bookmark, position)) = jump {
+            app.jump_to(bookmark, position);
+        }
+
+        // Правило оповещения попросило звонок: BEL в терминал, по разу на срабатывание.
+        // Пишем туда же, где рисуется интерфейс: stdout может быть перенаправлен
+        let bells = app.alert_status.bells.load(std::sync::atomic::Ordering::Relaxed);
+        if bells > app.bells_rung {
+            app.bells_rung = bells;
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            io::Write::flush(backend)?;
+        }
         
         // Проверяем изменения, требующие обновления логов
         if app.cur_mode == crate::common::enums::Mode::Page {
@@ -83,21 +224,140 @@ pub async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut app::App,
             // Вычисляем смещение на основе страницы и размера
             let offset = (app.cur_page.saturating_sub(1)) * app.cur_size;
             
-            // Читаем логи из всех путей с заданным порядком
-            match read_from_paths(
-                app.memory.paths.clone(),
-                app.cur_size,
-                offset,
-                Some(app.memory.filters.clone()),
-                app.cur_order.clone(),
-            ).await {
-                Ok(logs) => {
-                    app.logs = logs;
-                }
-                Err(e) => {
-                    eprintln!("Error reading logs: {}", e);
-                }
+            // Читаем логи из всех путей с заданным порядком. Агенты могут отвечать долго,
+            // поэтому страница читается фоном, как статистика, и интерфейс не замирает
+            app.page_request += 1;
+            app.page_loading = true;
+            let request = app.page_request;
+            let paths = app.memory.page_paths();
+            let agents = app.memory.agents.clone();
+            let size = app.cur_size;
+            let filters = Some(app.memory.filters.clone());
+            let order = app.cur_order.clone();
+            let page_result = app.page_result.clone();
+            if let Some(handle) = page_handle.take() {
+                handle.abort();
             }
+            page_handle = Some(task::spawn(async move {
+                let page = read_from_sources(paths, &agents, size, offset, filters, order)
+                    .await
+                    .map_err(|e| e.to_string());
+                *page_result.lock().unwrap() = Some((request, page));
+            }));
+        }
+    }
+}
+
+// Хвост не только отменяем, но и дожидаемся: вместе с ним снимаются задачи команд-источников,
+// и их guard'ы убивают группы процессов раньше, чем main завершит процесс
+async fn stop_tasks(tail: Option<task::JoinHandle<()>>, page: Option<task::JoinHandle<()>>) {
+    if let Some(handle) = page {
+        handle.abort();
+    }
+    if let Some(handle) = tail {
+        handle.abort();
+        let _ = handle.await;
+    }
+}
+
+// Тесты смотрят на процессы через /proc
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use crate::alert::AlertStatus;
+    use crate::cli::follow::shutdown_signal;
+    use crate::common::structs::{CommandSource, Log, Memory};
+    use crate::reader::{
+        command::CommandStatus,
+        tail::{ListenerStatus, tail_stream},
+        throughput::Throughput,
+    };
+
+    use super::*;
+
+    // Живые (не зомби) процессы группы, по /proc/<pid>/stat
+    fn group_alive(pgid: i32) -> bool {
+        std::fs::read_dir("/proc").unwrap().flatten().any(|entry| {
+            let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+                return false;
+            };
+            // После имени в скобках: состояние, ppid, pgrp
+            let Some((_, rest)) = stat.rsplit_once(") ") else {
+                return false;
+            };
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            fields.len() > 2 && fields[0] != "Z" && fields[2] == pgid.to_string()
+        })
+    }
+
+    // Ставится у копии тестового бинарника, которой шлём SIGTERM
+    const SIGTERM_CHILD: &str = "LOGSCRAPER_TEST_SIGTERM_CHILD";
+
+    // SIGTERM уходит всему процессу, поэтому тест перезапускает себя отдельным процессом
+    // и не задевает соседние тесты
+    #[test]
+    fn test_sigterm_stops_command_groups() {
+        if std::env::var_os(SIGTERM_CHILD).is_some() {
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(sigterm_stops_command_groups());
+            return;
         }
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "tui::test::test_sigterm_stops_command_groups",
+                "--nocapture",
+            ])
+            .env(SIGTERM_CHILD, "1")
+            .status()
+            .expect("Не удалось запустить тест отдельным процессом");
+        assert!(status.success());
+    }
+
+    async fn sigterm_stops_command_groups() {
+        let (tx, mut rx) = unbounded_channel::<Log>();
+        let mut memory = Memory::default();
+        // Внук sh в той же группе: его убивает только killpg из guard'а
+        memory.commands.push(CommandSource {
+            name: "test".to_string(),
+            command: "sleep 1000 & echo $$; wait".to_string(),
+        });
+        let tail = task::spawn(async move {
+            let _ = tail_stream(
+                memory,
+                tx,
+                Arc::new(CommandStatus::default()),
+                Arc::new(Throughput::default()),
+                Arc::new(AlertStatus::default()),
+                Arc::new(ListenerStatus::default()),
+            )
+            .await;
+        });
+        let log = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("Команда не прислала pid")
+            .unwrap();
+        let pgid: i32 = log.data.parse().expect("Ожидался pid");
+        assert!(group_alive(pgid));
+
+        // Первый опрос ставит обработчик сигнала, и SIGTERM не убивает тестовый процесс
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        assert!((&mut shutdown).now_or_never().is_none());
+        // Safety: kill только отправляет сигнал своему процессу — отдельной копии теста
+        unsafe {
+            libc::kill(libc::getpid(), libc::SIGTERM);
+        }
+        tokio::time::timeout(Duration::from_secs(2), &mut shutdown)
+            .await
+            .expect("SIGTERM не дошёл");
+
+        stop_tasks(Some(tail), None).await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!group_alive(pgid));
     }
 }
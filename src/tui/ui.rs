@@ -3,13 +3,25 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Borders, Clear, List, ListItem, Paragraph, Sparkline,
+    },
 };
 
+use std::{collections::HashSet, sync::atomic::Ordering};
+
 const TITLE: &str = "LogScraper on Rust by Devputat";
+// Сколько источников помещается в панель скорости хвоста, кроме общей строки
+const RATE_SOURCES: usize = 4;
 
 use crate::{
-    common::enums::Mode,
+    common::{
+        constants::CLUSTER_SAMPLES,
+        enums::{Dedup, Encoding, LogFormat, Mode},
+        profile,
+    },
+    index::lines,
+    reader::{command::CommandState, throughput::{RateSnapshot, Throughput}},
     tui::app::{self, App},
 };
 
@@ -26,17 +38,26 @@ pub fn ui(frame: &mut Frame, app: &App) {
         .borders(Borders::ALL)
         .style(Style::default());
     let title = Paragraph::new(Text::styled(
-        TITLE.to_string(),
+        format!("{} | profile {}", TITLE, profile::active()),
         Style::default().fg(ratatui::style::Color::Green),
     ))
     .block(title_block);
     frame.render_widget(title, chunks[0]);
 
-    let mode_footer = Paragraph::new(Line::from(vec![
+    let mut mode_spans = vec![
         app.cur_mode.nav_text(),
         Span::styled(" | ", Style::default().fg(Color::DarkGray)),
         app.cur_order.order_text(),
-    ]))
+    ];
+    let profile_status = app
+        .profile_status
+        .clone()
+        .map(|e| Span::styled(e, Style::default().fg(Color::Red)));
+    for status in [profile_status, page_status(app), tail_status(app), redact_status(app), redact_error_status(app), bookmark_status(app), alert_status(app), dedup_status(app), index_status(app), export_status(app)].into_iter().flatten() {
+        mode_spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+        mode_spans.push(status);
+    }
+    let mode_footer = Paragraph::new(Line::from(mode_spans))
     .block(Block::default().borders(Borders::ALL));
     let hint_footer = Paragraph::new(Line::from(app.cur_screen.keys_hint()))
         .block(Block::default().borders(Borders::ALL));
@@ -47,26 +68,42 @@ pub fn ui(frame: &mut Frame, app: &App) {
     frame.render_widget(mode_footer, footer_chunks[0]);
     frame.render_widget(hint_footer, footer_chunks[1]);
 
-    let display_logs = match app.cur_mode {
-        Mode::Page => {
-            // app.logs уже содержит логи для текущей страницы, 
-            // загруженные с правильным offset в run_app
-            &app.logs
-        }
-        Mode::Tail => {
-            let start = app.logs.len().saturating_sub(app.cur_size);
-            &app.logs[start..]
-        }
-        Mode::Stopped => &app.logs,
+    // В Page Mode app.logs уже содержит страницу, загруженную с нужным offset в run_app
+    let display_logs = app.visible_lines();
+    // Строки, на которых сработало правило с highlight
+    let highlighted = app.alert_status.highlighted();
+    let text = match app.dedup {
+        Dedup::Off => Text::from(
+            display_logs
+                .iter()
+                .enumerate()
+                .map(|(i, l)| {
+                    let style = match (i == app.line_selected, highlighted.contains(l)) {
+                        (true, _) => Style::default().bg(Color::DarkGray),
+                        (false, true) => alert_style(),
+                        (false, false) => Style::default(),
+                    };
+                    Line::styled(format!("{}{}", marks(app, l), app.shown(l)), style)
+                })
+                .collect::<Vec<Line>>(),
+        ),
+        _ => dedup_text(app, &highlighted),
     };
-    let text = Text::from(
-        display_logs
-            .iter()
-            .map(|l| Line::from(l.clone()))
-            .collect::<Vec<Line>>(),
-    );
     let log_block = Paragraph::new(text).block(Block::default().borders(Borders::ALL));
-    frame.render_widget(log_block, chunks[1]);
+    if matches!(app.cur_screen, app::Screen::Stats) {
+        stats_view(frame, app, chunks[1]);
+    } else if matches!(app.cur_screen, app::Screen::Clusters) {
+        clusters_view(frame, app, chunks[1]);
+    } else if app.cur_mode == Mode::Tail {
+        let log_area = tail_rates(frame, app, chunks[1]);
+        frame.render_widget(log_block, log_area);
+    } else {
+        frame.render_widget(log_block, chunks[1]);
+    }
+
+    if matches!(app.cur_screen, app::Screen::Export) {
+        export_dialog(frame, app);
+    }
 
     if let Some(modal) = &app.cur_modal {
         let area = centered_rect(60, 25, frame.area());
@@ -109,10 +146,60 @@ pub fn ui(frame: &mut Frame, app: &App) {
                         } else {
                             Style::default().fg(Color::White)
                         };
-                        ListItem::new(Line::from(Span::styled(
-                            format!("{}: {}", i, path.path),
+                        let mut text = format!("{}: {}", i, path.path);
+                        if path.name != path.path {
+                            text = format!("{}: {} ({})", i, path.name, path.path);
+                        }
+                        if path.encoding != Encoding::default() {
+                            text = format!("{} [{}]", text, path.encoding.label());
+                        }
+                        if path.format != LogFormat::default() {
+                            text = format!("{} [{}]", text, path.format.label());
+                        }
+                        ListItem::new(Line::from(Span::styled(text, style)))
+                    })
+                    .collect();
+
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .style(Style::default().bg(Color::DarkGray)),
+                    )
+                    .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+
+                frame.render_widget(Clear, popup_chunks[1]);
+                frame.render_widget(list, popup_chunks[1]);
+            }
+            app::Modal::Command => {
+                // Команды и их состояние в Tail Mode (код выхода, перезапуск)
+                let items: Vec<ListItem> = app
+                    .memory
+                    .commands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, command)| {
+                        let is_selected = app.selected_index == Some(i);
+                        let style = if is_selected {
+                            Style::default().bg(Color::Blue).fg(Color::White)
+                        } else {
+                            Style::default().fg(Color::White)
+                        };
+                        let mut spans = vec![Span::styled(
+                            format!("{}: {}", i, command.to_input()),
                             style,
-                        )))
+                        )];
+                        if let Some(state) = app.command_status.get(&command.name) {
+                            let color = match state {
+                                CommandState::Running => Color::Green,
+                                _ => Color::Yellow,
+                            };
+                            spans.push(Span::styled(
+                                format!(" [{}]", state),
+                                Style::default().fg(color),
+                            ));
+                        }
+                        ListItem::new(Line::from(spans))
                     })
                     .collect();
 
@@ -127,6 +214,106 @@ pub fn ui(frame: &mut Frame, app: &App) {
                 frame.render_widget(Clear, popup_chunks[1]);
                 frame.render_widget(list, popup_chunks[1]);
             }
+            app::Modal::Alert => {
+                // Правила и когда каждое срабатывало последний раз
+                let items: Vec<ListItem> = app
+                    .memory
+                    .alerts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, alert)| {
+                        let style = if app.selected_index == Some(i) {
+                            Style::default().bg(Color::Blue).fg(Color::White)
+                        } else {
+                            Style::default().fg(Color::White)
+                        };
+                        let mut spans = vec![Span::styled(format!("{}: {}", i, alert.to_input()), style)];
+                        if let Some(fired) = app.alert_status.last(&alert.name) {
+                            spans.push(Span::styled(
+                                format!(" [fired {} in {}]", fired.time.format("%H:%M:%S"), fired.source),
+                                Style::default().fg(Color::Red),
+                            ));
+                        }
+                        ListItem::new(Line::from(spans))
+                    })
+                    .collect();
+
+                let list = List::new(items).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(Style::default().bg(Color::DarkGray)),
+                );
+
+                frame.render_widget(Clear, popup_chunks[1]);
+                frame.render_widget(list, popup_chunks[1]);
+            }
+            app::Modal::Profile => {
+                // Профили из каталога настроек; активный отмечен звёздочкой
+                let active = profile::active();
+                let items: Vec<ListItem> = app
+                    .profiles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        let style = if app.selected_index == Some(i) {
+                            Style::default().bg(Color::Blue).fg(Color::White)
+                        } else {
+                            Style::default().fg(Color::White)
+                        };
+                        let marker = if *name == active { "* " } else { "  " };
+                        ListItem::new(Line::from(Span::styled(format!("{}{}", marker, name), style)))
+                    })
+                    .collect();
+
+                let list = List::new(items).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(Style::default().bg(Color::DarkGray)),
+                );
+
+                frame.render_widget(Clear, popup_chunks[1]);
+                frame.render_widget(list, popup_chunks[1]);
+            }
+            app::Modal::Bookmark => {
+                // Закладки по буквам: время строки, источник, заметка и сама строка
+                let items: Vec<ListItem> = app
+                    .memory
+                    .bookmarks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, bookmark)| {
+                        let style = if app.selected_index == Some(i) {
+                            Style::default().bg(Color::Blue).fg(Color::White)
+                        } else {
+                            Style::default().fg(Color::White)
+                        };
+                        let time = bookmark.time.map_or("-".to_string(), |t| {
+                            t.with_timezone(&chrono::Local).format("%m-%d %H:%M:%S").to_string()
+                        });
+                        let mut spans = vec![Span::styled(
+                            format!("[{}] {} {} ", bookmark.mark, time, bookmark.source),
+                            style,
+                        )];
+                        if !bookmark.note.is_empty() {
+                            spans.push(Span::styled(
+                                format!("{} ", bookmark.note),
+                                Style::default().fg(Color::Yellow),
+                            ));
+                        }
+                        spans.push(Span::styled(app.shown(&bookmark.line), Style::default().fg(Color::Gray)));
+                        ListItem::new(Line::from(spans))
+                    })
+                    .collect();
+
+                let list = List::new(items).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(Style::default().bg(Color::DarkGray)),
+                );
+
+                frame.render_widget(Clear, popup_chunks[1]);
+                frame.render_widget(list, popup_chunks[1]);
+            }
             app::Modal::Filter => {
                 // Display list of filters
                 let items: Vec<ListItem> = app
@@ -149,6 +336,9 @@ pub fn ui(frame: &mut Frame, app: &App) {
                             crate::common::enums::Filter::Regex(f) => {
                                 format!("{}: Regex '{}'", i, f.pattern)
                             }
+                            crate::common::enums::Filter::Field(f) => {
+                                format!("{}: Field '{}={}'", i, f.name, f.value)
+                            }
                             crate::common::enums::Filter::Date(f) => {
                                 let date_filter_type_str = match &f.filter_type {
                                     crate::common::structs::DateFilterType::Before => "Before",
@@ -187,7 +377,7 @@ pub fn ui(frame: &mut Frame, app: &App) {
 
         // Show editing interface or instructions
         let instructions = if app.editing_mode {
-            if app.filter_type == app::FilterType::Date {
+            if app.filter_type == app::FilterType::Date && *modal == app::Modal::Filter {
                 // Специальное отображение для датового фильтра
                 let field_name = match app.editing_date_field {
                     app::DateField::Start => "Start date",
@@ -211,7 +401,14 @@ pub fn ui(frame: &mut Frame, app: &App) {
             }
         } else {
             match modal {
-                app::Modal::Path => "Use arrow keys to select, Enter to edit, 'a' to add, 'd' to delete, 'q' to quit".to_string(),
+                app::Modal::Command => "Use arrow keys to select, Enter to edit as 'name: command', 'a' to add, 'd' to delete, 'q' to quit | Commands run in Tail Mode".to_string(),
+                app::Modal::Alert => "Use arrow keys to select, Enter to edit as 'name=oom match=REGEX source=api count=3 window=1m cooldown=5m bell highlight file=alerts.log run=./notify.sh webhook=https://host/hook', 'a' to add, 'd' to delete, 'q' to quit | Rules run in Tail Mode".to_string(),
+                app::Modal::Profile => format!(
+                    "Use arrow keys to select, Enter to switch, 'a' to add, 'd' to delete, 'q' to quit | Stored in {}",
+                    profile::dir().display()
+                ),
+                app::Modal::Bookmark => "Use arrow keys to select, Enter to edit the note, 'g' to jump, 'd' to delete, 'x' to export a timeline, 'q' to quit | b + letter sets a bookmark, ' + letter jumps to it".to_string(),
+                app::Modal::Path => "Use arrow keys to select, Enter to edit, 'a' to add, 'd' to delete, 'e' to change encoding, 't' to change format, 'q' to quit".to_string(),
                 app::Modal::Filter => {
                     if app.filter_type == app::FilterType::Date {
                         format!(
@@ -220,7 +417,7 @@ pub fn ui(frame: &mut Frame, app: &App) {
                         )
                     } else {
                         format!(
-                            "Use arrow keys to select, Enter to edit, 'a' to add, 'd' to delete, 'q' to quit | Filter type: 1-Search, 2-Regex, 3-Date (current: {:?})", 
+                            "Use arrow keys to select, Enter to edit, 'a' to add, 'd' to delete, 'q' to quit | Filter type: 1-Search, 2-Regex, 3-Date, 4-Field name=value (current: {:?})", 
                             app.filter_type
                         )
                     }
@@ -237,6 +434,481 @@ pub fn ui(frame: &mut Frame, app: &App) {
     }
 }
 
+fn page_status(app: &App) -> Option<Span<'static>> {
+    if app.cur_mode != Mode::Page {
+        return None;
+    }
+    if app.page_loading {
+        return Some(Span::styled("Loading page...", Style::default().fg(Color::Yellow)));
+    }
+    app.page_error
+        .as_ref()
+        .map(|e| Span::styled(format!("Page error: {}", e), Style::default().fg(Color::Red)))
+}
+
+fn tail_status(app: &App) -> Option<Span<'static>> {
+    if app.cur_mode != Mode::Tail {
+        return None;
+    }
+    let mut errors: Vec<String> = app.tail_error.lock().unwrap().iter().cloned().collect();
+    // Упавший приёмник не останавливает хвост, поэтому его ошибка показывается рядом с именем
+    errors.extend(
+        app.listener_status
+            .failed()
+            .into_iter()
+            .map(|(listener, e)| format!("{}: {}", listener, e)),
+    );
+    if errors.is_empty() {
+        return None;
+    }
+    Some(Span::styled(
+        format!("Tail error: {}", errors.join("; ")),
+        Style::default().fg(Color::Red),
+    ))
+}
+
+fn alert_status(app: &App) -> Option<Span<'static>> {
+    if let Some(e) = app.alert_status.error() {
+        return Some(Span::styled(format!("Alert error: {}", e), Style::default().fg(Color::Red)));
+    }
+    app.alert_status.latest().map(|fired| {
+        Span::styled(
+            format!(
+                "Alert {}: {}x in {} at {}",
+                fired.rule,
+                fired.count,
+                fired.source,
+                fired.time.with_timezone(&chrono::Local).format("%H:%M:%S")
+            ),
+            Style::default().fg(Color::Red),
+        )
+    })
+}
+
+// Буквы закладок перед строкой, на которую они указывают
+fn marks(app: &App, line: &str) -> String {
+    app.memory
+        .bookmarks
+        .iter()
+        .filter(|b| b.line == line)
+        .map(|b| format!("[{}] ", b.mark))
+        .collect()
+}
+
+fn bookmark_status(app: &App) -> Option<Span<'static>> {
+    let prompt = match app.bookmark_key {
+        Some('b') => Some("Bookmark: press a letter to set".to_string()),
+        Some(_) => Some("Bookmark: press a letter to jump".to_string()),
+        None => None,
+    };
+    prompt
+        .or_else(|| app.bookmark_status.clone())
+        .map(|text| Span::styled(text, Style::default().fg(Color::LightCyan)))
+}
+
+// Видно всегда, когда маскирование включено или временно снято с включённого по умолчанию
+fn redact_status(app: &App) -> Option<Span<'static>> {
+    match (app.redact, app.memory.redaction.enabled) {
+        (true, _) => Some(Span::styled("REDACTED (r - reveal)", Style::default().fg(Color::Black).bg(Color::Green))),
+        (false, true) => Some(Span::styled("UNREDACTED (r - redact)", Style::default().fg(Color::Black).bg(Color::Yellow))),
+        (false, false) => None,
+    }
+}
+
+// Неверное правило пропускается, остальные работают: ошибка показывается рядом с REDACTED
+fn redact_error_status(app: &App) -> Option<Span<'static>> {
+    app.redactor
+        .error
+        .as_ref()
+        .map(|e| Span::styled(format!("Redact error: {}", e), Style::default().fg(Color::Red)))
+}
+
+fn dedup_status(app: &App) -> Option<Span<'static>> {
+    match app.dedup {
+        Dedup::Off => None,
+        mode => Some(Span::styled(
+            format!("Dedup {} (Up/Down, e - expand)", mode.label()),
+            Style::default().fg(Color::Cyan),
+        )),
+    }
+}
+
+// Повторы свёрнуты в одну строку с ×N и временем первого и последнего; развёрнутые — все вхождения ниже
+fn alert_style() -> Style {
+    Style::default().fg(Color::White).bg(Color::Red)
+}
+
+fn dedup_text(app: &App, highlighted: &HashSet<String>) -> Text<'static> {
+    let time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+        t.map_or("-".to_string(), |t| t.format("%H:%M:%S").to_string())
+    };
+    let mut lines = Vec::new();
+    for (i, run) in app.visible_runs().iter().enumerate() {
+        let line = &app.logs[run.start];
+        let mut spans = vec![Span::raw(format!("{}{}", marks(app, line), app.shown(line)))];
+        if run.count > 1 {
+            spans.push(Span::styled(format!("  ×{}", run.count), Style::default().fg(Color::Yellow)));
+            spans.push(Span::styled(
+                format!(" {} .. {}", time(run.first), time(run.last)),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        let style = match (i == app.dedup_selected, highlighted.contains(&app.logs[run.start])) {
+            (true, _) => Style::default().bg(Color::DarkGray),
+            (false, true) => alert_style(),
+            (false, false) => Style::default(),
+        };
+        lines.push(Line::from(spans).style(style));
+        if app.dedup_expanded.contains(&run.start) {
+            lines.extend(
+                app.logs[run.start..run.end()]
+                    .iter()
+                    .map(|line| Line::from(format!("  │ {}", app.shown(line)))),
+            );
+        }
+    }
+    Text::from(lines)
+}
+
+fn index_status(app: &App) -> Option<Span<'static>> {
+    let progress = &app.index_progress;
+    if progress.running.load(Ordering::Relaxed) {
+        let current = progress.current.lock().unwrap().clone();
+        return Some(Span::styled(
+            format!("Indexing {}% {}", progress.percent(), current),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let error = progress.error.lock().unwrap().clone();
+    error
+        .or_else(lines::build_error)
+        .map(|e| Span::styled(format!("Index error: {}", e), Style::default().fg(Color::Red)))
+}
+
+// Сводка, счётчики по источникам и уровням, гистограмма по времени
+fn stats_view(frame: &mut Frame, app: &App, area: Rect) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(6),
+            Constraint::Length(8),
+        ])
+        .split(area);
+    let result = app.stats.result.lock().unwrap();
+    let running = app.stats.running.load(Ordering::Relaxed);
+
+    let mut summary = Vec::new();
+    if running {
+        summary.push(Line::from(Span::styled(
+            format!("Counting... {} lines", app.stats.lines.load(Ordering::Relaxed)),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else if let Some(e) = app.stats.error.lock().unwrap().as_ref() {
+        summary.push(Line::from(Span::styled(
+            format!("Stats error: {}", e),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    let Some(stats) = result.as_ref() else {
+        let block =
+            Paragraph::new(summary).block(Block::default().title("Stats").borders(Borders::ALL));
+        frame.render_widget(block, area);
+        return;
+    };
+    let time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+        t.map_or("N/A".to_string(), |t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+    };
+    summary.push(Line::from(format!("Matching records: {}", stats.total)));
+    summary.push(Line::from(format!(
+        "First: {}  Last: {}",
+        time(stats.first),
+        time(stats.last)
+    )));
+    let block = Paragraph::new(summary).block(
+        Block::default()
+            .title("Stats for current paths and filters")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(block, rows[0]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+    let mut sources: Vec<(&String, &u64)> = stats.by_source.iter().collect();
+    sources.sort_by(|a, b| b.1.cmp(a.1));
+    let items: Vec<ListItem> = sources
+        .iter()
+        .map(|(name, count)| ListItem::new(format!("{:>10}  {}", count, name)))
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::default().title("By source").borders(Borders::ALL)),
+        columns[0],
+    );
+    let bars: Vec<Bar> = stats
+        .by_level
+        .iter()
+        .map(|(level, count)| {
+            Bar::default()
+                .label(Line::from(level.clone()))
+                .value(*count)
+                .style(Style::default().fg(level_color(level)))
+        })
+        .collect();
+    let levels = BarChart::default()
+        .block(Block::default().title("By level").borders(Borders::ALL))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(6)
+        .bar_gap(1);
+    frame.render_widget(levels, columns[1]);
+
+    // Столбцов столько, сколько помещается в ширину: видны последние минуты или часы
+    let histogram = stats.histogram(app.stats_bucket, rows[2].width.saturating_sub(2) as usize);
+    let title = match (histogram.first(), histogram.last()) {
+        (Some((from, _)), Some((to, _))) => format!(
+            "Records per {} {} .. {}",
+            app.stats_bucket.label(),
+            from.format("%Y-%m-%d %H:%M"),
+            to.format("%Y-%m-%d %H:%M")
+        ),
+        _ => format!("Records per {}", app.stats_bucket.label()),
+    };
+    let counts: Vec<u64> = histogram.iter().map(|(_, count)| *count).collect();
+    let sparkline = Sparkline::default()
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .data(&counts)
+        .style(Style::default().fg(Color::Green));
+    frame.render_widget(sparkline, rows[2]);
+}
+
+// Шаблоны сообщений по частоте, снизу — примеры строк выбранного шаблона
+fn clusters_view(frame: &mut Frame, app: &App, area: Rect) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(4),
+            Constraint::Length(CLUSTER_SAMPLES as u16 + 2),
+        ])
+        .split(area);
+    let mut summary = Vec::new();
+    if app.cur_mode == Mode::Page && app.clusters.running.load(Ordering::Relaxed) {
+        summary.push(Span::styled(
+            format!("Mining templates... {} lines ", app.clusters.lines.load(Ordering::Relaxed)),
+            Style::default().fg(Color::Yellow),
+        ));
+    } else if app.cur_mode == Mode::Page && let Some(e) = app.clusters.error.lock().unwrap().as_ref() {
+        summary.push(Span::styled(format!("Templates error: {} ", e), Style::default().fg(Color::Red)));
+    }
+
+    app.with_clusters(|drain| {
+        let clusters = drain.map(|d| d.sorted()).unwrap_or_default();
+        if let Some(drain) = drain {
+            // Сколько шаблонов видно в списке — столько и в сводке
+            let top = clusters.len().min(rows[1].height.saturating_sub(2) as usize);
+            summary.push(Span::raw(format!(
+                "{} templates from {} lines, top {} cover {:.1}%",
+                clusters.len(),
+                drain.total,
+                top,
+                drain.coverage(top)
+            )));
+        }
+        let source = match app.cur_mode {
+            Mode::Page => "Templates for current paths and filters",
+            _ => "Templates of tail lines",
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(summary)).block(Block::default().title(source).borders(Borders::ALL)),
+            rows[0],
+        );
+
+        let time = |t: Option<chrono::DateTime<chrono::Utc>>| {
+            t.map_or("-".to_string(), |t| t.format("%m-%d %H:%M:%S").to_string())
+        };
+        let total = drain.map_or(0, |d| d.total).max(1);
+        // Список прокручивается так, чтобы выбранный шаблон был виден
+        let visible = rows[1].height.saturating_sub(2) as usize;
+        let skip = (app.cluster_selected + 1).saturating_sub(visible);
+        let items: Vec<ListItem> = clusters
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(i, cluster)| {
+                let style = match i == app.cluster_selected {
+                    true => Style::default().fg(Color::Black).bg(Color::Yellow),
+                    false => Style::default(),
+                };
+                ListItem::new(format!(
+                    "{:>8} {:>5.1}%  {} .. {}  {}",
+                    cluster.count,
+                    cluster.count as f64 * 100.0 / total as f64,
+                    time(cluster.first),
+                    time(cluster.last),
+                    app.shown(&cluster.text())
+                ))
+                .style(style)
+            })
+            .collect();
+        frame.render_widget(
+            List::new(items).block(
+                Block::default()
+                    .title("Count  Share  First .. Last  Template")
+                    .borders(Borders::ALL),
+            ),
+            rows[1],
+        );
+
+        let samples: Vec<Line> = clusters
+            .get(app.cluster_selected)
+            .map(|c| c.samples.iter().map(|s| Line::from(app.shown(s))).collect())
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(samples).block(Block::default().title("Samples").borders(Borders::ALL)),
+            rows[2],
+        );
+    });
+}
+
+// Панель над хвостом: строк в секунду за последнюю минуту, показано из принятого
+// и давность последней строки — всё и самые занятые источники. Возвращает место под логи
+fn tail_rates(frame: &mut Frame, app: &App, area: Rect) -> Rect {
+    let now = chrono::Utc::now();
+    let mut sources = app.throughput.snapshot(now);
+    let overall = Throughput::overall(&sources);
+    sources.sort_by_key(|s| std::cmp::Reverse(s.rate.iter().sum::<u64>()));
+    let hidden = sources.len().saturating_sub(RATE_SOURCES);
+    // Один источник совпадает с общей строкой
+    let shown = match sources.len() {
+        0 | 1 => &[][..],
+        len => &sources[..len - hidden],
+    };
+
+    let title = match hidden {
+        0 => "Tail rate, lines/s".to_string(),
+        _ => format!("Tail rate, lines/s (+{} quieter sources)", hidden),
+    };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(shown.len() as u16 + 3), Constraint::Min(1)])
+        .split(area);
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(rows[0]);
+    frame.render_widget(block, rows[0]);
+
+    let lines = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); shown.len() + 1])
+        .split(inner);
+    for (snapshot, line) in std::iter::once(&overall).chain(shown).zip(lines.iter()) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(60), Constraint::Min(1)])
+            .split(*line);
+        frame.render_widget(Paragraph::new(rate_line(snapshot, now)), columns[0]);
+        // Последняя секунда ещё идёт — она справа и растёт на глазах
+        let sparkline = Sparkline::default()
+            .data(&snapshot.rate[snapshot.rate.len().saturating_sub(columns[1].width as usize)..])
+            .style(Style::default().fg(Color::Green));
+        frame.render_widget(sparkline, columns[1]);
+    }
+    rows[1]
+}
+
+fn rate_line(snapshot: &RateSnapshot, now: chrono::DateTime<chrono::Utc>) -> Line<'static> {
+    // Скорость за последнюю закончившуюся секунду
+    let rate = snapshot.rate.iter().rev().nth(1).copied().unwrap_or(0);
+    let (ago, color) = match snapshot.last {
+        Some(last) => {
+            let seconds = (now - last).num_seconds().max(0);
+            let color = match seconds {
+                0..10 => Color::Green,
+                10..60 => Color::Yellow,
+                _ => Color::Red,
+            };
+            (format!("last {}s ago", seconds), color)
+        }
+        None => ("no lines yet".to_string(), Color::DarkGray),
+    };
+    let mut name = snapshot.name.clone();
+    if name.chars().count() > 16 {
+        name = name.chars().take(15).chain(['…']).collect();
+    }
+    let mut spans = vec![
+        Span::raw(format!("{:<16} {:>6}/s ", name, rate)),
+        Span::styled(
+            format!("{}/{} shown ", snapshot.passed, snapshot.total),
+            Style::default().fg(Color::Cyan),
+        ),
+    ];
+    if snapshot.dropped > 0 {
+        spans.push(Span::styled(
+            format!("{} dropped ", snapshot.dropped),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    spans.push(Span::styled(ago, Style::default().fg(color)));
+    Line::from(spans)
+}
+
+fn level_color(level: &str) -> Color {
+    match level {
+        "fatal" => Color::Magenta,
+        "error" => Color::Red,
+        "warn" => Color::Yellow,
+        "info" => Color::Green,
+        "debug" | "trace" => Color::Blue,
+        _ => Color::Gray,
+    }
+}
+
+fn export_status(app: &App) -> Option<Span<'static>> {
+    let progress = &app.export_progress;
+    if progress.running.load(Ordering::Relaxed) {
+        let current = progress.current.lock().unwrap().clone();
+        return Some(Span::styled(
+            format!("Exporting {} {} (x - cancel)", progress.text(), current),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(e) = progress.error.lock().unwrap().as_ref() {
+        return Some(Span::styled(
+            format!("Export error: {}", e),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    progress
+        .saved
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|saved| Span::styled(saved.clone(), Style::default().fg(Color::Green)))
+}
+
+fn export_dialog(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 25, frame.area());
+    let scope = match (app.export_all, &app.cur_mode) {
+        (true, _) => "all filtered lines",
+        (false, Mode::Page) => "current page",
+        (false, _) => "lines on screen",
+    };
+    let text = Text::from(vec![
+        Line::from(format!("File: {}", app.export_buffer)),
+        Line::from(format!("Format: {} (Tab)", app.export_format.label())),
+        Line::from(format!("Lines: {} (Up/Down)", scope)),
+        Line::from("Enter to export, Esc to cancel"),
+    ]);
+    let dialog = Paragraph::new(text).block(
+        Block::default()
+            .title("Export")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popout_layout = Layout::default()
         .direction(Direction::Vertical)